@@ -0,0 +1,96 @@
+//! Vazão de `try_set_reading`/`try_set_writing`/`remove_*` num recurso só,
+//! com uma thread e com várias disputando. Cada aquisição é um compare-and-swap
+//! nos contadores atômicos do recurso; leitores concorrentes não se serializam.
+//!
+//! `cargo bench` imprime ns/op e ops/s de cada cenário.
+//!
+//! O harness é feito à mão (`harness = false`) porque o criterion não está no registro
+//! offline em que o projeto é compilado, e o projeto evita dependências só para medir.
+//! Cada cenário roda uma vez, sem aquecimento nem estatística: ns/op é o tempo de parede
+//! do laço inteiro dividido pelas operações, e ops/s o inverso. Nos cenários disputados,
+//! as operações somam todas as threads e contam também as tentativas recusadas, então
+//! ns/op cai com mais núcleos mesmo que cada aquisição fique mais lenta. Para comparar
+//! duas versões, rode cada uma algumas vezes na mesma máquina (`cargo bench --bench
+//! acquire_release`) e olhe a tendência, não um número isolado.
+
+use std::hint::black_box;
+use std::sync::Barrier;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use macroquad::prelude::vec2;
+use threads::policy::ReaderPreferring;
+use threads::resource_box::Resource;
+
+const SINGLE_ITERATIONS: u32 = 2_000_000;
+const CONTENDED_ITERATIONS: u32 = 200_000;
+
+fn resource() -> Resource {
+    Resource::new("bench", vec2(0.0, 0.0), 10.0, 10.0, Box::new(ReaderPreferring))
+}
+
+fn report(name: &str, ops: u64, elapsed: Duration) {
+    let ns_per_op = elapsed.as_nanos() as f64 / ops as f64;
+    let ops_per_sec = ops as f64 / elapsed.as_secs_f64();
+    println!("{:<40} {:>10.1} ns/op {:>14.0} ops/s", name, ns_per_op, ops_per_sec);
+}
+
+fn single_threaded() {
+    let r = resource();
+    let start = Instant::now();
+    for _ in 0..SINGLE_ITERATIONS {
+        black_box(r.try_set_reading(0));
+        r.remove_reading(0);
+    }
+    report("1 thread: read acquire+release", SINGLE_ITERATIONS as u64, start.elapsed());
+
+    let start = Instant::now();
+    for _ in 0..SINGLE_ITERATIONS {
+        black_box(r.try_set_writing());
+        r.remove_writing();
+    }
+    report("1 thread: write acquire+release", SINGLE_ITERATIONS as u64, start.elapsed());
+}
+
+/// `readers` threads lendo e `writers` escrevendo no mesmo recurso ao mesmo tempo.
+fn contended(readers: usize, writers: usize) {
+    let r = resource();
+    let total = readers + writers;
+    let barrier = Barrier::new(total + 1);
+    let elapsed = thread::scope(|scope| {
+        for i in 0..total {
+            let (r, barrier) = (&r, &barrier);
+            let writing = i >= readers;
+            scope.spawn(move || {
+                barrier.wait();
+                for _ in 0..CONTENDED_ITERATIONS {
+                    if writing {
+                        if r.try_set_writing() {
+                            r.remove_writing();
+                        }
+                    } else if r.try_set_reading(i) {
+                        r.remove_reading(i);
+                    }
+                }
+            });
+        }
+        barrier.wait();
+        let start = Instant::now();
+        // Sair do escopo espera todas as threads
+        start
+    })
+    .elapsed();
+    report(
+        &format!("{} readers + {} writers (contended)", readers, writers),
+        CONTENDED_ITERATIONS as u64 * total as u64,
+        elapsed,
+    );
+}
+
+fn main() {
+    single_threaded();
+    let cores = thread::available_parallelism().map_or(4, |n| n.get());
+    contended(2, 0);
+    contended(cores, 0);
+    contended(cores.saturating_sub(1).max(1), 1);
+}
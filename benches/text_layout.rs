@@ -0,0 +1,113 @@
+//! Custo por frame do layout de texto (medir e quebrar os rótulos das threads e dos
+//! recursos), com e sem o cache de `text.rs`, ou seja, com e sem `--no-text-cache`.
+//!
+//! Sem janela não há `measure_text`: a medição é trocada (`set_measure`) por uma que
+//! faz o mesmo trabalho de CPU que a `Font::measure_text` do macroquad com os glifos
+//! já no atlas, isto é, por caractere dois locks no mapa de glifos e um no atlas.
+//! O desenho em si (`draw_text_ex`) não muda com o cache e fica de fora, então a
+//! diferença medida é o que o cache tira do frame.
+//!
+//! Harness à mão, como em `acquire_release.rs`: cada cenário roda uma vez, sem
+//! estatística; compare a tendência de algumas execuções na mesma máquina
+//! (`cargo bench --bench text_layout`).
+
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use threads::clock::MockClock;
+use threads::config::Config;
+use threads::simulation::{build_simulation, step_simulation};
+use threads::text::{set_measure, set_text_cache, text_width, wrapped_lines};
+
+const FRAMES: u32 = 2_000;
+/// Passo da simulação por frame, como a 60 fps
+const FRAME_TIME: f64 = 1.0 / 60.0;
+
+/// Glifos de um tamanho: avanço e sprite no atlas
+type Glyphs = Mutex<HashMap<(char, u16), (f32, u64)>>;
+/// Atlas: altura de cada sprite
+type Atlas = Mutex<HashMap<u64, f32>>;
+
+fn font() -> &'static (Glyphs, Atlas, Mutex<f32>) {
+    static FONT: OnceLock<(Glyphs, Atlas, Mutex<f32>)> = OnceLock::new();
+    FONT.get_or_init(|| (Mutex::new(HashMap::new()), Mutex::new(HashMap::new()), Mutex::new(1.0)))
+}
+
+/// O percurso da `Font::measure_text` do macroquad, sem rasterizar
+fn measure(text: &str, size: u16) -> f32 {
+    let (glyphs, atlas, dpi) = font();
+    let dpi = *dpi.lock().unwrap();
+    let mut width = 0.0;
+    let mut max_y = f32::MIN;
+    for ch in text.chars() {
+        if !glyphs.lock().unwrap().contains_key(&(ch, size)) {
+            cache_glyph(ch, size);
+        }
+        let (advance, sprite) = glyphs.lock().unwrap()[&(ch, size)];
+        let height = atlas.lock().unwrap()[&sprite];
+        width += advance;
+        max_y = max_y.max(height);
+    }
+    black_box(max_y);
+    width / dpi
+}
+
+/// Primeira vez do glifo: entra no atlas (o macroquad rasteriza aqui)
+fn cache_glyph(ch: char, size: u16) {
+    let (glyphs, atlas, _) = font();
+    let mut atlas = atlas.lock().unwrap();
+    let sprite = atlas.len() as u64;
+    atlas.insert(sprite, size as f32);
+    glyphs.lock().unwrap().insert((ch, size), (size as f32 * 0.5, sprite));
+}
+
+/// `threads` threads e `resources` recursos rodando; mede só o layout de cada frame.
+fn frames(threads: usize, resources: u32, cached: bool) -> Duration {
+    let config = Config::parse(["--threads", &threads.to_string(), "--resources", &resources.to_string()])
+        .expect("configuração do bench");
+    let (resource_box, mut threads_vis) = build_simulation(&config, &config.policy, Some(7));
+    let clock = MockClock::new(0.0);
+    threads_vis.clock = Arc::new(clock.clone());
+    set_text_cache(cached);
+
+    let mut elapsed = Duration::ZERO;
+    for frame in 1..=FRAMES {
+        let now = frame as f64 * FRAME_TIME;
+        clock.set(now);
+        step_simulation(&mut threads_vis, &resource_box, now, true);
+
+        let start = Instant::now();
+        for i in 0..threads_vis.threads.len() {
+            black_box(text_width(&(i + 1).to_string(), 18.0));
+            black_box(wrapped_lines(&threads_vis.display_label(i), 180.0, 200.0, 18.0));
+        }
+        for resource in &resource_box.resources {
+            black_box(wrapped_lines(&resource.display_label(now), 110.0, 70.0, 18.0));
+        }
+        elapsed += start.elapsed();
+    }
+    elapsed
+}
+
+fn report(threads: usize, resources: u32) {
+    let uncached = frames(threads, resources, false);
+    let cached = frames(threads, resources, true);
+    let per_frame = |d: Duration| d.as_secs_f64() * 1e6 / FRAMES as f64;
+    println!(
+        "{:>2} threads, {:>2} resources: {:>8.1} us/frame sem cache, {:>8.1} us/frame com cache ({:.1}x)",
+        threads,
+        resources,
+        per_frame(uncached),
+        per_frame(cached),
+        uncached.as_secs_f64() / cached.as_secs_f64(),
+    );
+}
+
+fn main() {
+    set_measure(Some(measure));
+    report(8, 4);
+    report(16, 10);
+    report(32, 20);
+}
@@ -0,0 +1,46 @@
+use macroquad::prelude::*;
+
+use crate::theme::Theme;
+
+/// Por quanto tempo (segundos) a borda pisca depois de o alarme disparar
+const FLASH_DURATION: f64 = 2.0;
+const FLASH_PERIOD: f64 = 0.25;
+
+/// Alarme visual de inanição: dispara só na transição para o estado ruim
+/// (e não a cada frame) e só volta a disparar depois que a condição passa.
+#[derive(Debug, Default)]
+pub struct Alarm {
+    active: bool,
+    triggered_at: Option<f64>,
+}
+
+impl Alarm {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Atualiza a condição; retorna `true` no frame em que o alarme dispara.
+    pub fn update(&mut self, bad: bool, now: f64) -> bool {
+        let triggered = bad && !self.active;
+        if triggered {
+            self.triggered_at = Some(now);
+        }
+        self.active = bad;
+        triggered
+    }
+
+    /// Pisca a borda da tela durante `FLASH_DURATION` depois do disparo.
+    pub fn draw(&self, theme: &Theme, now: f64) {
+        let Some(start) = self.triggered_at else {
+            return;
+        };
+        let elapsed = now - start;
+        if !(0.0..FLASH_DURATION).contains(&elapsed) {
+            return;
+        }
+        if ((elapsed / FLASH_PERIOD) as u64).is_multiple_of(2) {
+            let thickness = 12.0;
+            draw_rectangle_lines(0.0, 0.0, screen_width(), screen_height(), thickness, theme.alert);
+        }
+    }
+}
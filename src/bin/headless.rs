@@ -0,0 +1,143 @@
+//! Simulação sem janela: roda `--ticks N` passos fixos com a configuração de
+//! sempre (mesmas opções do `threads`) e imprime as estatísticas finais.
+//! Sai com código 1 se alguma regra de lock for violada, para servir de checagem
+//! no CI (`cargo run --bin headless -- --ticks 100000 --seed 7`).
+
+use std::process::ExitCode;
+
+use threads::chaos::Chaos;
+use threads::config::{Config, DEFAULT_STEPS_PER_SECOND};
+use threads::logger::{self, Level};
+use threads::scenario::Scenario;
+use threads::simulation::{build_simulation, step_simulation};
+use threads::stats::SessionStats;
+use threads::tick::{TickDriver, TickMode};
+
+/// Passos quando `--ticks` não é passado
+const DEFAULT_TICKS: u64 = 10_000;
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("erro: {}", err);
+            eprintln!("uso: headless [--ticks N] [opções do threads, sem --workers nem --compare]");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Roda a simulação e imprime o relatório. `Ok(false)` = houve violação.
+fn run() -> Result<bool, String> {
+    let (ticks, args) = split_ticks(std::env::args().skip(1))?;
+    let config = Config::parse(args)?;
+    if config.workers || config.compare.is_some() {
+        return Err("--workers e --compare precisam da janela".to_string());
+    }
+    let steps_per_second = match config.tick {
+        TickMode::FixedSteps { steps_per_second, .. } | TickMode::Manual { steps_per_second } => steps_per_second,
+        TickMode::RealTime => DEFAULT_STEPS_PER_SECOND,
+    };
+    // Um passo por "frame": a linha do tempo só depende do número de passos
+    let mode = TickMode::FixedSteps {
+        steps_per_second,
+        steps_per_frame: 1,
+    };
+    let seed = config.seed.unwrap_or_else(::rand::random);
+    println!("configuração: {}", config);
+    logger::init(config.log_level, config.log_file.as_deref())?;
+    println!("semente: {}, passos: {}", seed, ticks);
+
+    let (resource_box, mut threads_vis) = build_simulation(&config, &config.policy, Some(seed));
+    let mut driver = TickDriver::new(mode, 2.0, 0.0);
+    if let Some(clock) = driver.clock() {
+        threads_vis.clock = clock;
+    }
+    if let Some(name) = &config.scenario {
+        threads_vis.load_scenario(Scenario::load(name)?, 0.0);
+    }
+    if let Some(path) = &config.replay {
+        threads_vis.replay_from(path, &resource_box, 0.0)?;
+    }
+    if let Some(path) = &config.record {
+        threads_vis.record_to(path, &resource_box, 0.0)?;
+    }
+
+    let mut chaos = config
+        .chaos
+        .map(|interval| Chaos::new(interval, config.chaos_duration, Some(seed), 0.0));
+    let mut session = SessionStats::new();
+    let mut violations: Vec<String> = Vec::new();
+    for tick in 1..=ticks {
+        for (now, tick_due) in driver.steps(threads_vis.clock.now()) {
+            step_simulation(&mut threads_vis, &resource_box, now, tick_due);
+            if let Some(chaos) = &mut chaos {
+                for change in chaos.update(&threads_vis, &resource_box, now) {
+                    change.apply(&mut threads_vis, &resource_box, now);
+                }
+            }
+            session.observe(&threads_vis, &resource_box, now);
+        }
+        // Regras de lock e contadores contra as threads, a cada passo
+        let found = resource_box.validate().err().unwrap_or_default();
+        let drifts = threads_vis.reconcile(&resource_box);
+        for problem in found.into_iter().chain(drifts) {
+            let problem = format!("passo {}: {}", tick, problem);
+            logger::log(Level::Error, || problem.clone());
+            violations.push(problem);
+        }
+    }
+
+    let now = threads_vis.clock.now();
+    if let Some(chaos) = &mut chaos {
+        for change in chaos.stop() {
+            change.apply(&mut threads_vis, &resource_box, now);
+        }
+    }
+    let starving: Vec<&str> = threads_vis
+        .starving_threads(now, threads_vis.starvation_threshold)
+        .map(|i| threads_vis.threads[i].name.as_str())
+        .collect();
+    println!("tempo simulado: {:.1}s", now);
+    println!("aquisições: {}", threads_vis.throughput.total());
+    println!("tentativas bloqueadas: {}", threads_vis.failed_attempts);
+    if let (Some(max), Some(average)) = (threads_vis.latency.max(), threads_vis.latency.average()) {
+        println!("latência de aquisição: máx {:.2}s, média {:.2}s", max, average);
+    }
+    println!("pico de leitores: {}", session.peak_readers);
+    println!("deadlocks: {}", session.deadlocks);
+    match session.longest_wait {
+        Some((i, wait)) => println!("maior espera: {} ({:.1}s)", threads_vis.threads[i].name, wait),
+        None => println!("maior espera: nenhuma"),
+    }
+    if starving.is_empty() {
+        println!("em inanição no fim: nenhuma");
+    } else {
+        println!("em inanição no fim: {}", starving.join(", "));
+    }
+
+    for problem in threads_vis.shutdown(&resource_box) {
+        logger::log(Level::Error, || problem.clone());
+        violations.push(problem);
+    }
+    println!("violações: {}", violations.len());
+    Ok(violations.is_empty())
+}
+
+/// Tira `--ticks N` dos argumentos; o resto vai para `Config::parse`.
+fn split_ticks(mut args: impl Iterator<Item = String>) -> Result<(u64, Vec<String>), String> {
+    let mut ticks = DEFAULT_TICKS;
+    let mut rest = Vec::new();
+    while let Some(arg) = args.next() {
+        if arg == "--ticks" {
+            let value = args.next().ok_or_else(|| "--ticks precisa de um valor".to_string())?;
+            ticks = value
+                .parse()
+                .map_err(|_| format!("valor inválido para --ticks: {}", value))?;
+        } else {
+            rest.push(arg);
+        }
+    }
+    Ok((ticks, rest))
+}
@@ -0,0 +1,78 @@
+use macroquad::prelude::*;
+
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 8.0;
+const ZOOM_STEP: f32 = 1.1;
+
+/// Câmera de zoom/arrasto sobre a cena.
+///
+/// As coordenadas do "mundo" são as mesmas da tela com zoom 1 e sem arrasto,
+/// então as rotinas de desenho continuam usando `screen_width()`/`screen_height()`
+/// e posições absolutas normalmente. Vale: `tela = mundo * zoom + pan`.
+pub struct ViewCamera {
+    pub zoom: f32,
+    pub pan: Vec2,
+    /// Última posição do mouse durante o arrasto com o botão do meio
+    drag_from: Option<Vec2>,
+}
+
+impl Default for ViewCamera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ViewCamera {
+    pub fn new() -> Self {
+        Self {
+            zoom: 1.0,
+            pan: Vec2::ZERO,
+            drag_from: None,
+        }
+    }
+
+    /// Volta para o enquadramento original.
+    pub fn home(&mut self) {
+        self.zoom = 1.0;
+        self.pan = Vec2::ZERO;
+        self.drag_from = None;
+    }
+
+    /// Converte um ponto da tela (ex.: o mouse) para coordenadas do mundo.
+    pub fn screen_to_world(&self, point: Vec2) -> Vec2 {
+        (point - self.pan) / self.zoom
+    }
+
+    /// Zoom na roda do mouse (centrado no cursor) e arrasto com o botão do meio.
+    pub fn handle_input(&mut self) {
+        let mouse = Vec2::from(mouse_position());
+
+        let (_, wheel_y) = mouse_wheel();
+        if wheel_y != 0.0 {
+            // Mantém fixo o ponto do mundo que está embaixo do cursor
+            let anchor = self.screen_to_world(mouse);
+            let factor = if wheel_y > 0.0 { ZOOM_STEP } else { 1.0 / ZOOM_STEP };
+            self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+            self.pan = mouse - anchor * self.zoom;
+        }
+
+        if is_mouse_button_down(MouseButton::Middle) {
+            if let Some(from) = self.drag_from {
+                self.pan += mouse - from;
+            }
+            self.drag_from = Some(mouse);
+        } else {
+            self.drag_from = None;
+        }
+    }
+
+    /// Ativa a câmera para os próximos desenhos (a cena).
+    /// Depois, `set_default_camera()` volta para coordenadas de tela (HUD).
+    pub fn apply(&self) {
+        let top_left = self.screen_to_world(Vec2::ZERO);
+        let size = vec2(screen_width(), screen_height()) / self.zoom;
+        set_camera(&Camera2D::from_display_rect(Rect::new(
+            top_left.x, top_left.y, size.x, size.y,
+        )));
+    }
+}
@@ -0,0 +1,160 @@
+use ::rand::rngs::StdRng;
+use ::rand::{Rng, SeedableRng};
+use macroquad::prelude::*;
+
+use crate::resource_box::ResourceBox;
+use crate::text::draw_label;
+use crate::theme::Theme;
+use crate::threads::{ThreadState, ThreadsVisualizer};
+
+/// Quanto dura um evento quando `--chaos-duration` não é passado
+pub const DEFAULT_CHAOS_DURATION: f64 = 3.0;
+
+/// Alvo de um evento do caos
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChaosTarget {
+    /// Recurso congelado (ver `Resource::set_frozen`)
+    Resource(usize),
+    /// Thread derrubada (Crashed)
+    Thread(usize),
+}
+
+/// O que o caos pede para fazer neste frame, aplicado a cada lado por `apply`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChaosChange {
+    Start(ChaosTarget),
+    Recover(ChaosTarget),
+}
+
+impl ChaosChange {
+    /// Congela/derruba ou descongela/revive o alvo em `threads_vis` e `resource_box`.
+    /// A thread só é revivida se ainda estiver Crashed (alguém pode tê-la revivido antes).
+    pub fn apply(self, threads_vis: &mut ThreadsVisualizer, resource_box: &ResourceBox, now: f64) {
+        match self {
+            Self::Start(ChaosTarget::Resource(idx)) => threads_vis.set_resource_frozen(resource_box, idx, true, now),
+            Self::Recover(ChaosTarget::Resource(idx)) => threads_vis.set_resource_frozen(resource_box, idx, false, now),
+            Self::Start(ChaosTarget::Thread(i)) if i < threads_vis.threads.len() => {
+                threads_vis.set_thread_resource_state(resource_box, i, ThreadState::Crashed, None, now);
+            }
+            Self::Recover(ChaosTarget::Thread(i)) => {
+                if threads_vis.threads.get(i).is_some_and(|t| t.state == ThreadState::Crashed) {
+                    threads_vis.set_thread_resource_state(resource_box, i, ThreadState::Idle, None, now);
+                }
+            }
+            Self::Start(ChaosTarget::Thread(_)) => {}
+        }
+    }
+}
+
+/// Modo caos: a intervalos sorteados (em média `interval` segundos) congela um recurso
+/// ou derruba uma thread, e desfaz cada evento `duration` segundos depois. Todo evento
+/// começado fica em `active` até ser desfeito, inclusive no `stop`, então nada se perde.
+#[derive(Debug)]
+pub struct Chaos {
+    pub interval: f64,
+    pub duration: f64,
+    rng: StdRng,
+    next_event: f64,
+    /// Eventos em andamento e quando cada um acaba
+    pub active: Vec<(ChaosTarget, f64)>,
+}
+
+impl Chaos {
+    /// Caos com a semente `seed` (None sorteia uma), com o primeiro evento depois de `now`.
+    pub fn new(interval: f64, duration: f64, seed: Option<u64>, now: f64) -> Self {
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed ^ 0xc4a0_5c4a_05c4_a05c),
+            None => StdRng::from_rng(&mut ::rand::rng()),
+        };
+        let next_event = now + interval * rng.random_range(0.5..1.5);
+        Self {
+            interval,
+            duration,
+            rng,
+            next_event,
+            active: Vec::new(),
+        }
+    }
+
+    /// Mudanças deste frame: primeiro as recuperações vencidas e, se for a hora, um
+    /// evento novo num alvo que ainda está de pé (em `threads_vis`/`resource_box`).
+    pub fn update(&mut self, threads_vis: &ThreadsVisualizer, resource_box: &ResourceBox, now: f64) -> Vec<ChaosChange> {
+        let mut changes: Vec<ChaosChange> = self
+            .active
+            .iter()
+            .filter(|&&(_, until)| now >= until)
+            .map(|&(target, _)| ChaosChange::Recover(target))
+            .collect();
+        self.active.retain(|&(_, until)| now < until);
+
+        if now >= self.next_event {
+            self.next_event = now + self.interval * self.rng.random_range(0.5..1.5);
+            let busy = |target: ChaosTarget| self.active.iter().any(|&(t, _)| t == target);
+            let resources: Vec<ChaosTarget> = (0..resource_box.resources.len())
+                .filter(|&idx| !resource_box.is_frozen(idx))
+                .map(ChaosTarget::Resource)
+                .filter(|&t| !busy(t))
+                .collect();
+            let threads: Vec<ChaosTarget> = (0..threads_vis.threads.len())
+                .filter(|&i| threads_vis.threads[i].state != ThreadState::Crashed)
+                .map(ChaosTarget::Thread)
+                .filter(|&t| !busy(t))
+                .collect();
+            // Meio a meio entre recurso e thread; se um lado não tiver alvo, vai o outro
+            let (first, second) = if self.rng.random_bool(0.5) {
+                (&resources, &threads)
+            } else {
+                (&threads, &resources)
+            };
+            let pool = if first.is_empty() { second } else { first };
+            if !pool.is_empty() {
+                let target = pool[self.rng.random_range(0..pool.len())];
+                self.active.push((target, now + self.duration));
+                changes.push(ChaosChange::Start(target));
+            }
+        }
+        changes
+    }
+
+    /// Desfaz todos os eventos em andamento (para sair ou desligar o caos).
+    pub fn stop(&mut self) -> Vec<ChaosChange> {
+        self.active
+            .drain(..)
+            .map(|(target, _)| ChaosChange::Recover(target))
+            .collect()
+    }
+
+    /// Faixa "CHAOS" no canto de cima à direita enquanto houver evento, com cada alvo
+    /// e quanto falta para ele voltar.
+    pub fn draw_banner(&self, threads_vis: &ThreadsVisualizer, resource_box: &ResourceBox, theme: &Theme, now: f64) {
+        if self.active.is_empty() {
+            return;
+        }
+        let lines: Vec<String> = self
+            .active
+            .iter()
+            .map(|&(target, until)| {
+                let left = (until - now).max(0.0);
+                match target {
+                    ChaosTarget::Resource(idx) => {
+                        let name = resource_box.resources.get(idx).map_or_else(String::new, |r| r.name());
+                        format!("{} frozen ({:.1}s)", name, left)
+                    }
+                    ChaosTarget::Thread(i) => {
+                        let name = threads_vis.threads.get(i).map_or("", |t| t.name.as_str());
+                        format!("{} crashed ({:.1}s)", name, left)
+                    }
+                }
+            })
+            .collect();
+        let width = 260.0;
+        let height = 40.0 + 22.0 * lines.len() as f32;
+        let x = screen_width() - width - 20.0;
+        let y = 20.0;
+        draw_rectangle(x, y, width, height, theme.alert);
+        draw_label("CHAOS", x + 10.0, y + 28.0, 26.0, WHITE);
+        for (i, line) in lines.iter().enumerate() {
+            draw_label(line, x + 10.0, y + 52.0 + 22.0 * i as f32, 18.0, WHITE);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use macroquad::time::get_time;
+
+/// Fonte do tempo (em segundos) da simulação.
+///
+/// O jogo usa `RealClock`; testes usam `MockClock` e avançam o tempo na mão,
+/// sem dormir, para os comportamentos temporizados serem determinísticos.
+pub trait Clock: Send + Sync + fmt::Debug {
+    fn now(&self) -> f64;
+}
+
+/// Relógio do macroquad (`get_time()`, segundos desde o início do programa).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RealClock;
+
+impl Clock for RealClock {
+    fn now(&self) -> f64 {
+        get_time()
+    }
+}
+
+/// Relógio parado que só anda com `advance`/`set`.
+/// Os clones compartilham o mesmo tempo, então o teste pode ficar com um
+/// e entregar o outro ao `ThreadsVisualizer`.
+#[derive(Clone, Debug, Default)]
+pub struct MockClock {
+    bits: Arc<AtomicU64>,
+}
+
+impl MockClock {
+    pub fn new(start: f64) -> Self {
+        Self {
+            bits: Arc::new(AtomicU64::new(start.to_bits())),
+        }
+    }
+
+    pub fn set(&self, time: f64) {
+        self.bits.store(time.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn advance(&self, seconds: f64) {
+        self.set(self.now() + seconds);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> f64 {
+        f64::from_bits(self.bits.load(Ordering::Relaxed))
+    }
+}
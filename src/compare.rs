@@ -0,0 +1,86 @@
+use macroquad::prelude::*;
+
+use crate::resource_box::ResourceBox;
+use crate::text::{draw_label, scaled};
+use crate::theme::Theme;
+use crate::threads::{ThreadState, ThreadsVisualizer};
+
+/// Segunda simulação, com outra política, rodando a mesma carga ao lado da principal.
+/// As duas usam a mesma `seed`, então cada thread recebe as mesmas decisões nos dois
+/// lados e o que muda de um para o outro vem só da política.
+pub struct Comparison {
+    pub resource_box: ResourceBox,
+    pub threads_vis: ThreadsVisualizer,
+}
+
+impl Comparison {
+    pub fn new(resource_box: ResourceBox, threads_vis: ThreadsVisualizer) -> Self {
+        Self {
+            resource_box,
+            threads_vis,
+        }
+    }
+
+    /// Copia para este lado os modos e a aparência que o teclado muda no lado principal.
+    pub fn sync_modes(&mut self, primary: &ThreadsVisualizer) {
+        let vis = &mut self.threads_vis;
+        vis.burst_mode = primary.burst_mode;
+        vis.burst_size = primary.burst_size;
+        vis.demand_mode = primary.demand_mode;
+        vis.blocking_mode = primary.blocking_mode;
+        vis.priority_inheritance = primary.priority_inheritance;
+        vis.compact = primary.compact;
+        vis.show_ids = primary.show_ids;
+        vis.activity_radius = primary.activity_radius;
+        vis.scroll_offset = primary.scroll_offset;
+        vis.tree_style = primary.tree_style;
+        vis.thread_line_length = primary.thread_line_length;
+        if primary.preset != vis.preset {
+            if let Some(preset) = primary.preset {
+                vis.apply_preset(preset);
+            }
+        }
+        self.resource_box.compact = primary.compact;
+    }
+}
+
+/// Métricas de um lado da comparação, à direita da sua caixa de recursos:
+/// política, vazão, fila de espera e inanição.
+pub fn draw_side_metrics(
+    threads_vis: &ThreadsVisualizer,
+    resource_box: &ResourceBox,
+    theme: &Theme,
+    x: f32,
+    y: f32,
+) {
+    let now = threads_vis.clock.now();
+    let policy = resource_box
+        .resources
+        .first()
+        .map_or("-", |r| r.policy.name());
+    let waiting = threads_vis.threads_in_state(ThreadState::Waiting).count();
+    let starving = threads_vis
+        .starving_threads(now, threads_vis.starvation_threshold)
+        .count();
+    let longest_wait = threads_vis
+        .threads
+        .iter()
+        .filter_map(|t| t.waiting_since)
+        .map(|since| now - since)
+        .fold(0.0, f64::max);
+
+    let font_size = 20.0;
+    let line_height = scaled(font_size) + 5.0;
+    let top = y + scaled(font_size);
+    draw_label(&format!("Policy: {}", policy), x, top, 28.0, theme.highlight);
+    let lines = [
+        format!("Throughput: {:.1} ops/s", threads_vis.throughput.rate(now)),
+        format!("Waiting: {} / {}", waiting, threads_vis.threads.len()),
+        format!("Longest wait: {:.1}s", longest_wait),
+        format!("Starving: {}", starving),
+    ];
+    for (i, line) in lines.iter().enumerate() {
+        let color = if i == 3 && starving > 0 { theme.alert } else { theme.text };
+        draw_label(line, x, top + 10.0 + line_height * (i as f32 + 1.0), font_size, color);
+    }
+}
@@ -0,0 +1,693 @@
+use std::fmt;
+
+use crate::chaos::DEFAULT_CHAOS_DURATION;
+use crate::logger::{Level, LOG_LEVELS};
+use crate::policy::{policy_factory, POLICY_NAMES};
+use crate::preset::{Preset, PRESETS};
+use crate::threads::{
+    validate_hold_range, validate_state_weights, DEFAULT_AFFINITY_STRENGTH, DEFAULT_BACKOFF_FACTOR,
+};
+use crate::tick::{TickMode, TICK_MODES};
+use crate::transitions::{LogOverflow, DEFAULT_LOG_CAPACITY, LOG_OVERFLOWS};
+
+/// Valores padrão quando nada é passado na linha de comando
+pub const DEFAULT_THREADS: usize = 8;
+pub const DEFAULT_RESOURCES: u32 = 5;
+/// Passos por segundo de `--tick fixed`/`manual` (um por frame a 60 fps)
+pub const DEFAULT_STEPS_PER_SECOND: f64 = 60.0;
+
+/// Limites para o layout não quebrar
+pub const MAX_THREADS: usize = 32;
+pub const MAX_RESOURCES: u32 = 20;
+
+/// Configuração efetiva da simulação.
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub threads: usize,
+    pub resources: u32,
+    /// Nome de um roteiro embutido ou caminho de um arquivo de roteiro
+    pub scenario: Option<String>,
+    /// Arquivo em que gravar as decisões da execução (ver `ThreadsVisualizer::record_to`)
+    pub record: Option<String>,
+    /// Execução gravada a refazer no lugar do sorteio (ver `ThreadsVisualizer::replay_from`)
+    pub replay: Option<String>,
+    /// Pesos dos recursos no sorteio, na ordem dos recursos
+    pub weights: Vec<f32>,
+    /// Categoria de cada recurso, na ordem dos recursos (vazia = grupo padrão)
+    pub categories: Vec<String>,
+    /// Política de aquisição dos recursos (ver `policy::POLICY_NAMES`)
+    pub policy: String,
+    /// Usar threads de verdade (`std::thread`) em vez da simulação
+    pub workers: bool,
+    /// Política do lado direito da tela, rodando a mesma carga ao lado da principal
+    pub compare: Option<String>,
+    /// Semente dos sorteios (ver `ThreadsVisualizer::seed`)
+    pub seed: Option<u64>,
+    /// Máximo de leitores simultâneos por recurso (0 = sem limite)
+    pub max_readers: u32,
+    /// Máximo de escritores simultâneos por recurso (1 = escrita exclusiva)
+    pub max_writers: u32,
+    /// Leituras reentrantes: a thread que já lê um recurso pode ler de novo
+    /// (ver `Resource::set_reentrant_reads`)
+    pub reentrant_reads: bool,
+    /// Dependências entre recursos, (a, b) = pegar `a` exige `b` antes, com índices
+    /// a partir de 0 (ver `ResourceBox::add_dependency`)
+    pub dependencies: Vec<(usize, usize)>,
+    /// O que faz o tempo da simulação andar (ver `tick::TickDriver`)
+    pub tick: TickMode,
+    /// Recursos em grade em vez de uma faixa só (ver `ResourceBox::grid`)
+    pub grid: bool,
+    /// Janela (segundos) da taxa de aquisições
+    pub throughput_window: f64,
+    /// Decaimento (por segundo) da contenção e limiar para destacar o HOTSPOT
+    pub hotspot_decay: f32,
+    pub hotspot_threshold: f32,
+    /// Intervalos (min, max) em segundos das posses de leitura e de escrita
+    pub read_hold: (f64, f64),
+    pub write_hold: (f64, f64),
+    /// Pesos do sorteio de estado: Idle, Waiting, Reading, Writing
+    pub state_weights: [f32; 4],
+    /// Carga que preencheu os pesos e tempos de posse (os que vêm depois dela na
+    /// linha de comando ainda valem)
+    pub preset: Option<Preset>,
+    /// Espera (segundos) a partir da qual o alarme de inanição dispara
+    pub starvation_threshold: f64,
+    /// Segundos em Idle até uma thread tentar pegar um recurso sozinha
+    pub idle_timeout: Option<f64>,
+    /// Segundos em que a thread fica fora do sorteio depois de mudar de estado
+    pub cooldown: f64,
+    /// Recuo entre tentativas de quem insiste num alvo e quanto ele cresce a cada
+    /// falha (ver `ThreadsVisualizer::retry_backoff`); 0 desliga
+    pub retry_backoff: f64,
+    pub backoff_factor: f64,
+    /// Recurso preferido de cada thread, (thread, recurso) com índices a partir de 0,
+    /// e a chance de o sorteio cair nele (ver `ThreadsVisualizer::pick_resource`)
+    pub affinities: Vec<(usize, usize)>,
+    pub affinity_strength: f64,
+    /// Modo caos (ver `chaos::Chaos`): intervalo médio entre eventos (None = desligado)
+    /// e quanto cada evento dura
+    pub chaos: Option<f64>,
+    pub chaos_duration: f64,
+    /// Máximo de transições guardadas no log e o que fazer quando enche
+    /// (ver `ThreadsVisualizer::set_log_capacity`)
+    pub log_capacity: usize,
+    pub log_overflow: LogOverflow,
+    /// Log (ver `logger`): nível (None = desligado; por padrão só os erros) e arquivo (None = stderr)
+    pub log_level: Option<Level>,
+    pub log_file: Option<String>,
+    /// Ler comandos da entrada padrão (ver `repl::Command`)
+    pub repl: bool,
+    /// Fonte TTF de todos os textos (None = a padrão do macroquad)
+    pub font: Option<String>,
+    /// Multiplicador de todos os tamanhos de fonte (ver `text::set_text_scale`)
+    pub text_scale: f32,
+    /// Suavizar a fonte carregada (desligado, os glifos ficam serrilhados, "pixelados")
+    pub antialias: bool,
+    /// Guardar medidas e quebras de linha entre frames (ver `text::set_text_cache`)
+    pub text_cache: bool,
+    /// Desenhar as linhas vertical/horizontal da "árvore" e os fios das threads
+    pub tree_lines: bool,
+    pub fios: bool,
+    /// Segundos parados até o descanso de tela (ver `screensaver::Screensaver`);
+    /// None = desligado até a tecla
+    pub screensaver: Option<f64>,
+    /// Mostrar o resumo da sessão ao sair (ver `stats::SessionStats`)
+    pub summary: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            threads: DEFAULT_THREADS,
+            resources: DEFAULT_RESOURCES,
+            scenario: None,
+            record: None,
+            replay: None,
+            weights: Vec::new(),
+            categories: Vec::new(),
+            policy: "reader".to_string(),
+            workers: false,
+            compare: None,
+            seed: None,
+            max_readers: 0,
+            max_writers: 1,
+            reentrant_reads: false,
+            dependencies: Vec::new(),
+            tick: TickMode::RealTime,
+            grid: false,
+            throughput_window: 1.0,
+            hotspot_decay: 0.5,
+            hotspot_threshold: 2.0,
+            read_hold: (1.0, 4.0),
+            write_hold: (1.5, 6.0),
+            state_weights: [1.0; 4],
+            preset: None,
+            starvation_threshold: 8.0,
+            idle_timeout: None,
+            cooldown: 0.0,
+            retry_backoff: 0.0,
+            backoff_factor: DEFAULT_BACKOFF_FACTOR,
+            affinities: Vec::new(),
+            affinity_strength: DEFAULT_AFFINITY_STRENGTH,
+            chaos: None,
+            chaos_duration: DEFAULT_CHAOS_DURATION,
+            log_capacity: DEFAULT_LOG_CAPACITY,
+            log_overflow: LogOverflow::DropOldest,
+            log_level: Some(Level::Error),
+            log_file: None,
+            repl: false,
+            font: None,
+            text_scale: 1.0,
+            antialias: true,
+            text_cache: true,
+            screensaver: None,
+            tree_lines: true,
+            fios: true,
+            summary: true,
+        }
+    }
+}
+
+impl Config {
+    /// Lê a configuração de `std::env::args`.
+    pub fn from_env() -> Result<Self, String> {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    /// Interpreta `--threads N --resources M`. Valores ausentes usam os padrões,
+    /// valores acima do máximo são limitados.
+    pub fn parse<I, S>(args: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut config = Config::default();
+        let mut args = args.into_iter();
+        // `--tick` e os tamanhos dos passos podem vir em qualquer ordem
+        let mut tick_name = None;
+        // `--log-file` sem `--log-level` registra as transições
+        let mut log_level_given = false;
+        let mut steps_per_second = DEFAULT_STEPS_PER_SECOND;
+        let mut steps_per_frame = 1;
+
+        while let Some(arg) = args.next() {
+            match arg.as_ref() {
+                "--threads" => {
+                    let n: usize = parse_value("--threads", args.next())?;
+                    if n == 0 {
+                        return Err("--threads precisa ser maior que zero".to_string());
+                    }
+                    config.threads = n.min(MAX_THREADS);
+                }
+                "--resources" => {
+                    let n: u32 = parse_value("--resources", args.next())?;
+                    config.resources = n.min(MAX_RESOURCES);
+                }
+                "--scenario" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "--scenario precisa de um valor".to_string())?;
+                    config.scenario = Some(value.as_ref().to_string());
+                }
+                "--weights" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "--weights precisa de um valor".to_string())?;
+                    config.weights = value
+                        .as_ref()
+                        .split(',')
+                        .map(|w| match w.trim().parse::<f32>() {
+                            Ok(w) if w >= 0.0 => Ok(w),
+                            _ => Err(format!("peso inválido em --weights: {}", w)),
+                        })
+                        .collect::<Result<_, _>>()?;
+                }
+                "--categories" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "--categories precisa de um valor".to_string())?;
+                    config.categories = value
+                        .as_ref()
+                        .split(',')
+                        .map(|c| c.trim().to_string())
+                        .collect();
+                }
+                "--policy" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "--policy precisa de um valor".to_string())?;
+                    let name = value.as_ref();
+                    if policy_factory(name).is_none() {
+                        return Err(format!(
+                            "política desconhecida: {} (use {})",
+                            name,
+                            POLICY_NAMES.join(", ")
+                        ));
+                    }
+                    config.policy = name.to_string();
+                }
+                "--compare" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "--compare precisa de um valor".to_string())?;
+                    let name = value.as_ref();
+                    if policy_factory(name).is_none() {
+                        return Err(format!(
+                            "política desconhecida: {} (use {})",
+                            name,
+                            POLICY_NAMES.join(", ")
+                        ));
+                    }
+                    config.compare = Some(name.to_string());
+                }
+                "--seed" => {
+                    config.seed = Some(parse_value("--seed", args.next())?);
+                }
+                "--workers" => config.workers = true,
+                "--no-tree" => config.tree_lines = false,
+                "--repl" => config.repl = true,
+                "--font" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "--font precisa de um valor".to_string())?;
+                    config.font = Some(value.as_ref().to_string());
+                }
+                "--no-antialias" => config.antialias = false,
+                "--no-text-cache" => config.text_cache = false,
+                "--no-fios" => config.fios = false,
+                "--no-summary" => config.summary = false,
+                "--record" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "--record precisa de um valor".to_string())?;
+                    config.record = Some(value.as_ref().to_string());
+                }
+                "--replay" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "--replay precisa de um valor".to_string())?;
+                    config.replay = Some(value.as_ref().to_string());
+                }
+                "--hotspot-decay" => {
+                    config.hotspot_decay = parse_value("--hotspot-decay", args.next())?;
+                }
+                "--hotspot-threshold" => {
+                    config.hotspot_threshold = parse_value("--hotspot-threshold", args.next())?;
+                }
+                "--text-scale" => {
+                    let scale: f32 = parse_value("--text-scale", args.next())?;
+                    if !(0.5..=4.0).contains(&scale) {
+                        return Err("--text-scale precisa estar entre 0.5 e 4".to_string());
+                    }
+                    config.text_scale = scale;
+                }
+                "--throughput-window" => {
+                    let secs: f64 = parse_value("--throughput-window", args.next())?;
+                    if secs <= 0.0 {
+                        return Err("--throughput-window precisa ser maior que zero".to_string());
+                    }
+                    config.throughput_window = secs;
+                }
+                "--read-hold" => config.read_hold = parse_range("--read-hold", args.next())?,
+                "--write-hold" => config.write_hold = parse_range("--write-hold", args.next())?,
+                "--preset" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "--preset precisa de um valor".to_string())?;
+                    let preset = Preset::from_name(value.as_ref()).ok_or_else(|| {
+                        let names: Vec<&str> = PRESETS.iter().map(|p| p.name()).collect();
+                        format!("carga desconhecida: {} (use {})", value.as_ref(), names.join(", "))
+                    })?;
+                    config.state_weights = preset.state_weights();
+                    (config.read_hold, config.write_hold) = preset.hold_ranges();
+                    config.preset = Some(preset);
+                }
+                "--state-weights" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "--state-weights precisa de um valor".to_string())?;
+                    let invalid = || {
+                        format!(
+                            "valor inválido para --state-weights: {} (use IDLE,WAITING,READING,WRITING)",
+                            value.as_ref()
+                        )
+                    };
+                    let weights: Vec<f32> = value
+                        .as_ref()
+                        .split(',')
+                        .map(|w| w.trim().parse::<f32>().map_err(|_| invalid()))
+                        .collect::<Result<_, _>>()?;
+                    let weights: [f32; 4] = weights.try_into().map_err(|_| invalid())?;
+                    validate_state_weights(&weights)?;
+                    config.state_weights = weights;
+                }
+                "--starvation-threshold" => {
+                    let secs: f64 = parse_value("--starvation-threshold", args.next())?;
+                    if secs <= 0.0 {
+                        return Err("--starvation-threshold precisa ser maior que zero".to_string());
+                    }
+                    config.starvation_threshold = secs;
+                }
+                "--idle-timeout" => {
+                    let secs: f64 = parse_value("--idle-timeout", args.next())?;
+                    if secs <= 0.0 {
+                        return Err("--idle-timeout precisa ser maior que zero".to_string());
+                    }
+                    config.idle_timeout = Some(secs);
+                }
+                "--cooldown" => {
+                    let secs: f64 = parse_value("--cooldown", args.next())?;
+                    if secs < 0.0 {
+                        return Err("--cooldown não pode ser negativo".to_string());
+                    }
+                    config.cooldown = secs;
+                }
+                "--backoff" => {
+                    let secs: f64 = parse_value("--backoff", args.next())?;
+                    if secs < 0.0 {
+                        return Err("--backoff não pode ser negativo".to_string());
+                    }
+                    config.retry_backoff = secs;
+                }
+                "--backoff-factor" => {
+                    let factor: f64 = parse_value("--backoff-factor", args.next())?;
+                    if factor < 1.0 {
+                        return Err("--backoff-factor precisa ser pelo menos 1".to_string());
+                    }
+                    config.backoff_factor = factor;
+                }
+                "--affinity" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "--affinity precisa de um valor".to_string())?;
+                    config.affinities = parse_pairs("--affinity", value.as_ref(), "T:R")?;
+                }
+                "--affinity-strength" => {
+                    let p: f64 = parse_value("--affinity-strength", args.next())?;
+                    if !(0.0..=1.0).contains(&p) {
+                        return Err("--affinity-strength precisa estar entre 0 e 1".to_string());
+                    }
+                    config.affinity_strength = p;
+                }
+                "--chaos" => {
+                    let secs: f64 = parse_value("--chaos", args.next())?;
+                    if secs <= 0.0 {
+                        return Err("--chaos precisa ser maior que zero".to_string());
+                    }
+                    config.chaos = Some(secs);
+                }
+                "--screensaver" => {
+                    let secs: f64 = parse_value("--screensaver", args.next())?;
+                    if secs < 0.0 {
+                        return Err("--screensaver não pode ser negativo".to_string());
+                    }
+                    config.screensaver = Some(secs);
+                }
+                "--chaos-duration" => {
+                    let secs: f64 = parse_value("--chaos-duration", args.next())?;
+                    if secs <= 0.0 {
+                        return Err("--chaos-duration precisa ser maior que zero".to_string());
+                    }
+                    config.chaos_duration = secs;
+                }
+                "--log-level" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "--log-level precisa de um valor".to_string())?;
+                    config.log_level = Level::from_name(value.as_ref()).ok_or_else(|| {
+                        format!("--log-level inválido: {} (use {})", value.as_ref(), LOG_LEVELS.join(", "))
+                    })?;
+                    log_level_given = true;
+                }
+                "--log-file" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "--log-file precisa de um valor".to_string())?;
+                    config.log_file = Some(value.as_ref().to_string());
+                }
+                "--log-capacity" => {
+                    config.log_capacity = parse_value("--log-capacity", args.next())?;
+                }
+                "--log-overflow" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "--log-overflow precisa de um valor".to_string())?;
+                    config.log_overflow = LogOverflow::from_name(value.as_ref()).ok_or_else(|| {
+                        format!("--log-overflow inválido: {} (use {})", value.as_ref(), LOG_OVERFLOWS.join(", "))
+                    })?;
+                }
+                "--max-readers" => {
+                    config.max_readers = parse_value("--max-readers", args.next())?;
+                }
+                "--max-writers" => {
+                    let n: u32 = parse_value("--max-writers", args.next())?;
+                    if n == 0 {
+                        return Err("--max-writers precisa ser maior que zero".to_string());
+                    }
+                    config.max_writers = n;
+                }
+                "--tick" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "--tick precisa de um valor".to_string())?;
+                    let name = value.as_ref();
+                    if !TICK_MODES.contains(&name) {
+                        return Err(format!("--tick inválido: {} (use {})", name, TICK_MODES.join(", ")));
+                    }
+                    tick_name = Some(name.to_string());
+                }
+                "--steps-per-second" => {
+                    steps_per_second = parse_value("--steps-per-second", args.next())?;
+                    if steps_per_second <= 0.0 {
+                        return Err("--steps-per-second precisa ser maior que zero".to_string());
+                    }
+                }
+                "--steps-per-frame" => {
+                    steps_per_frame = parse_value("--steps-per-frame", args.next())?;
+                    if steps_per_frame == 0 {
+                        return Err("--steps-per-frame precisa ser maior que zero".to_string());
+                    }
+                }
+                "--grid" => config.grid = true,
+                "--reentrant-reads" => config.reentrant_reads = true,
+                "--depends" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "--depends precisa de um valor".to_string())?;
+                    config.dependencies = parse_pairs("--depends", value.as_ref(), "A:B")?;
+                    if let Some(&(a, _)) = config.dependencies.iter().find(|&&(a, b)| a == b) {
+                        return Err(format!("--depends: R{} não pode depender de si mesmo", a + 1));
+                    }
+                }
+                other => return Err(format!("argumento desconhecido: {}", other)),
+            }
+        }
+        if config.log_file.is_some() && !log_level_given {
+            config.log_level = Some(Level::Info);
+        }
+        if let Some(name) = tick_name {
+            config.tick = TickMode::from_name(&name, steps_per_second, steps_per_frame)
+                .ok_or_else(|| format!("--tick inválido: {}", name))?;
+        }
+        if config.workers && config.tick != TickMode::RealTime {
+            return Err("--workers só funciona com --tick real".to_string());
+        }
+        if config.workers && config.compare.is_some() {
+            return Err("--compare não funciona com --workers".to_string());
+        }
+        for (flag, set) in [("--record", config.record.is_some()), ("--replay", config.replay.is_some())] {
+            if set && (config.workers || config.compare.is_some()) {
+                return Err(format!("{} não funciona com --workers nem com --compare", flag));
+            }
+        }
+        if config.workers && config.chaos.is_some() {
+            return Err("--chaos não funciona com --workers".to_string());
+        }
+        // A gravação já traz os congelamentos do caos; outro caos por cima desfaria o replay
+        if config.replay.is_some() && config.chaos.is_some() {
+            return Err("--chaos não funciona com --replay".to_string());
+        }
+        if config.replay.is_some() && config.scenario.is_some() {
+            return Err("--replay e --scenario não podem ser usados juntos".to_string());
+        }
+        if let Some(&(a, b)) = config.dependencies.iter().find(|&&(a, b)| a.max(b) >= config.resources as usize) {
+            return Err(format!("--depends {}:{}: só há {} recursos", a + 1, b + 1, config.resources));
+        }
+        if let Some(&(t, r)) = config
+            .affinities
+            .iter()
+            .find(|&&(t, r)| t >= config.threads || r >= config.resources as usize)
+        {
+            return Err(format!(
+                "--affinity {}:{}: há {} threads e {} recursos",
+                t + 1,
+                r + 1,
+                config.threads,
+                config.resources
+            ));
+        }
+        Ok(config)
+    }
+}
+
+/// Lista `A:B,C:D` de `flag` (números a partir de 1, no formato `form`), já com
+/// índices a partir de 0.
+fn parse_pairs(flag: &str, value: &str, form: &str) -> Result<Vec<(usize, usize)>, String> {
+    value
+        .split(',')
+        .map(|pair| {
+            let invalid = || format!("valor inválido para {}: {} (use {},...)", flag, pair, form);
+            let (a, b) = pair.split_once(':').ok_or_else(invalid)?;
+            let a: usize = a.trim().parse().map_err(|_| invalid())?;
+            let b: usize = b.trim().parse().map_err(|_| invalid())?;
+            if a == 0 || b == 0 {
+                return Err(invalid());
+            }
+            Ok((a - 1, b - 1))
+        })
+        .collect()
+}
+
+fn parse_value<T, S>(flag: &str, value: Option<S>) -> Result<T, String>
+where
+    T: std::str::FromStr,
+    S: AsRef<str>,
+{
+    let value = value.ok_or_else(|| format!("{} precisa de um valor", flag))?;
+    value
+        .as_ref()
+        .parse()
+        .map_err(|_| format!("valor inválido para {}: {}", flag, value.as_ref()))
+}
+
+/// Lê um intervalo `MIN,MAX` de segundos.
+fn parse_range<S>(flag: &str, value: Option<S>) -> Result<(f64, f64), String>
+where
+    S: AsRef<str>,
+{
+    let value = value.ok_or_else(|| format!("{} precisa de um valor", flag))?;
+    let invalid = || format!("valor inválido para {}: {} (use MIN,MAX)", flag, value.as_ref());
+    let (min, max) = value.as_ref().split_once(',').ok_or_else(invalid)?;
+    let min: f64 = min.trim().parse().map_err(|_| invalid())?;
+    let max: f64 = max.trim().parse().map_err(|_| invalid())?;
+    validate_hold_range(min, max).map_err(|err| format!("{}: {}", flag, err))?;
+    Ok((min, max))
+}
+
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "threads: {}, resources: {}, policy: {}",
+            self.threads, self.resources, self.policy
+        )?;
+        if self.max_readers > 0 {
+            write!(f, ", max readers: {}", self.max_readers)?;
+        }
+        if self.max_writers > 1 {
+            write!(f, ", max writers: {}", self.max_writers)?;
+        }
+        if self.reentrant_reads {
+            write!(f, ", reentrant reads")?;
+        }
+        if !self.dependencies.is_empty() {
+            let pairs: Vec<String> = self.dependencies.iter().map(|(a, b)| format!("{}:{}", a + 1, b + 1)).collect();
+            write!(f, ", depends: {}", pairs.join(","))?;
+        }
+        match self.tick {
+            TickMode::RealTime => {}
+            TickMode::FixedSteps {
+                steps_per_second,
+                steps_per_frame,
+            } => write!(f, ", tick: fixed {}/s x{} per frame", steps_per_second, steps_per_frame)?,
+            TickMode::Manual { steps_per_second } => write!(f, ", tick: manual {}/s", steps_per_second)?,
+        }
+        if self.grid {
+            write!(f, ", grid")?;
+        }
+        if let Some(timeout) = self.idle_timeout {
+            write!(f, ", idle timeout: {}s", timeout)?;
+        }
+        // Só os erros no stderr é o padrão e não aparece
+        if self.log_level != Some(Level::Error) || self.log_file.is_some() {
+            write!(f, ", log level: {}", self.log_level.map_or("off", Level::name))?;
+            if let Some(path) = &self.log_file {
+                write!(f, " -> {}", path)?;
+            }
+        }
+        if !self.affinities.is_empty() {
+            let pairs: Vec<String> = self.affinities.iter().map(|(t, r)| format!("{}:{}", t + 1, r + 1)).collect();
+            write!(f, ", affinity: {} ({:.0}%)", pairs.join(","), self.affinity_strength * 100.0)?;
+        }
+        if self.cooldown > 0.0 {
+            write!(f, ", cooldown: {}s", self.cooldown)?;
+        }
+        if self.retry_backoff > 0.0 {
+            write!(f, ", backoff: {}s x{}", self.retry_backoff, self.backoff_factor)?;
+        }
+        if let Some(interval) = self.chaos {
+            write!(f, ", chaos: every ~{}s for {}s", interval, self.chaos_duration)?;
+        }
+        if self.log_capacity != DEFAULT_LOG_CAPACITY || self.log_overflow != LogOverflow::DropOldest {
+            write!(f, ", log: {} ({})", self.log_capacity, self.log_overflow.name())?;
+        }
+        if self.workers {
+            write!(f, ", mode: workers")?;
+        }
+        if let Some(policy) = &self.compare {
+            write!(f, ", compare with: {}", policy)?;
+        }
+        if let Some(seed) = self.seed {
+            write!(f, ", seed: {}", seed)?;
+        }
+        if self.repl {
+            write!(f, ", repl")?;
+        }
+        if let Some(font) = &self.font {
+            write!(f, ", font: {}", font)?;
+        }
+        if self.text_scale != 1.0 {
+            write!(f, ", text scale: {}x", self.text_scale)?;
+        }
+        if !self.antialias {
+            write!(f, ", no antialias")?;
+        }
+        if let Some(delay) = self.screensaver {
+            write!(f, ", screensaver after {}s", delay)?;
+        }
+        if !self.text_cache {
+            write!(f, ", no text cache")?;
+        }
+        if !self.tree_lines {
+            write!(f, ", no tree lines")?;
+        }
+        if !self.fios {
+            write!(f, ", no fios")?;
+        }
+        if !self.summary {
+            write!(f, ", no summary")?;
+        }
+        if let Some(path) = &self.record {
+            write!(f, ", record: {}", path)?;
+        }
+        if let Some(path) = &self.replay {
+            write!(f, ", replay: {}", path)?;
+        }
+        if let Some(preset) = self.preset {
+            write!(f, ", preset: {}", preset.name())?;
+        }
+        if self.state_weights != [1.0; 4] {
+            write!(f, ", state weights: {:?}", self.state_weights)?;
+        }
+        if !self.weights.is_empty() {
+            write!(f, ", weights: {:?}", self.weights)?;
+        }
+        if !self.categories.is_empty() {
+            write!(f, ", categories: {:?}", self.categories)?;
+        }
+        if let Some(scenario) = &self.scenario {
+            write!(f, ", scenario: {}", scenario)?;
+        }
+        Ok(())
+    }
+}
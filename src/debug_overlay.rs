@@ -0,0 +1,78 @@
+use macroquad::prelude::*;
+
+use crate::resource_box::ResourceBox;
+use crate::text::{draw_label, text_cache_enabled, text_cache_stats};
+use crate::theme::Theme;
+use crate::threads::ThreadsVisualizer;
+
+const GRID_STEP: f32 = 50.0;
+const LABEL_STEP: f32 = 100.0;
+
+/// Sobreposição de depuração do layout: grade de coordenadas com os eixos
+/// rotulados, a caixa de recursos e as caixas de texto (normalmente invisíveis)
+/// de cada thread. Desenhada no espaço do mundo, alinhada com a cena.
+pub fn draw_debug_overlay(resource_box: &ResourceBox, threads_vis: &ThreadsVisualizer, theme: &Theme) {
+    let sw = screen_width();
+    let sh = screen_height();
+    let grid_color = Color::new(theme.text.r, theme.text.g, theme.text.b, 0.12);
+    let axis_color = Color::new(theme.text.r, theme.text.g, theme.text.b, 0.6);
+
+    // Grade + rótulos no topo (x) e na esquerda (y)
+    let mut x = 0.0;
+    while x <= sw {
+        draw_line(x, 0.0, x, sh, 1.0, grid_color);
+        if x % LABEL_STEP == 0.0 {
+            draw_label(&format!("{}", x), x + 2.0, 12.0, 14.0, axis_color);
+        }
+        x += GRID_STEP;
+    }
+    let mut y = 0.0;
+    while y <= sh {
+        draw_line(0.0, y, sw, y, 1.0, grid_color);
+        if y % LABEL_STEP == 0.0 && y > 0.0 {
+            draw_label(&format!("{}", y), 2.0, y - 2.0, 14.0, axis_color);
+        }
+        y += GRID_STEP;
+    }
+
+    // Tempo do frame e o cache de texto, para comparar com `--no-text-cache`
+    let stats = text_cache_stats();
+    let cache = if text_cache_enabled() {
+        format!("text cache: {} hits, {} misses", stats.hits, stats.misses)
+    } else {
+        "text cache: off".to_string()
+    };
+    draw_label(
+        &format!("frame: {:.2} ms, {}", get_frame_time() * 1000.0, cache),
+        LABEL_STEP + 4.0,
+        LABEL_STEP - 4.0,
+        16.0,
+        axis_color,
+    );
+
+    // Caixa de recursos
+    let bounds = resource_box.bounds();
+    draw_rectangle_lines(bounds.x, bounds.y, bounds.w, bounds.h, 2.0, MAGENTA);
+    draw_label(
+        &format!("ResourceBox {}x{}", bounds.w, bounds.h),
+        bounds.x,
+        bounds.y - 4.0,
+        14.0,
+        MAGENTA,
+    );
+
+    // Caixas de texto das threads; sobreposições ficam com cor mais forte
+    let layout = threads_vis.layout();
+    let boxes: Vec<Rect> = (0..threads_vis.threads.len())
+        .filter(|&i| layout.is_visible(i))
+        .map(|i| layout.text_box(i))
+        .collect();
+    for (i, rect) in boxes.iter().enumerate() {
+        let overlaps = boxes
+            .iter()
+            .enumerate()
+            .any(|(j, other)| i != j && rect.overlaps(other));
+        let color = if overlaps { theme.alert } else { SKYBLUE };
+        draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 1.0, color);
+    }
+}
@@ -0,0 +1,151 @@
+use macroquad::prelude::*;
+
+use crate::resource_box::ResourceBox;
+use crate::theme::Theme;
+use crate::threads::{ThreadState, ThreadsVisualizer};
+
+/// Até que distância (em pixels do mundo) de um recurso o arrasto ainda "gruda" nele
+const SNAP_DISTANCE: f32 = 40.0;
+
+/// Quanto o mouse pode andar entre apertar e soltar para ainda contar como clique
+const CLICK_DISTANCE: f32 = 5.0;
+
+/// Um pedido feito com o mouse: a thread `thread` quer `mode` no recurso `resource`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DragRequest {
+    pub thread: usize,
+    pub mode: ThreadState,
+    pub resource: usize,
+}
+
+/// O que um gesto do mouse pediu.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MouseAction {
+    /// Arrastou uma thread até um recurso
+    Assign(DragRequest),
+    /// Clicou (sem arrastar) numa thread ou num recurso
+    SelectThread(usize),
+    SelectResource(usize),
+    /// Clicou no vazio
+    Deselect,
+}
+
+/// Arrastar do círculo de uma thread até um recurso para pedir o lock:
+/// botão esquerdo pede leitura, botão direito pede escrita. Um clique com o
+/// esquerdo (apertar e soltar quase no mesmo lugar) seleciona em vez de arrastar.
+/// Trabalha em coordenadas do mundo (o `main` converte o mouse pela câmera).
+#[derive(Debug, Default)]
+pub struct DragAssign {
+    /// Thread sendo arrastada, o modo pedido e o botão que começou o arrasto
+    active: Option<(usize, ThreadState, MouseButton)>,
+    /// Onde o botão foi apertado (para distinguir clique de arrasto)
+    pressed_at: Option<Vec2>,
+}
+
+impl DragAssign {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Começa o arrasto ao apertar sobre uma thread e, ao soltar, devolve o pedido
+    /// para o recurso mais próximo. Soltar longe de qualquer recurso cancela.
+    /// Um clique vira seleção e não pede nada (nem começa arrasto).
+    pub fn update(
+        &mut self,
+        mouse: Vec2,
+        threads_vis: &ThreadsVisualizer,
+        resource_box: &ResourceBox,
+    ) -> Option<MouseAction> {
+        if self.pressed_at.is_none() && is_mouse_button_pressed(MouseButton::Left) {
+            self.pressed_at = Some(mouse);
+        }
+        if self.active.is_none() {
+            // Clique que não começou em thread nenhuma: seleciona o recurso ou tira a seleção
+            if self.pressed_at.is_some() && is_mouse_button_released(MouseButton::Left) {
+                let clicked = self.take_click(mouse);
+                if clicked {
+                    return Some(match resource_box.nearest_resource(mouse, 0.0) {
+                        Some(idx) => MouseAction::SelectResource(idx),
+                        None => MouseAction::Deselect,
+                    });
+                }
+                return None;
+            }
+            for (button, mode) in [
+                (MouseButton::Left, ThreadState::Reading),
+                (MouseButton::Right, ThreadState::Writing),
+            ] {
+                if !is_mouse_button_pressed(button) {
+                    continue;
+                }
+                // Thread morta não pede nada: precisa ser revivida antes
+                let thread = threads_vis
+                    .thread_at(mouse)
+                    .filter(|&i| threads_vis.threads[i].state != ThreadState::Crashed);
+                if let Some(thread) = thread {
+                    self.active = Some((thread, mode, button));
+                    break;
+                }
+            }
+            return None;
+        }
+
+        let (thread, mode, button) = self.active?;
+        if !is_mouse_button_released(button) {
+            return None;
+        }
+        self.active = None;
+        if button == MouseButton::Left && self.take_click(mouse) {
+            return Some(MouseAction::SelectThread(thread));
+        }
+        let resource = resource_box.nearest_resource(mouse, SNAP_DISTANCE)?;
+        Some(MouseAction::Assign(DragRequest {
+            thread,
+            mode,
+            resource,
+        }))
+    }
+
+    /// Esquece onde o botão esquerdo foi apertado; diz se foi um clique (soltou perto).
+    fn take_click(&mut self, mouse: Vec2) -> bool {
+        self.pressed_at
+            .take()
+            .is_some_and(|at| at.distance(mouse) <= CLICK_DISTANCE)
+    }
+
+    /// Um arrasto está em andamento (o mouse já se afastou de onde apertou)?
+    fn dragging(&self, mouse: Vec2) -> bool {
+        match (self.active, self.pressed_at) {
+            (Some((_, _, MouseButton::Left)), Some(at)) => at.distance(mouse) > CLICK_DISTANCE,
+            (active, _) => active.is_some(),
+        }
+    }
+
+    /// Linha da thread até o mouse e o contorno do recurso em que ela vai grudar.
+    pub fn draw(
+        &self,
+        mouse: Vec2,
+        threads_vis: &ThreadsVisualizer,
+        resource_box: &ResourceBox,
+        theme: &Theme,
+    ) {
+        let Some((thread, mode, _)) = self.active.filter(|_| self.dragging(mouse)) else {
+            return;
+        };
+        let layout = threads_vis.layout();
+        if !layout.is_visible(thread) {
+            return;
+        }
+        let color = if mode == ThreadState::Writing { theme.writing } else { theme.reading };
+        let from = layout.circle_center(thread);
+        draw_line(from.x, from.y, mouse.x, mouse.y, 3.0, color);
+        draw_circle(mouse.x, mouse.y, 5.0, color);
+
+        if let Some(rect) = resource_box
+            .nearest_resource(mouse, SNAP_DISTANCE)
+            .and_then(|idx| resource_box.resource_rect(idx))
+        {
+            draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 4.0, color);
+        }
+    }
+}
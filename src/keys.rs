@@ -0,0 +1,389 @@
+use macroquad::prelude::*;
+
+use crate::text::{draw_label, scaled, text_width};
+use crate::theme::Theme;
+
+/// O que cada tecla faz. O `main` trata as ações; a tabela `KEY_BINDINGS`
+/// diz qual tecla dispara cada uma e alimenta o painel de ajuda.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    ToggleHelp,
+    ToggleTheme,
+    NextTextScale,
+    ToggleCompact,
+    ToggleIds,
+    ToggleActivityRadius,
+    ToggleBurst,
+    ToggleDemand,
+    ToggleBlocking,
+    ToggleInheritance,
+    NextPreset,
+    ToggleDebugOverlay,
+    ToggleExplain,
+    ToggleScreensaver,
+    ToggleTour,
+    TogglePause,
+    ToggleFreezeRandom,
+    StepTick,
+    CameraHome,
+    /// Índice da thread (0 para a tecla '1')
+    SelectThread(usize),
+    NextResource,
+    RenameResource,
+    SearchResources,
+    CrashThread,
+    ReviveThread,
+    RaisePriority,
+    LowerPriority,
+    ScrollLeft,
+    ScrollRight,
+    RequestTarget,
+    RaiseThreadLine,
+    LowerThreadLine,
+    ShortenThreads,
+    LengthenThreads,
+    RemoveThread,
+    AddThread,
+    ForceRelease,
+    WriteStorm,
+    ToggleFrozen,
+    Reset,
+    Screenshot,
+    ExportJson,
+    Quit,
+}
+
+/// Uma linha da tabela de teclas
+pub struct KeyBinding {
+    pub keys: &'static [KeyCode],
+    /// Como a tecla aparece no painel de ajuda
+    pub label: &'static str,
+    pub description: &'static str,
+    pub action: Action,
+}
+
+pub const KEY_BINDINGS: &[KeyBinding] = &[
+    KeyBinding {
+        keys: &[KeyCode::H, KeyCode::Slash],
+        label: "H / ?",
+        description: "Show/hide this help",
+        action: Action::ToggleHelp,
+    },
+    KeyBinding {
+        keys: &[KeyCode::T],
+        label: "T",
+        description: "Toggle light/dark theme",
+        action: Action::ToggleTheme,
+    },
+    KeyBinding {
+        keys: &[KeyCode::Z],
+        label: "Z",
+        description: "Bigger text (1x, 1.25x, 1.5x, 2x, then back)",
+        action: Action::NextTextScale,
+    },
+    KeyBinding {
+        keys: &[KeyCode::C],
+        label: "C",
+        description: "Toggle compact mode (colors only)",
+        action: Action::ToggleCompact,
+    },
+    KeyBinding {
+        keys: &[KeyCode::I],
+        label: "I",
+        description: "Show/hide thread numbers inside the circles",
+        action: Action::ToggleIds,
+    },
+    KeyBinding {
+        keys: &[KeyCode::A],
+        label: "A",
+        description: "Toggle circle size by activity (holders grow, idle shrink)",
+        action: Action::ToggleActivityRadius,
+    },
+    KeyBinding {
+        keys: &[KeyCode::B],
+        label: "B",
+        description: "Toggle burst mode (few threads change per tick)",
+        action: Action::ToggleBurst,
+    },
+    KeyBinding {
+        keys: &[KeyCode::D],
+        label: "D",
+        description: "Toggle demand mode (blocked threads retry the same resource)",
+        action: Action::ToggleDemand,
+    },
+    KeyBinding {
+        keys: &[KeyCode::L],
+        label: "L",
+        description: "Toggle blocking acquisition (waiters stick to their target)",
+        action: Action::ToggleBlocking,
+    },
+    KeyBinding {
+        keys: &[KeyCode::U],
+        label: "U",
+        description: "Toggle priority inheritance (holders take their waiters' priority)",
+        action: Action::ToggleInheritance,
+    },
+    KeyBinding {
+        keys: &[KeyCode::W],
+        label: "W",
+        description: "Next workload preset (balanced, read-heavy, write-heavy)",
+        action: Action::NextPreset,
+    },
+    KeyBinding {
+        keys: &[KeyCode::G],
+        label: "G",
+        description: "Toggle layout debug grid",
+        action: Action::ToggleDebugOverlay,
+    },
+    KeyBinding {
+        keys: &[KeyCode::E],
+        label: "E",
+        description: "Toggle explain mode (narrates the last transition)",
+        action: Action::ToggleExplain,
+    },
+    KeyBinding {
+        keys: &[KeyCode::Y],
+        label: "Y",
+        description: "Toggle idle screensaver (colors cycle when nothing runs)",
+        action: Action::ToggleScreensaver,
+    },
+    KeyBinding {
+        keys: &[KeyCode::O],
+        label: "O",
+        description: "Start/stop the guided tour",
+        action: Action::ToggleTour,
+    },
+    KeyBinding {
+        keys: &[KeyCode::P],
+        label: "P",
+        description: "Pause/resume the simulation (and the tour)",
+        action: Action::TogglePause,
+    },
+    KeyBinding {
+        keys: &[KeyCode::R],
+        label: "R",
+        description: "Freeze/unfreeze the random draw (holds still expire)",
+        action: Action::ToggleFreezeRandom,
+    },
+    KeyBinding {
+        keys: &[KeyCode::M],
+        label: "M",
+        description: "Advance one step (with --tick manual)",
+        action: Action::StepTick,
+    },
+    KeyBinding {
+        keys: &[KeyCode::Home],
+        label: "Home",
+        description: "Reset zoom and pan",
+        action: Action::CameraHome,
+    },
+    KeyBinding {
+        keys: &[
+            KeyCode::Key1,
+            KeyCode::Key2,
+            KeyCode::Key3,
+            KeyCode::Key4,
+            KeyCode::Key5,
+            KeyCode::Key6,
+            KeyCode::Key7,
+            KeyCode::Key8,
+            KeyCode::Key9,
+        ],
+        label: "1-9",
+        description: "Select thread",
+        action: Action::SelectThread(0),
+    },
+    KeyBinding {
+        keys: &[KeyCode::Tab],
+        label: "Tab",
+        description: "Select next resource",
+        action: Action::NextResource,
+    },
+    KeyBinding {
+        keys: &[KeyCode::N],
+        label: "N",
+        description: "Rename selected resource",
+        action: Action::RenameResource,
+    },
+    KeyBinding {
+        keys: &[KeyCode::Q],
+        label: "Q",
+        description: "Search resources by name (Esc clears)",
+        action: Action::SearchResources,
+    },
+    KeyBinding {
+        keys: &[KeyCode::X],
+        label: "X",
+        description: "Crash selected thread",
+        action: Action::CrashThread,
+    },
+    KeyBinding {
+        keys: &[KeyCode::V],
+        label: "V",
+        description: "Revive selected thread",
+        action: Action::ReviveThread,
+    },
+    KeyBinding {
+        keys: &[KeyCode::Equal, KeyCode::KpAdd],
+        label: "+",
+        description: "Raise priority of selected thread",
+        action: Action::RaisePriority,
+    },
+    KeyBinding {
+        keys: &[KeyCode::Minus, KeyCode::KpSubtract],
+        label: "-",
+        description: "Lower priority of selected thread",
+        action: Action::LowerPriority,
+    },
+    KeyBinding {
+        keys: &[KeyCode::Left],
+        label: "Left",
+        description: "Scroll threads left (cycle target with a thread selected)",
+        action: Action::ScrollLeft,
+    },
+    KeyBinding {
+        keys: &[KeyCode::Right],
+        label: "Right",
+        description: "Scroll threads right (cycle target with a thread selected)",
+        action: Action::ScrollRight,
+    },
+    KeyBinding {
+        keys: &[KeyCode::Enter, KeyCode::KpEnter],
+        label: "Enter",
+        description: "Selected thread reads the target (Shift: writes)",
+        action: Action::RequestTarget,
+    },
+    KeyBinding {
+        keys: &[KeyCode::PageUp],
+        label: "PgUp",
+        description: "Move the thread line up",
+        action: Action::RaiseThreadLine,
+    },
+    KeyBinding {
+        keys: &[KeyCode::PageDown],
+        label: "PgDn",
+        description: "Move the thread line down",
+        action: Action::LowerThreadLine,
+    },
+    KeyBinding {
+        keys: &[KeyCode::Comma],
+        label: ",",
+        description: "Shorten thread lines",
+        action: Action::ShortenThreads,
+    },
+    KeyBinding {
+        keys: &[KeyCode::Period],
+        label: ".",
+        description: "Lengthen thread lines",
+        action: Action::LengthenThreads,
+    },
+    KeyBinding {
+        keys: &[KeyCode::LeftBracket],
+        label: "[",
+        description: "Remove the last thread",
+        action: Action::RemoveThread,
+    },
+    KeyBinding {
+        keys: &[KeyCode::RightBracket],
+        label: "]",
+        description: "Add a thread",
+        action: Action::AddThread,
+    },
+    KeyBinding {
+        keys: &[KeyCode::F],
+        label: "F",
+        description: "Force release of selected resource",
+        action: Action::ForceRelease,
+    },
+    KeyBinding {
+        keys: &[KeyCode::S],
+        label: "S",
+        description: "Write storm: idle threads all write the selected resource",
+        action: Action::WriteStorm,
+    },
+    KeyBinding {
+        keys: &[KeyCode::K],
+        label: "K",
+        description: "Freeze/unfreeze selected resource (rejects every acquisition)",
+        action: Action::ToggleFrozen,
+    },
+    KeyBinding {
+        keys: &[KeyCode::Key0],
+        label: "0",
+        description: "Reset simulation",
+        action: Action::Reset,
+    },
+    KeyBinding {
+        keys: &[KeyCode::F12],
+        label: "F12",
+        description: "Save screenshot",
+        action: Action::Screenshot,
+    },
+    KeyBinding {
+        keys: &[KeyCode::J],
+        label: "J",
+        description: "Export state snapshot as JSON",
+        action: Action::ExportJson,
+    },
+    KeyBinding {
+        keys: &[KeyCode::Escape],
+        label: "Esc",
+        description: "Close help / deselect thread / quit",
+        action: Action::Quit,
+    },
+];
+
+/// Ações cujas teclas foram apertadas neste frame, na ordem da tabela.
+pub fn pressed_actions() -> Vec<Action> {
+    let mut actions = Vec::new();
+    for binding in KEY_BINDINGS {
+        for (pos, key) in binding.keys.iter().enumerate() {
+            if is_key_pressed(*key) {
+                actions.push(match binding.action {
+                    Action::SelectThread(_) => Action::SelectThread(pos),
+                    action => action,
+                });
+            }
+        }
+    }
+    actions
+}
+
+/// Painel semitransparente com todas as teclas, centralizado na tela.
+pub fn draw_help_overlay(theme: &Theme) {
+    let font_size = 20.0;
+    let row_height = scaled(26.0);
+    let padding = 20.0;
+    let label_width = KEY_BINDINGS
+        .iter()
+        .map(|b| text_width(b.label, font_size))
+        .fold(0.0, f32::max);
+    let description_width = KEY_BINDINGS
+        .iter()
+        .map(|b| text_width(b.description, font_size))
+        .fold(0.0, f32::max);
+
+    let width = label_width + description_width + padding * 3.0;
+    let height = row_height * (KEY_BINDINGS.len() as f32 + 1.0) + padding * 2.0;
+    let x = (screen_width() - width) * 0.5;
+    let y = (screen_height() - height) * 0.5;
+
+    // Fundo do próprio tema (contrasta com `theme.text`), quase opaco sobre a cena
+    let bg = theme.text_background;
+    draw_rectangle(x, y, width, height, Color::new(bg.r, bg.g, bg.b, 0.92));
+    draw_rectangle_lines(x, y, width, height, 2.0, theme.border);
+
+    let mut line_y = y + padding + scaled(font_size);
+    draw_label("Keys", x + padding, line_y, font_size, theme.highlight);
+    for binding in KEY_BINDINGS {
+        line_y += row_height;
+        draw_label(binding.label, x + padding, line_y, font_size, theme.highlight);
+        draw_label(
+            binding.description,
+            x + padding * 2.0 + label_width,
+            line_y,
+            font_size,
+            theme.text,
+        );
+    }
+}
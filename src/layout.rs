@@ -0,0 +1,106 @@
+use macroquad::prelude::*;
+
+/// Tamanho de tela usado antes do primeiro frame (e como referência das proporções)
+pub const REFERENCE_SCREEN: (f32, f32) = (1920.0, 1080.0);
+
+/// Margem da caixa de recursos em relação ao canto da tela
+const BOX_MARGIN: f32 = 50.0;
+const MAX_BOX_WIDTH: f32 = 500.0;
+const MAX_BOX_HEIGHT: f32 = 300.0;
+
+/// Espaço abaixo da caixa para o texto da política
+const BOX_LABEL_SPACE: f32 = 40.0;
+
+/// Altura preferida da linha horizontal das threads, como fração da tela
+pub const THREADS_TOP_RATIO: f32 = 0.45;
+
+/// Fração da largura da tela ocupada por cada metade da linha horizontal,
+/// para ela caber com margem mesmo em telas estreitas
+const THREADS_HALF_WIDTH_RATIO: f32 = 0.45;
+
+/// Faixa de baixo da tela, dividida entre a legenda (esquerda) e a linha do tempo (direita)
+const BOTTOM_BAND_HEIGHT: f32 = 180.0;
+const LEGEND_WIDTH: f32 = 220.0;
+const TIMELINE_WIDTH: f32 = 640.0;
+const SCREEN_MARGIN: f32 = 20.0;
+
+/// Zonas da tela, sem sobreposição, em que cada parte da cena se desenha.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Layout {
+    pub resource_box: Rect,
+    /// Linha horizontal, fios e caixas de texto (a linha fica no topo da zona)
+    pub threads: Rect,
+    pub legend: Rect,
+    pub timeline: Rect,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self::compute(REFERENCE_SCREEN.0, REFERENCE_SCREEN.1)
+    }
+}
+
+impl Layout {
+    /// Reparte uma tela de `sw` x `sh`: a caixa de recursos no canto de cima à esquerda
+    /// (sem passar do meio da tela, onde desce a linha vertical das threads), as threads
+    /// logo abaixo dela e a legenda e a linha do tempo na faixa de baixo.
+    pub fn compute(sw: f32, sh: f32) -> Self {
+        Self::with_threads_top(sw, sh, THREADS_TOP_RATIO)
+    }
+
+    /// Como `compute`, com a linha horizontal das threads a `threads_top_ratio`
+    /// da altura da tela (a caixa de recursos encolhe para caber acima dela).
+    pub fn with_threads_top(sw: f32, sh: f32, threads_top_ratio: f32) -> Self {
+        let band_height = BOTTOM_BAND_HEIGHT.min(sh * 0.25);
+        let band_top = sh - SCREEN_MARGIN - band_height;
+
+        let preferred_threads_top = sh * threads_top_ratio;
+        let box_width = MAX_BOX_WIDTH.min(sw * 0.5 - BOX_MARGIN - SCREEN_MARGIN).max(0.0);
+        let box_height = MAX_BOX_HEIGHT
+            .min(preferred_threads_top - BOX_MARGIN - BOX_LABEL_SPACE)
+            .max(0.0);
+        let resource_box = Rect::new(BOX_MARGIN, BOX_MARGIN, box_width, box_height);
+
+        let threads_top = preferred_threads_top.max(resource_box.bottom() + BOX_LABEL_SPACE);
+        let threads_half_width = sw * THREADS_HALF_WIDTH_RATIO;
+        let threads = Rect::new(
+            sw * 0.5 - threads_half_width,
+            threads_top,
+            threads_half_width * 2.0,
+            (band_top - SCREEN_MARGIN - threads_top).max(0.0),
+        );
+
+        let legend = Rect::new(SCREEN_MARGIN, band_top, LEGEND_WIDTH, band_height);
+        let timeline_width = TIMELINE_WIDTH.min(sw - LEGEND_WIDTH - SCREEN_MARGIN * 3.0).max(0.0);
+        let timeline = Rect::new(
+            sw - SCREEN_MARGIN - timeline_width,
+            band_top,
+            timeline_width,
+            band_height,
+        );
+
+        Self {
+            resource_box,
+            threads,
+            legend,
+            timeline,
+        }
+    }
+
+    /// Duas cenas lado a lado, cada uma com metade da largura da tela (ver `Comparison`).
+    pub fn side_by_side(sw: f32, sh: f32, threads_top_ratio: f32) -> [Self; 2] {
+        let left = Self::with_threads_top(sw * 0.5, sh, threads_top_ratio);
+        [left, left.translated(sw * 0.5)]
+    }
+
+    /// O mesmo layout deslocado `dx` para a direita.
+    fn translated(self, dx: f32) -> Self {
+        let shift = |rect: Rect| Rect::new(rect.x + dx, rect.y, rect.w, rect.h);
+        Self {
+            resource_box: shift(self.resource_box),
+            threads: shift(self.threads),
+            legend: shift(self.legend),
+            timeline: shift(self.timeline),
+        }
+    }
+}
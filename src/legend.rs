@@ -0,0 +1,39 @@
+use macroquad::prelude::*;
+
+use crate::text::{draw_label, scaled};
+use crate::theme::Theme;
+
+/// Legenda das cores, no pé de `area` (o canto inferior esquerdo da tela, no `Layout`).
+/// É o que dá significado às cores no modo compacto.
+pub fn draw_legend(theme: &Theme, area: Rect) {
+    let thread_entries = [
+        ("Reading", theme.reading),
+        ("Writing", theme.writing),
+        ("Waiting", theme.waiting),
+        ("Idle", theme.idle),
+        ("Crashed", theme.crashed),
+    ];
+    let resource_entries = [
+        ("Resource reading", theme.resource_reading),
+        ("Resource writing", theme.resource_writing),
+        ("Resource idle", theme.resource_idle),
+    ];
+
+    let font_size = 18.0;
+    let row_height = 22.0f32.max(scaled(18.0) + 4.0);
+    let rows = thread_entries.len() + resource_entries.len();
+    let mut y = area.bottom() - rows as f32 * row_height;
+    let x = area.x;
+
+    for (label, color) in thread_entries {
+        draw_circle(x + 8.0, y + 8.0, 8.0, color);
+        draw_label(label, x + 24.0, y + 14.0, font_size, theme.text);
+        y += row_height;
+    }
+    for (label, color) in resource_entries {
+        draw_rectangle(x, y, 16.0, 16.0, color);
+        draw_rectangle_lines(x, y, 16.0, 16.0, 1.0, theme.border);
+        draw_label(label, x + 24.0, y + 14.0, font_size, theme.text);
+        y += row_height;
+    }
+}
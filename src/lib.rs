@@ -0,0 +1,32 @@
+// Módulos do visualizador: a simulação (recursos, threads, políticas) e o que a desenha
+pub mod alarm;
+pub mod camera;
+pub mod chaos;
+pub mod clock;
+pub mod compare;
+pub mod config;
+pub mod debug_overlay;
+pub mod drag;
+pub mod keys;
+pub mod layout;
+pub mod legend;
+pub mod logger;
+pub mod policy;
+pub mod preset;
+pub mod recording;
+pub mod repl;
+pub mod resource_box;
+pub mod scenario;
+pub mod screensaver;
+pub mod screenshot;
+pub mod simulation;
+pub mod stats;
+pub mod text;
+pub mod text_input;
+pub mod theme;
+pub mod threads;
+pub mod tick;
+pub mod timeline;
+pub mod tour;
+pub mod transitions;
+pub mod workers;
@@ -0,0 +1,75 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Mutex;
+
+/// Níveis do log, do mais grave ao mais detalhado. Ligar um nível liga também
+/// todos os anteriores.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Locks envenenados, regras de lock violadas e falhas em geral (o padrão)
+    Error = 1,
+    /// Mudanças de estado das threads
+    Info = 2,
+    /// Aquisições recusadas e por quê
+    Debug = 3,
+    /// Cada checagem de consistência, mesmo as que passam
+    Trace = 4,
+}
+
+/// Nomes aceitos por `--log-level` ("off" desliga)
+pub const LOG_LEVELS: &[&str] = &["off", "error", "info", "debug", "trace"];
+
+impl Level {
+    pub fn name(self) -> &'static str {
+        LOG_LEVELS[self as usize]
+    }
+
+    /// Nível pelo nome de `LOG_LEVELS`; `Some(None)` para "off".
+    pub fn from_name(name: &str) -> Option<Option<Self>> {
+        match name {
+            "off" => Some(None),
+            "error" => Some(Some(Self::Error)),
+            "info" => Some(Some(Self::Info)),
+            "debug" => Some(Some(Self::Debug)),
+            "trace" => Some(Some(Self::Trace)),
+            _ => None,
+        }
+    }
+}
+
+/// Nível mais detalhado ligado (0 = log desligado, o padrão)
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(0);
+/// Para onde vão as linhas (None = stderr)
+static OUTPUT: Mutex<Option<File>> = Mutex::new(None);
+
+/// Liga o log até `level` (None desliga), escrevendo no arquivo `path` (criado
+/// do zero) ou, sem arquivo, no stderr.
+pub fn init(level: Option<Level>, path: Option<&str>) -> Result<(), String> {
+    let file = match path {
+        Some(path) => Some(File::create(path).map_err(|e| format!("não foi possível criar {}: {}", path, e))?),
+        None => None,
+    };
+    *OUTPUT.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = file;
+    MAX_LEVEL.store(level.map_or(0, |l| l as u8), Ordering::Relaxed);
+    Ok(())
+}
+
+/// O nível `level` está ligado? Uma leitura atômica, para o caminho sem log ser barato.
+pub fn enabled(level: Level) -> bool {
+    level as u8 <= MAX_LEVEL.load(Ordering::Relaxed)
+}
+
+/// Escreve uma linha em `level`. A mensagem só é montada se o nível estiver ligado.
+pub fn log(level: Level, message: impl FnOnce() -> String) {
+    if !enabled(level) {
+        return;
+    }
+    let line = format!("[{}] {}\n", level.name(), message());
+    let mut output = OUTPUT.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    // Falha ao escrever o log não deve derrubar a simulação
+    let _ = match output.as_mut() {
+        Some(file) => file.write_all(line.as_bytes()),
+        None => io::stderr().write_all(line.as_bytes()),
+    };
+}
@@ -4,17 +4,38 @@ use macroquad::{
     window::{Conf, next_frame, clear_background},
 };
 
-// Importamos nossos módulos
-mod resource_box;
-mod threads;
-
-use resource_box::ResourceBox;
-use threads::{ThreadsVisualizer, ThreadState};
+use threads::alarm::Alarm;
+use threads::camera::ViewCamera;
+use threads::chaos::Chaos;
+use threads::compare::{draw_side_metrics, Comparison};
+use threads::config::Config;
+use threads::debug_overlay::draw_debug_overlay;
+use threads::drag::{DragAssign, MouseAction};
+use threads::keys::{draw_help_overlay, pressed_actions, Action};
+use threads::layout::Layout;
+use threads::legend::draw_legend;
+use threads::logger::{self, Level};
+use threads::preset::Preset;
+use threads::repl::{Command, CommandReader};
+use threads::resource_box::ResourceBox;
+use threads::scenario::Scenario;
+use threads::screensaver::{Screensaver, DEFAULT_SCREENSAVER_DELAY};
+use threads::screenshot::{save_screenshot, timestamped_file, timestamped_path};
+use threads::simulation::{build_simulation, step_simulation};
+use threads::stats::SessionStats;
+use threads::text::{draw_label, draw_wrapped_text, next_text_scale, scaled, set_font, set_text_cache, set_text_scale, text_width};
+use threads::text_input::{TextInput, TextInputEvent};
+use threads::theme::Theme;
+use threads::threads::{ThreadsVisualizer, ThreadState};
+use threads::tick::TickDriver;
+use threads::timeline::Timeline;
+use threads::tour::Tour;
+use threads::workers::{system_time, WorkerTiming, Workers};
 
 /// Configuração da janela
 pub fn screen_config() -> Conf {
     Conf {
-        window_resizable: false,
+        window_resizable: true,
         fullscreen: true,
         window_title: "threads".to_string(),
         ..Default::default()
@@ -23,33 +44,846 @@ pub fn screen_config() -> Conf {
 
 #[macroquad::main(screen_config)]
 async fn main() {
-    let resource_box = ResourceBox::new(vec2(50.0, 50.0), 5);
+    let config = match Config::from_env() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("erro: {}", err);
+            eprintln!("uso: threads [--threads N] [--resources M] [--scenario NOME|ARQUIVO] [--record ARQUIVO] [--replay ARQUIVO] [--weights P1,P2,...] [--categories C1,C2,...] [--policy reader|writer|exclusive] [--compare reader|writer|exclusive] [--seed N] [--tick real|fixed|manual] [--steps-per-second N] [--steps-per-frame N] [--workers] [--font ARQUIVO.ttf] [--text-scale X] [--no-antialias] [--no-text-cache] [--repl] [--no-tree] [--no-fios] [--no-summary] [--screensaver SEGUNDOS] [--max-readers N] [--max-writers N] [--reentrant-reads] [--depends A:B,...] [--grid] [--throughput-window SEGUNDOS] [--hotspot-decay TAXA] [--hotspot-threshold N] [--read-hold MIN,MAX] [--write-hold MIN,MAX] [--preset balanced|read-heavy|write-heavy] [--state-weights IDLE,WAITING,READING,WRITING] [--starvation-threshold SEGUNDOS] [--idle-timeout SEGUNDOS] [--cooldown SEGUNDOS] [--backoff SEGUNDOS] [--backoff-factor X] [--affinity T:R,...] [--affinity-strength P] [--chaos SEGUNDOS] [--chaos-duration SEGUNDOS] [--log-level off|error|info|debug|trace] [--log-file ARQUIVO] [--log-capacity N] [--log-overflow drop-oldest|stop]");
+            std::process::exit(2);
+        }
+    };
+    println!("configuração: {}", config);
+    if let Err(err) = logger::init(config.log_level, config.log_file.as_deref()) {
+        eprintln!("erro: {}", err);
+        std::process::exit(2);
+    }
 
-    let mut threads_vis = ThreadsVisualizer::new(8);
+    // Fonte de todos os textos; se não carregar, fica a padrão
+    if let Some(path) = &config.font {
+        match load_ttf_font(path).await {
+            Ok(mut font) => {
+                if !config.antialias {
+                    font.set_filter(FilterMode::Nearest);
+                }
+                set_font(Some(font));
+            }
+            Err(err) => logger::log(Level::Error, || {
+                format!("falha ao carregar a fonte {}: {} (usando a padrão)", path, err)
+            }),
+        }
+    }
+    set_text_scale(config.text_scale);
+    set_text_cache(config.text_cache);
 
-    let mut last_update_time = 0.0;
-    let update_interval = 2.0; // a cada 2 segundos, as threads podem mudar
+    // Comparando políticas, os dois lados precisam da mesma semente
+    let seed = config
+        .seed
+        .or_else(|| config.compare.as_ref().map(|_| ::rand::random()));
+    let (mut resource_box, mut threads_vis) = build_simulation(&config, &config.policy, seed);
+    let mut comparison = config.compare.as_ref().map(|policy| {
+        let (resource_box, threads_vis) = build_simulation(&config, policy, seed);
+        Comparison::new(resource_box, threads_vis)
+    });
+
+    // Quem faz o tempo andar: o relógio real ou passos fixos (que viram o relógio
+    // dos dois lados), com um sorteio a cada 2 segundos da simulação
+    let mut ticks = TickDriver::new(config.tick, 2.0, 0.0);
+    if let Some(clock) = ticks.clock() {
+        threads_vis.clock = clock.clone();
+        if let Some(comparison) = &mut comparison {
+            comparison.threads_vis.clock = clock;
+        }
+    }
+    if let Some(seed) = seed {
+        println!("semente: {}", seed);
+    }
+
+    if let Some(name) = &config.scenario {
+        match Scenario::load(name) {
+            Ok(scenario) => {
+                if let Some(comparison) = &mut comparison {
+                    let vis = &mut comparison.threads_vis;
+                    vis.load_scenario(scenario.clone(), vis.clock.now());
+                }
+                threads_vis.load_scenario(scenario, threads_vis.clock.now())
+            }
+            Err(err) => {
+                logger::log(Level::Error, || format!("erro no roteiro {}", err));
+                std::process::exit(2);
+            }
+        }
+    }
+
+    // Gravar esta execução e/ou refazer uma gravada (no lugar do sorteio)
+    if let Some(path) = &config.replay {
+        let now = threads_vis.clock.now();
+        if let Err(err) = threads_vis.replay_from(path, &resource_box, now) {
+            logger::log(Level::Error, || format!("erro no replay: {}", err));
+            std::process::exit(2);
+        }
+    }
+    if let Some(path) = &config.record {
+        let now = threads_vis.clock.now();
+        if let Err(err) = threads_vis.record_to(path, &resource_box, now) {
+            logger::log(Level::Error, || err.to_string());
+            std::process::exit(2);
+        }
+    }
+
+    // Modo opcional com threads de verdade: elas dirigem o estado e o loop só desenha
+    let workers = config.workers.then(|| {
+        let timing = WorkerTiming {
+            read_hold_range: threads_vis.read_hold_range,
+            write_hold_range: threads_vis.write_hold_range,
+            time_base: system_time() - threads_vis.clock.now(),
+        };
+        Workers::spawn(&resource_box, threads_vis.threads.clone(), timing)
+    });
+
+    let mut camera = ViewCamera::new();
+    // Arrastar uma thread até um recurso (esquerdo: leitura, direito: escrita)
+    let mut drag = DragAssign::new();
+    let mut theme = Theme::light();
+    let mut show_debug_overlay = false;
+    let mut show_help = false;
+    let mut explain_mode = false;
+
+    // Histórico de ocupação: uma amostra a cada 0,1 s, os últimos 60 s
+    let mut timeline = Timeline::new(0.1, 600);
+    let mut starvation_alarm = Alarm::new();
+    let mut deadlock_alarm = Alarm::new();
+    let mut screensaver = Screensaver::new(
+        config.screensaver.is_some(),
+        config.screensaver.unwrap_or(DEFAULT_SCREENSAVER_DELAY),
+    );
+    // Modo caos (--chaos): a mesma sequência de eventos nos dois lados, comparando
+    let mut chaos = config
+        .chaos
+        .map(|interval| Chaos::new(interval, config.chaos_duration, seed, threads_vis.clock.now()));
+    // Números da sessão para o resumo de saída (um por lado, comparando)
+    let mut session = [SessionStats::new(), SessionStats::new()];
+
+    // Violações das regras de leitura/escrita vistas no último frame (ver `ResourceBox::validate`)
+    let mut violations: Vec<String> = Vec::new();
+
+    // Comandos da entrada padrão (só com --repl); `pause` congela a simulação
+    let commands = config.repl.then(CommandReader::spawn);
+    let mut paused = false;
+    // Sorteio congelado (tecla R): os estados ficam, mas animações e posses seguem
+    let mut freeze_random = false;
+
+    // Tour guiado em andamento (tecla O)
+    let mut tour: Option<Tour> = None;
+
+    // Renomeação em andamento: (recurso, nome original, campo de texto)
+    let mut renaming: Option<(usize, String, TextInput)> = None;
+    // Busca de recursos por nome (tecla Q): o campo, enquanto se digita
+    let mut searching: Option<TextInput> = None;
+
+    // Tamanho de tela (e altura da linha das threads) para o qual as zonas foram calculadas
+    let mut layout_key = None;
+    let mut layouts = [Layout::default(); 2];
 
     loop {
-        clear_background(WHITE);
+        clear_background(theme.background);
+
+        // A janela mudou de tamanho (ou a linha das threads subiu/desceu): tudo se reacomoda
+        let key = (screen_width(), screen_height(), threads_vis.vertical_end_ratio);
+        if layout_key != Some(key) {
+            layout_key = Some(key);
+            layouts = on_resize(key.0, key.1, &mut threads_vis, &mut resource_box, comparison.as_mut());
+        }
+        let [layout, compare_layout] = layouts;
+        if let Some(comparison) = &mut comparison {
+            comparison.sync_modes(&threads_vis);
+        }
+
+        // Zoom (roda do mouse) e arrasto (botão do meio)
+        camera.handle_input();
+        let world_mouse = camera.screen_to_world(Vec2::from(mouse_position()));
 
-        // Desenhar a ResourceBox
-        resource_box.draw();
+        // A cena é desenhada através da câmera...
+        camera.apply();
+
+        // Quem compartilha o recurso (ou a thread) sob o mouse ou selecionado
+        resource_box.hovered_resource = resource_box.nearest_resource(world_mouse, 0.0);
+        threads_vis.hovered_thread = threads_vis.thread_at(world_mouse);
+        threads_vis.sync_sharing_highlight(&mut resource_box);
+
+        // Desenhar a ResourceBox (com a fila de cada recurso, que faz a cor pulsar)
+        resource_box.waiting = threads_vis.waiting_per_resource(resource_box.resources.len());
+        // Descanso de tela: as cores de Idle giram quando os dois lados estão parados
+        let quiet = Screensaver::is_quiet(&threads_vis)
+            && comparison.as_ref().is_none_or(|c| Screensaver::is_quiet(&c.threads_vis));
+        screensaver.update(quiet, threads_vis.clock.now());
+        let scene_theme = screensaver.theme(&theme, threads_vis.clock.now());
+        resource_box.draw(&scene_theme, threads_vis.clock.now());
 
         // Desenhar as threads
-        threads_vis.draw();
+        threads_vis.draw_affinity_lines(&resource_box, &theme);
+        threads_vis.draw_sharing_lines(&resource_box, &theme);
+        threads_vis.draw_acquisition_order(&resource_box, &theme);
+        threads_vis.draw(&scene_theme);
+        // Fora do controle manual o arrasto não pede nada, então nem mostra a linha
+        if workers.is_none() && comparison.is_none() {
+            drag.draw(world_mouse, &threads_vis, &resource_box, &theme);
+        }
+        if let Some(comparison) = &mut comparison {
+            let side = &mut comparison.resource_box;
+            side.hovered_resource = side.nearest_resource(world_mouse, 0.0);
+            comparison.threads_vis.hovered_thread = comparison.threads_vis.thread_at(world_mouse);
+            comparison.threads_vis.sync_sharing_highlight(side);
+            side.waiting = comparison.threads_vis.waiting_per_resource(side.resources.len());
+            side.draw(&scene_theme, comparison.threads_vis.clock.now());
+            comparison.threads_vis.draw_affinity_lines(side, &theme);
+            comparison.threads_vis.draw_sharing_lines(side, &theme);
+            comparison.threads_vis.draw_acquisition_order(side, &theme);
+            comparison.threads_vis.draw(&scene_theme);
+        }
+
+        if show_debug_overlay {
+            draw_debug_overlay(&resource_box, &threads_vis, &theme);
+        }
+
+        // ...e o HUD direto na tela
+        set_default_camera();
+        if let Some(comparison) = &comparison {
+            for (vis, rb, area) in [
+                (&threads_vis, &resource_box, layout.resource_box),
+                (&comparison.threads_vis, &comparison.resource_box, compare_layout.resource_box),
+            ] {
+                draw_side_metrics(vis, rb, &theme, area.right() + 20.0, area.y);
+            }
+            draw_legend(&theme, layout.legend);
+        } else {
+            threads_vis.draw_contention_header(&resource_box, &theme);
+            draw_legend(&theme, layout.legend);
+            timeline.draw(&theme, layout.timeline);
+        }
+        if let Some((idx, _, input)) = &renaming {
+            draw_rename_prompt(*idx, &input.buffer, &theme);
+        } else if let Some(input) = &searching {
+            draw_search_prompt(&input.buffer, true, &theme);
+        } else if !resource_box.search.is_empty() {
+            draw_search_prompt(&resource_box.search, false, &theme);
+        }
+        if explain_mode || tour.is_some() {
+            if let Some(transition) = threads_vis.log.last() {
+                let text = transition.explain(&threads_vis.threads, &resource_box);
+                draw_explanation(&text, layout.threads, &theme);
+            }
+        }
+        if let Some(tour) = &tour {
+            tour.draw(&theme, threads_vis.clock.now());
+        } else {
+            // Com legenda do roteiro, o aviso de pausa desce para baixo dela
+            let caption = threads_vis.scenario_caption(threads_vis.clock.now());
+            if let Some(caption) = caption {
+                draw_caption(caption, &theme);
+            }
+            let mut y = if caption.is_some() { 125.0 } else { 40.0 };
+            if paused {
+                let label = "PAUSED";
+                let x = (screen_width() - text_width(label, 32.0)) * 0.5;
+                draw_label(label, x, y, 32.0, theme.alert);
+                y += scaled(36.0);
+            }
+            if freeze_random {
+                let label = "RANDOM FROZEN";
+                let x = (screen_width() - text_width(label, 24.0)) * 0.5;
+                draw_label(label, x, y, 24.0, theme.alert);
+            }
+        }
+        starvation_alarm.draw(&theme, threads_vis.clock.now());
+        deadlock_alarm.draw(&theme, threads_vis.clock.now());
+        if let Some(chaos) = &chaos {
+            chaos.draw_banner(&threads_vis, &resource_box, &theme, threads_vis.clock.now());
+        }
+        if !violations.is_empty() {
+            draw_violations(&violations, &theme);
+        }
+        if show_help {
+            draw_help_overlay(&theme);
+        }
+
+        let now = threads_vis.clock.now();
+
+        // Com workers, o estado vem das threads de verdade.
+        // Senão, os passos da simulação deste frame (ver `step_simulation` e `TickDriver`)
+        if let Some(workers) = &workers {
+            threads_vis.sync_from_workers(workers.snapshot());
+        } else if !paused {
+            // Os dois lados da comparação andam juntos, no mesmo tick
+            // Com o sorteio congelado (tecla R) só as posses vencem; o tempo segue igual
+            for (step_now, tick_due) in ticks.steps(now) {
+                if freeze_random {
+                    threads_vis.release_expired_holds(&resource_box, step_now);
+                } else {
+                    step_simulation(&mut threads_vis, &resource_box, step_now, tick_due);
+                }
+                if let Some(comparison) = &mut comparison {
+                    let (vis, side) = (&mut comparison.threads_vis, &comparison.resource_box);
+                    if freeze_random {
+                        vis.release_expired_holds(side, step_now);
+                    } else {
+                        step_simulation(vis, side, step_now, tick_due);
+                    }
+                }
+            }
+        }
+        // Com passos fixos o relógio andou neste frame
+        let now = threads_vis.clock.now();
+
+        if let Some(chaos) = &mut chaos {
+            if !paused {
+                for change in chaos.update(&threads_vis, &resource_box, now) {
+                    change.apply(&mut threads_vis, &resource_box, now);
+                    if let Some(comparison) = &mut comparison {
+                        change.apply(&mut comparison.threads_vis, &comparison.resource_box, now);
+                    }
+                }
+            }
+        }
+
+        if let Some(current) = &mut tour {
+            if !current.update(&mut threads_vis, now) {
+                if let Some(finished) = tour.take() {
+                    finished.stop(&mut threads_vis, &resource_box);
+                }
+                ticks.restart(now);
+            }
+        }
+
+        timeline.record(&resource_box, now);
+        session[0].observe(&threads_vis, &resource_box, now);
+        if let Some(comparison) = &comparison {
+            session[1].observe(&comparison.threads_vis, &comparison.resource_box, now);
+        }
+
+        // Checagem de consistência: avisa no stderr só quando o conjunto de violações muda
+        let mut current = resource_box.validate().err().unwrap_or_default();
+        if let Some(comparison) = &comparison {
+            current.extend(comparison.resource_box.validate().err().unwrap_or_default());
+        }
+        if current != violations {
+            for violation in &current {
+                logger::log(Level::Error, || format!("regra de lock violada: {}", violation));
+            }
+            violations = current;
+        }
+
+        // Com a grade de debug (G), confere também os contadores contra as threads
+        // e conserta os desvios (com workers as threads são só uma cópia atrasada)
+        if show_debug_overlay && workers.is_none() {
+            let mut drifts = threads_vis.reconcile(&resource_box);
+            if let Some(comparison) = &comparison {
+                drifts.extend(comparison.threads_vis.reconcile(&comparison.resource_box));
+            }
+            for drift in drifts {
+                logger::log(Level::Error, || format!("contadores fora de sincronia: {}", drift));
+            }
+        }
 
-        // Chamar a função de atualização aleatória depois de "update_interval" segundos
-        let now = get_time();
-        if now - last_update_time >= update_interval {
-            threads_vis.update_threads_randomly(&resource_box);
-            last_update_time = now;
+        // Alarme de inanição: avisa só quando alguma thread passa a esperar demais
+        let starving: Vec<usize> = threads_vis
+            .starving_threads(now, threads_vis.starvation_threshold)
+            .collect();
+        if starvation_alarm.update(!starving.is_empty(), now) {
+            let names: Vec<&str> = starving
+                .iter()
+                .map(|&i| threads_vis.threads[i].name.as_str())
+                .collect();
+            logger::log(Level::Error, || format!("alerta: inanição de {}", names.join(", ")));
         }
+        // E o de deadlock: dispara quando um ciclo de espera se forma, com quem está nele
+        let deadlocked = threads_vis.deadlocked_threads();
+        if deadlock_alarm.update(!deadlocked.is_empty(), now) {
+            let names: Vec<&str> = deadlocked
+                .iter()
+                .map(|&i| threads_vis.threads[i].name.as_str())
+                .collect();
+            logger::log(Level::Error, || format!("alerta: deadlock entre {}", names.join(", ")));
+        }
+
+        // Com workers, são eles que mexem nos recursos, e comparando políticas os dois lados
+        // precisam ver as mesmas decisões: nos dois casos os controles manuais ficam desligados
+        let manual_control = workers.is_none() && comparison.is_none();
+        let mut quit = false;
 
-        if is_key_pressed(KeyCode::Escape) {
+        // Comandos do stdin, aplicados aqui entre um frame e outro
+        for command in commands.iter().flat_map(CommandReader::pending) {
+            match command {
+                Command::Set { thread, .. } | Command::Release(thread)
+                    if thread >= threads_vis.threads.len() =>
+                {
+                    logger::log(Level::Error, || format!("comando ignorado: thread {} não existe", thread + 1));
+                }
+                Command::Set { resource: Some(res_idx), .. }
+                    if res_idx >= resource_box.resources.len() =>
+                {
+                    logger::log(Level::Error, || format!("comando ignorado: recurso {} não existe", res_idx + 1));
+                }
+                Command::Set { .. } | Command::Release(_) if !manual_control => {
+                    logger::log(Level::Error, || "comando ignorado: controle manual desligado neste modo".to_string());
+                }
+                Command::Set { thread, state, resource } => {
+                    threads_vis.set_thread_resource_state(&resource_box, thread, state, resource, now);
+                }
+                Command::Release(thread) => {
+                    threads_vis.set_thread_resource_state(&resource_box, thread, ThreadState::Idle, None, now);
+                }
+                Command::Pause | Command::Resume => {
+                    paused = command == Command::Pause;
+                    if let Some(tour) = &mut tour {
+                        tour.set_paused(paused, &mut threads_vis, now);
+                    }
+                }
+                Command::Snapshot(path) => save_snapshot(&path, &threads_vis, &resource_box),
+                Command::Quit => quit = true,
+            }
+        }
+        if quit {
+            break;
+        }
+
+        // Enquanto um nome é digitado, o teclado vai só para o campo de texto
+        if let Some((idx, original, input)) = &mut renaming {
+            let event = input.update();
+            let resource = &resource_box.resources[*idx];
+            match event {
+                TextInputEvent::Editing => resource.set_name(&input.buffer),
+                TextInputEvent::Submitted if !input.buffer.trim().is_empty() => {
+                    resource.set_name(input.buffer.trim());
+                }
+                TextInputEvent::Submitted | TextInputEvent::Cancelled => resource.set_name(original),
+            }
+            if event != TextInputEvent::Editing {
+                renaming = None;
+            }
+            next_frame().await;
+            continue;
+        }
+
+        // Busca: o destaque acompanha a digitação; Enter mantém a busca e Esc a limpa
+        if let Some(input) = &mut searching {
+            let event = input.update();
+            let query = match event {
+                TextInputEvent::Cancelled => String::new(),
+                _ => input.buffer.trim().to_string(),
+            };
+            if let Some(comparison) = &mut comparison {
+                comparison.resource_box.search = query.clone();
+            }
+            resource_box.search = query;
+            if event != TextInputEvent::Editing {
+                searching = None;
+            }
+            next_frame().await;
+            continue;
+        }
+
+        // Clique seleciona (thread ou recurso); arrastar uma thread até um recurso pede o lock
+        match drag.update(world_mouse, &threads_vis, &resource_box) {
+            Some(MouseAction::Assign(request)) if manual_control => {
+                threads_vis.set_thread_resource_state(
+                    &resource_box,
+                    request.thread,
+                    request.mode,
+                    Some(request.resource),
+                    now,
+                );
+            }
+            Some(MouseAction::SelectThread(i)) => select_thread(&mut threads_vis, &mut resource_box, i),
+            Some(MouseAction::SelectResource(idx)) => resource_box.selected_resource = Some(idx),
+            Some(MouseAction::Deselect) => {
+                threads_vis.selected_thread = None;
+                resource_box.selected_resource = None;
+                resource_box.candidate_resource = None;
+            }
+            _ => {}
+        }
+        for action in pressed_actions() {
+            match action {
+                Action::ToggleHelp => show_help = !show_help,
+                Action::ToggleTheme => theme = theme.toggled(),
+                Action::NextTextScale => set_text_scale(next_text_scale()),
+                Action::ToggleCompact => {
+                    threads_vis.compact = !threads_vis.compact;
+                    resource_box.compact = threads_vis.compact;
+                }
+                Action::ToggleBurst => threads_vis.burst_mode = !threads_vis.burst_mode,
+                Action::ToggleIds => threads_vis.show_ids = !threads_vis.show_ids,
+                Action::ToggleActivityRadius => {
+                    threads_vis.activity_radius = !threads_vis.activity_radius
+                }
+                Action::ToggleDemand => threads_vis.demand_mode = !threads_vis.demand_mode,
+                Action::NextPreset => {
+                    let preset = threads_vis.preset.map_or(Preset::ReadHeavy, Preset::next);
+                    threads_vis.apply_preset(preset);
+                }
+                Action::ToggleBlocking => threads_vis.blocking_mode = !threads_vis.blocking_mode,
+                Action::ToggleInheritance => {
+                    threads_vis.priority_inheritance = !threads_vis.priority_inheritance
+                }
+                Action::ToggleDebugOverlay => show_debug_overlay = !show_debug_overlay,
+                Action::ToggleExplain => explain_mode = !explain_mode,
+                Action::ToggleScreensaver => screensaver.enabled = !screensaver.enabled,
+                Action::ToggleTour => match tour.take() {
+                    Some(current) => {
+                        current.stop(&mut threads_vis, &resource_box);
+                        ticks.restart(now);
+                    }
+                    None if manual_control => {
+                        paused = false;
+                        match Tour::start(&mut threads_vis, &resource_box, now) {
+                            Ok(started) => tour = Some(started),
+                            Err(err) => logger::log(Level::Error, || err.to_string()),
+                        }
+                    }
+                    None => logger::log(Level::Error, || {
+                        "o tour precisa do controle manual (sem --workers nem --compare)".to_string()
+                    }),
+                },
+                Action::TogglePause => {
+                    paused = !paused;
+                    if let Some(tour) = &mut tour {
+                        tour.set_paused(paused, &mut threads_vis, now);
+                    }
+                }
+                Action::ToggleFreezeRandom => freeze_random = !freeze_random,
+                Action::StepTick => ticks.request_step(),
+                Action::CameraHome => camera.home(),
+                Action::SelectThread(i) => select_thread(&mut threads_vis, &mut resource_box, i),
+                Action::NextResource => resource_box.select_next_resource(),
+                Action::RenameResource => {
+                    if let Some(idx) = resource_box.selected_resource {
+                        let original = resource_box.resources[idx].name();
+                        let input = TextInput::new(&original, MAX_RESOURCE_NAME_LEN);
+                        renaming = Some((idx, original, input));
+                    }
+                }
+                Action::SearchResources => {
+                    searching = Some(TextInput::new(&resource_box.search, MAX_RESOURCE_NAME_LEN));
+                }
+                // 'X' derruba a thread selecionada (libera o recurso) e 'V' a revive em Idle
+                Action::CrashThread => {
+                    if let Some(selected) = threads_vis.selected_thread.filter(|_| manual_control) {
+                        threads_vis.set_thread_resource_state(&resource_box, selected, ThreadState::Crashed, None, now);
+                    }
+                }
+                Action::ReviveThread => {
+                    if let Some(selected) = threads_vis.selected_thread.filter(|_| manual_control) {
+                        if threads_vis.threads[selected].state == ThreadState::Crashed {
+                            threads_vis.set_thread_resource_state(&resource_box, selected, ThreadState::Idle, None, now);
+                        }
+                    }
+                }
+                Action::RaisePriority | Action::LowerPriority => {
+                    if let Some(selected) = threads_vis.selected_thread.filter(|_| manual_control) {
+                        let delta = if action == Action::RaisePriority { 1 } else { -1 };
+                        threads_vis.adjust_priority(selected, delta);
+                    }
+                }
+                // Com uma thread selecionada, as setas escolhem o recurso que ela vai pedir
+                Action::ScrollLeft | Action::ScrollRight => {
+                    let delta = if action == Action::ScrollLeft { -1 } else { 1 };
+                    if threads_vis.selected_thread.is_some() && manual_control {
+                        resource_box.cycle_candidate(delta);
+                    } else {
+                        threads_vis.scroll_threads(delta);
+                    }
+                }
+                Action::RequestTarget => {
+                    let selected = threads_vis.selected_thread.filter(|_| manual_control);
+                    if let (Some(thread), Some(res_idx)) = (selected, resource_box.candidate_resource) {
+                        let mode = if is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift) {
+                            ThreadState::Writing
+                        } else {
+                            ThreadState::Reading
+                        };
+                        threads_vis.set_thread_resource_state(&resource_box, thread, mode, Some(res_idx), now);
+                    }
+                }
+                Action::RaiseThreadLine => {
+                    threads_vis.set_vertical_end_ratio(threads_vis.vertical_end_ratio - 0.02)
+                }
+                Action::LowerThreadLine => {
+                    threads_vis.set_vertical_end_ratio(threads_vis.vertical_end_ratio + 0.02)
+                }
+                Action::ShortenThreads => {
+                    threads_vis.set_thread_line_length(threads_vis.thread_line_length - 10.0)
+                }
+                Action::LengthenThreads => {
+                    threads_vis.set_thread_line_length(threads_vis.thread_line_length + 10.0)
+                }
+                Action::RemoveThread => {
+                    if manual_control {
+                        threads_vis.remove_thread(&resource_box, now);
+                    }
+                }
+                Action::AddThread => {
+                    if manual_control {
+                        threads_vis.add_thread();
+                    }
+                }
+                Action::ForceRelease => {
+                    if let Some(idx) = resource_box.selected_resource.filter(|_| manual_control) {
+                        threads_vis.force_release_resource(&resource_box, idx, now);
+                    }
+                }
+                Action::WriteStorm => {
+                    if manual_control {
+                        // Sem recurso selecionado, vai no primeiro
+                        let target = resource_box.selected_resource.unwrap_or(0);
+                        threads_vis.inject_write_storm(&resource_box, target, WRITE_STORM_SIZE, now);
+                    }
+                }
+                // Vale nos dois lados da comparação, para a carga continuar igual
+                Action::ToggleFrozen => {
+                    if let Some(idx) = resource_box.selected_resource {
+                        let frozen = !resource_box.is_frozen(idx);
+                        threads_vis.set_resource_frozen(&resource_box, idx, frozen, now);
+                        if let Some(comparison) = &mut comparison {
+                            comparison.threads_vis.set_resource_frozen(&comparison.resource_box, idx, frozen, now);
+                        }
+                    }
+                }
+                Action::Reset => {
+                    if manual_control {
+                        threads_vis.reset(&resource_box);
+                        resource_box.reset_stats();
+                        session[0] = SessionStats::new();
+                        ticks.restart(now);
+                        threads_vis.scenario_start = now;
+                        timeline.clear();
+                    }
+                }
+                // O frame já está todo desenhado aqui
+                Action::Screenshot => {
+                    let path = timestamped_path();
+                    match save_screenshot(&path) {
+                        Ok(()) => logger::log(Level::Info, || format!("captura salva em {}", path)),
+                        Err(err) => logger::log(Level::Error, || format!("falha ao salvar a captura {}: {}", path, err)),
+                    }
+                }
+                Action::ExportJson => {
+                    save_snapshot(&timestamped_file("snapshot", "json"), &threads_vis, &resource_box)
+                }
+                // Com a ajuda aberta, Esc só fecha o painel; com uma thread selecionada, tira a seleção
+                Action::Quit if show_help => show_help = false,
+                Action::Quit if threads_vis.selected_thread.is_some() => {
+                    threads_vis.selected_thread = None;
+                    resource_box.candidate_resource = None;
+                }
+                Action::Quit => quit = true,
+            }
+        }
+        if quit {
             break;
         }
 
         next_frame().await;
     }
+
+    // O Esc para os workers na hora: sinaliza, junta e pega o estado final, já sem
+    // nenhum recurso em uso, antes do resumo
+    if let Some(workers) = workers {
+        threads_vis.sync_from_workers(workers.stop());
+    }
+
+    // Nada fica congelado nem derrubado pelo caos depois de sair
+    if let Some(chaos) = &mut chaos {
+        let now = threads_vis.clock.now();
+        for change in chaos.stop() {
+            change.apply(&mut threads_vis, &resource_box, now);
+            if let Some(comparison) = &mut comparison {
+                change.apply(&mut comparison.threads_vis, &comparison.resource_box, now);
+            }
+        }
+    }
+
+    // Resumo da sessão até um novo Esc (pulado com --no-summary)
+    if config.summary {
+        next_frame().await;
+        let mut sides = vec![(&threads_vis, &resource_box, &session[0])];
+        if let Some(comparison) = &comparison {
+            sides.push((&comparison.threads_vis, &comparison.resource_box, &session[1]));
+        }
+        while !is_key_pressed(KeyCode::Escape) {
+            clear_background(theme.background);
+            draw_session_summary(&sides, &theme);
+            next_frame().await;
+        }
+    }
+
+    shutdown(&mut threads_vis, &resource_box);
+    if let Some(comparison) = &mut comparison {
+        shutdown(&mut comparison.threads_vis, &comparison.resource_box);
+    }
+}
+
+/// Recalcula as zonas da cena para uma tela de `sw` x `sh` (metade para cada lado,
+/// comparando) e reposiciona a caixa de recursos e as threads de cada simulação.
+fn on_resize(
+    sw: f32,
+    sh: f32,
+    threads_vis: &mut ThreadsVisualizer,
+    resource_box: &mut ResourceBox,
+    comparison: Option<&mut Comparison>,
+) -> [Layout; 2] {
+    let ratio = threads_vis.vertical_end_ratio;
+    let layouts = if comparison.is_some() {
+        Layout::side_by_side(sw, sh, ratio)
+    } else {
+        [Layout::with_threads_top(sw, sh, ratio); 2]
+    };
+    let sides = std::iter::once((resource_box, threads_vis))
+        .chain(comparison.map(|c| (&mut c.resource_box, &mut c.threads_vis)));
+    for ((resource_box, threads_vis), layout) in sides.zip(layouts) {
+        resource_box.set_bounds(layout.resource_box);
+        threads_vis.area = layout.threads;
+        // Com a fileira mais curta, a rolagem não pode passar da última thread
+        threads_vis.scroll_threads(0);
+    }
+    layouts
+}
+
+/// Seleciona a thread `i` (se existir). O alvo começa no que ela já segura ou
+/// está tentando pegar.
+fn select_thread(threads_vis: &mut ThreadsVisualizer, resource_box: &mut ResourceBox, i: usize) {
+    if let Some(thread) = threads_vis.threads.get(i) {
+        resource_box.candidate_resource = thread.resource_in_use.or(thread.intended_resource);
+        threads_vis.selected_thread = Some(i);
+    }
+}
+
+/// Encerramento (com os workers já parados): devolve todos os recursos e mostra as
+/// estatísticas finais e qualquer contador que não tenha voltado a zero.
+fn shutdown(threads_vis: &mut ThreadsVisualizer, resource_box: &ResourceBox) {
+    let policy = resource_box.resources.first().map_or("-", |r| r.policy.name());
+    println!("encerrando ({}) depois de {:.1}s", policy, threads_vis.clock.now());
+    for (idx, resource) in resource_box.resources.iter().enumerate() {
+        let holds: usize = resource_box.hold_duration_histogram(idx).iter().map(|&(_, n)| n).sum();
+        println!("  {}: {} posses", resource.read_inner().name, holds);
+    }
+    let problems = threads_vis.shutdown(resource_box);
+    for problem in &problems {
+        logger::log(Level::Error, || format!("ao encerrar: {}", problem));
+    }
+    if problems.is_empty() {
+        println!("  contadores zerados");
+    }
+}
+
+/// Grava o estado atual em JSON (ver `ThreadsVisualizer::to_json`).
+fn save_snapshot(path: &str, threads_vis: &ThreadsVisualizer, resource_box: &ResourceBox) {
+    match std::fs::write(path, threads_vis.to_json(resource_box)) {
+        Ok(()) => logger::log(Level::Info, || format!("estado salvo em {}", path)),
+        Err(err) => logger::log(Level::Error, || format!("falha ao salvar o estado {}: {}", path, err)),
+    }
+}
+
+/// Quantas threads Idle entram numa tempestade de escritas (ver `Action::WriteStorm`)
+const WRITE_STORM_SIZE: usize = 4;
+
+/// Maior nome aceito ao renomear um recurso
+const MAX_RESOURCE_NAME_LEN: usize = 40;
+
+/// Campo de renomeação, centralizado na parte de baixo da tela
+fn draw_rename_prompt(idx: usize, buffer: &str, theme: &Theme) {
+    let text = format!("Rename R{}: {}_  (Enter confirms, Esc cancels)", idx + 1, buffer);
+    let font_size = 24.0;
+    let width = text_width(&text, font_size);
+    let x = (screen_width() - width) * 0.5;
+    let y = screen_height() - 30.0;
+    draw_rectangle(x - 10.0, y - scaled(font_size), width + 20.0, scaled(font_size) + 12.0, theme.text_background);
+    draw_label(&text, x, y, font_size, theme.text);
+}
+
+/// Busca de recursos no pé da tela: o campo sendo digitado (`editing`) ou a busca
+/// que ficou valendo
+fn draw_search_prompt(query: &str, editing: bool, theme: &Theme) {
+    let text = if editing {
+        format!("Search: {}_  (Enter keeps, Esc clears)", query)
+    } else {
+        format!("Search: {}  (Q to change)", query)
+    };
+    let font_size = 24.0;
+    let width = text_width(&text, font_size);
+    let x = (screen_width() - width) * 0.5;
+    let y = screen_height() - 30.0;
+    draw_rectangle(x - 10.0, y - scaled(font_size), width + 20.0, scaled(font_size) + 12.0, theme.text_background);
+    draw_label(&text, x, y, font_size, theme.highlight);
+}
+
+/// Frase do modo explicação, numa faixa no pé da zona das threads
+fn draw_explanation(text: &str, area: Rect, theme: &Theme) {
+    let font_size = 22.0;
+    let height = 60.0;
+    let y = area.bottom() - height;
+    draw_rectangle(area.x, y, area.w, height, theme.text_background);
+    draw_wrapped_text(text, area.x + 10.0, y + 6.0, area.w - 20.0, height - 6.0, font_size, theme.text);
+}
+
+/// Legenda do roteiro numa faixa no topo da tela (como a do tour)
+fn draw_caption(text: &str, theme: &Theme) {
+    let width = screen_width() * 0.6;
+    let height = 80.0;
+    let x = (screen_width() - width) * 0.5;
+    draw_rectangle(x, 10.0, width, height, theme.text_background);
+    draw_rectangle_lines(x, 10.0, width, height, 2.0, theme.highlight);
+    draw_wrapped_text(text, x + 10.0, 16.0, width - 20.0, height - 12.0, 22.0, theme.text);
+}
+
+/// Tela de resumo da sessão: uma coluna por lado (política) com os números de
+/// `SessionStats` e o tempo total.
+fn draw_session_summary(sides: &[(&ThreadsVisualizer, &ResourceBox, &SessionStats)], theme: &Theme) {
+    let font_size = 24.0;
+    let line_height = scaled(font_size) + 8.0;
+    let column_width = screen_width() / sides.len() as f32;
+    let top = screen_height() * 0.25;
+    let title = "Session summary";
+    let x = (screen_width() - text_width(title, 40.0)) * 0.5;
+    draw_label(title, x, top - scaled(40.0), 40.0, theme.highlight);
+
+    for (col, (threads_vis, resource_box, stats)) in sides.iter().enumerate() {
+        let policy = resource_box.resources.first().map_or("-", |r| r.policy.name());
+        let most_starved = match stats.longest_wait {
+            Some((i, wait)) => format!("{} ({:.1}s)", threads_vis.threads.get(i).map_or("-", |t| t.name.as_str()), wait),
+            None => "none".to_string(),
+        };
+        let lines = [
+            format!("Policy: {}", policy),
+            format!("Total acquisitions: {}", threads_vis.throughput.total()),
+            format!("Peak concurrent readers: {}", stats.peak_readers),
+            format!("Deadlocks detected: {}", stats.deadlocks),
+            format!("Most starved: {}", most_starved),
+            format!("Runtime: {:.1}s", threads_vis.clock.now()),
+        ];
+        let x = column_width * col as f32 + 40.0;
+        for (i, line) in lines.iter().enumerate() {
+            let color = if i == 0 { theme.highlight } else { theme.text };
+            draw_label(line, x, top + line_height * (i as f32 + 1.0), font_size, color);
+        }
+    }
+
+    let hint = "Press Esc to quit";
+    let x = (screen_width() - text_width(hint, 20.0)) * 0.5;
+    draw_label(hint, x, screen_height() * 0.85, 20.0, theme.text);
+}
+
+/// Faixa de erro no topo da tela com as violações das regras de lock
+fn draw_violations(violations: &[String], theme: &Theme) {
+    let font_size = 24.0;
+    let line_height = scaled(font_size) + 6.0;
+    let height = line_height * (violations.len() as f32 + 1.0) + 10.0;
+    let width = screen_width() * 0.6;
+    let x = (screen_width() - width) * 0.5;
+    draw_rectangle(x, 0.0, width, height, theme.alert);
+    let mut y = scaled(font_size) + 4.0;
+    draw_label("LOCK INVARIANT VIOLATED", x + 10.0, y, font_size, WHITE);
+    for violation in violations {
+        y += line_height;
+        draw_label(violation, x + 10.0, y, font_size, WHITE);
+    }
 }
@@ -0,0 +1,91 @@
+use crate::resource_box::LockSnapshot;
+
+/// Regras de aquisição de um recurso.
+///
+/// `Resource::try_set_reading`/`try_set_writing` consultam a política com um retrato
+/// do recurso (contadores, limite de leitores e fila de espera) e só contam a aquisição
+/// se ela permitir. A consulta pode se repetir se outra thread mexer nos contadores
+/// no meio, então deve ser pura.
+pub trait LockPolicy: Send + Sync {
+    /// Nome curto para exibição e linha de comando
+    fn name(&self) -> &'static str;
+
+    /// Um novo leitor pode entrar?
+    fn can_read(&self, state: &LockSnapshot) -> bool;
+
+    /// Um novo escritor pode entrar?
+    fn can_write(&self, state: &LockSnapshot) -> bool;
+}
+
+/// Comportamento clássico: leitores compartilham o recurso, e só não entram
+/// se houver um escritor. O escritor precisa do recurso totalmente livre.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ReaderPreferring;
+
+impl LockPolicy for ReaderPreferring {
+    fn name(&self) -> &'static str {
+        "reader"
+    }
+
+    fn can_read(&self, state: &LockSnapshot) -> bool {
+        state.write_count == 0
+    }
+
+    fn can_write(&self, state: &LockSnapshot) -> bool {
+        state.read_count == 0 && state.write_count == 0
+    }
+}
+
+/// Preferência de escrita: enquanto houver um escritor na fila, nenhum leitor novo
+/// entra, e o escritor pega o recurso assim que os leitores atuais saírem. Escritores
+/// não passam fome; em troca, com muita escrita, quem passa fome são os leitores.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WriterPreferring;
+
+impl LockPolicy for WriterPreferring {
+    fn name(&self) -> &'static str {
+        "writer"
+    }
+
+    fn can_read(&self, state: &LockSnapshot) -> bool {
+        state.write_count == 0 && state.waiting_writers == 0
+    }
+
+    fn can_write(&self, state: &LockSnapshot) -> bool {
+        state.read_count == 0 && state.write_count == 0
+    }
+}
+
+/// Mutex simples: uma thread por vez, seja lendo ou escrevendo.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Exclusive;
+
+impl LockPolicy for Exclusive {
+    fn name(&self) -> &'static str {
+        "exclusive"
+    }
+
+    fn can_read(&self, state: &LockSnapshot) -> bool {
+        state.read_count == 0 && state.write_count == 0
+    }
+
+    fn can_write(&self, state: &LockSnapshot) -> bool {
+        state.read_count == 0 && state.write_count == 0
+    }
+}
+
+/// Fábrica de políticas, usada por `ResourceBox::new` (uma política por recurso)
+pub type PolicyFactory = fn() -> Box<dyn LockPolicy>;
+
+/// Nomes aceitos em `--policy`
+pub const POLICY_NAMES: &[&str] = &["reader", "writer", "exclusive"];
+
+/// Fábrica da política com o nome dado.
+pub fn policy_factory(name: &str) -> Option<PolicyFactory> {
+    match name {
+        "reader" => Some(|| Box::new(ReaderPreferring)),
+        "writer" => Some(|| Box::new(WriterPreferring)),
+        "exclusive" => Some(|| Box::new(Exclusive)),
+        _ => None,
+    }
+}
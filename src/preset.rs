@@ -0,0 +1,53 @@
+/// Cargas de exemplo que mudam de uma vez os pesos do sorteio de estado e os
+/// tempos de posse (ver `ThreadsVisualizer::apply_preset`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Preset {
+    /// Muitas leituras longas e simultâneas, escritas raras e curtas
+    ReadHeavy,
+    /// Escritas frequentes e longas: quase todo mundo acaba esperando
+    WriteHeavy,
+    /// Os valores padrão
+    Balanced,
+}
+
+/// Todas as cargas, na ordem em que a tecla passa por elas
+pub const PRESETS: &[Preset] = &[Preset::Balanced, Preset::ReadHeavy, Preset::WriteHeavy];
+
+impl Preset {
+    /// Nome na tela e em `--preset`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::ReadHeavy => "read-heavy",
+            Preset::WriteHeavy => "write-heavy",
+            Preset::Balanced => "balanced",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        PRESETS.iter().copied().find(|p| p.name() == name)
+    }
+
+    /// A carga seguinte em `PRESETS` (volta à primeira depois da última).
+    pub fn next(self) -> Self {
+        let pos = PRESETS.iter().position(|&p| p == self).unwrap_or(0);
+        PRESETS[(pos + 1) % PRESETS.len()]
+    }
+
+    /// Pesos do sorteio de estado (Idle, Waiting, Reading, Writing).
+    pub fn state_weights(self) -> [f32; 4] {
+        match self {
+            Preset::ReadHeavy => [1.0, 0.5, 4.0, 0.3],
+            Preset::WriteHeavy => [1.0, 0.5, 0.5, 3.0],
+            Preset::Balanced => [1.0; 4],
+        }
+    }
+
+    /// Intervalos (min, max) em segundos das posses de leitura e de escrita.
+    pub fn hold_ranges(self) -> ((f64, f64), (f64, f64)) {
+        match self {
+            Preset::ReadHeavy => ((2.0, 5.0), (0.5, 1.5)),
+            Preset::WriteHeavy => ((0.5, 1.5), (2.0, 6.0)),
+            Preset::Balanced => ((1.0, 4.0), (1.5, 6.0)),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use std::fs::File;
+use std::io::{self, LineWriter, Write};
+
+use crate::scenario::ScenarioStep;
+use crate::threads::ThreadState;
+
+/// Grava as decisões de uma execução (thread, estado pedido, recurso) e os congelamentos
+/// de recursos no formato de `Scenario`, com os tempos contados do começo da gravação. Como um roteiro refaz cada
+/// pedido na mesma ordem, inclusive os que falham, carregar o arquivo de volta
+/// (`ThreadsVisualizer::replay_from`) reproduz a execução sem depender do sorteio.
+#[derive(Debug)]
+pub struct Recorder {
+    /// Uma linha por decisão, gravada na hora (não se perde nada se o programa fechar)
+    out: LineWriter<File>,
+    start: f64,
+}
+
+impl Recorder {
+    /// Cria (ou sobrescreve) o arquivo `path`; os tempos passam a contar de `now`.
+    pub fn create(path: &str, now: f64) -> io::Result<Self> {
+        Ok(Self {
+            out: LineWriter::new(File::create(path)?),
+            start: now,
+        })
+    }
+
+    pub fn record(
+        &mut self,
+        now: f64,
+        thread: usize,
+        state: ThreadState,
+        resource: Option<usize>,
+    ) -> io::Result<()> {
+        let step = ScenarioStep {
+            time: (now - self.start).max(0.0),
+            thread,
+            state,
+            resource,
+            priority: None,
+            frozen: None,
+        };
+        writeln!(self.out, "{}", step)
+    }
+
+    /// Grava o congelamento (ou descongelamento) do recurso `resource` em `now`.
+    pub fn record_frozen(&mut self, now: f64, resource: usize, frozen: bool) -> io::Result<()> {
+        let step = ScenarioStep {
+            time: (now - self.start).max(0.0),
+            thread: 0,
+            state: ThreadState::Idle,
+            resource: Some(resource),
+            priority: None,
+            frozen: Some(frozen),
+        };
+        writeln!(self.out, "{}", step)
+    }
+}
@@ -0,0 +1,118 @@
+use std::io::BufRead;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use crate::logger::{self, Level};
+use crate::scenario::parse_index;
+use crate::threads::ThreadState;
+
+/// Um comando lido da entrada padrão.
+///
+/// Gramática (um comando por linha, índices começam em 1 como na tela,
+/// `#` inicia comentário):
+///
+/// ```text
+/// set <thread> read|write <recurso>   pede o recurso (pode ficar esperando)
+/// set <thread> idle|wait              muda o estado sem recurso
+/// release <thread>                    libera o que a thread segura (fica Idle)
+/// pause | resume                      para / volta o sorteio e as posses
+/// snapshot <arquivo>                  salva o estado em JSON (ver `to_json`)
+/// quit                                fecha o programa
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Set {
+        thread: usize,
+        state: ThreadState,
+        resource: Option<usize>,
+    },
+    Release(usize),
+    Pause,
+    Resume,
+    Snapshot(String),
+    Quit,
+}
+
+impl Command {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let thread = |token: Option<&&str>| {
+            token
+                .and_then(|t| parse_index(t))
+                .ok_or_else(|| "número de thread inválido".to_string())
+        };
+        let command = match tokens.first().copied() {
+            Some("set") => {
+                let thread = thread(tokens.get(1))?;
+                let (state, needs_resource) = match tokens.get(2).copied() {
+                    Some("read") => (ThreadState::Reading, true),
+                    Some("write") => (ThreadState::Writing, true),
+                    Some("wait") => (ThreadState::Waiting, false),
+                    Some("idle") => (ThreadState::Idle, false),
+                    _ => return Err("estado desconhecido (use read, write, wait ou idle)".to_string()),
+                };
+                let resource = match (needs_resource, tokens.get(3)) {
+                    (true, Some(tok)) => Some(
+                        parse_index(tok).ok_or_else(|| "número de recurso inválido".to_string())?,
+                    ),
+                    (true, None) => return Err("read/write precisa de um recurso".to_string()),
+                    (false, _) => None,
+                };
+                let expected = if needs_resource { 4 } else { 3 };
+                if tokens.len() > expected {
+                    return Err("argumentos sobrando".to_string());
+                }
+                Command::Set {
+                    thread,
+                    state,
+                    resource,
+                }
+            }
+            Some("release") if tokens.len() == 2 => Command::Release(thread(tokens.get(1))?),
+            Some("pause") if tokens.len() == 1 => Command::Pause,
+            Some("resume") if tokens.len() == 1 => Command::Resume,
+            Some("snapshot") if tokens.len() == 2 => Command::Snapshot(tokens[1].to_string()),
+            Some("quit") if tokens.len() == 1 => Command::Quit,
+            Some(_) => return Err("comando desconhecido ou com argumentos errados".to_string()),
+            None => return Err("linha vazia".to_string()),
+        };
+        Ok(command)
+    }
+}
+
+/// Lê comandos da entrada padrão numa thread à parte. O `main` pega os que
+/// chegaram com `pending` entre um frame e outro, e só ele mexe na simulação.
+pub struct CommandReader {
+    rx: Receiver<Command>,
+}
+
+impl CommandReader {
+    pub fn spawn() -> Self {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for line in std::io::stdin().lock().lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if line.split('#').next().unwrap_or("").trim().is_empty() {
+                    continue;
+                }
+                match Command::parse(&line) {
+                    Ok(command) => {
+                        if tx.send(command).is_err() {
+                            break;
+                        }
+                    }
+                    Err(err) => logger::log(Level::Error, || format!("comando inválido \"{}\": {}", line.trim(), err)),
+                }
+            }
+        });
+        Self { rx }
+    }
+
+    /// Comandos que chegaram desde a última chamada, na ordem.
+    pub fn pending(&self) -> Vec<Command> {
+        self.rx.try_iter().collect()
+    }
+}
@@ -1,132 +1,764 @@
+use ::rand::Rng;
 use macroquad::prelude::*;
-use std::sync::{Arc, RwLock};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-/// Dados internos de um Recurso.
+use crate::logger::{self, Level};
+use crate::policy::{LockPolicy, PolicyFactory};
+use crate::text::{draw_label, draw_wrapped_text, text_width};
+use crate::theme::Theme;
+
+/// Largura, em segundos, de cada faixa do histograma de posses
+pub const HOLD_BUCKET_WIDTH: f64 = 0.5;
+/// Número de faixas do histograma; a última junta todas as posses mais longas
+pub const HOLD_BUCKETS: usize = 16;
+
+/// Dados internos de um Recurso (os contadores de leitores/escritores ficam fora,
+/// em `Resource::counts`, para as aquisições não precisarem do lock).
 pub struct ResourceInner {
     pub name: String,
     pub pos: Vec2,
     pub width: f32,
     pub height: f32,
 
+    /// Peso no sorteio de recursos (recursos "quentes" têm peso maior)
+    pub weight: f32,
+
+    /// Grupo do recurso na caixa ("Disks", "Network"...); None = grupo padrão
+    pub category: Option<String>,
+
+    /// Contenção recente: tentativas que falharam, decaindo com o tempo.
+    /// `contention` vale no instante `contention_time` e cai a uma taxa
+    /// `contention_decay` por segundo (decaimento exponencial).
+    pub contention: f32,
+    pub contention_time: f64,
+    pub contention_decay: f32,
+
+    /// Escrita em andamento: início e fim previsto (None = sem prazo), ver `busy_fraction`
+    pub write_hold: Option<(f64, Option<f64>)>,
+
+    /// Quantas posses (leitura ou escrita) duraram o tempo de cada faixa,
+    /// ver `HOLD_BUCKET_WIDTH`
+    pub hold_histogram: [usize; HOLD_BUCKETS],
+
+    /// Recurso "fora do ar": recusa toda aquisição (quem já segura continua até soltar)
+    pub frozen: bool,
+
+    /// Quem está lendo (índice da thread) e quantas vezes entrou, com as leituras
+    /// reentrantes ligadas (ver `Resource::set_reentrant_reads`)
+    pub read_holders: HashMap<usize, u32>,
+
+    /// Quem segura o recurso agora (índice da thread) e desde quando, na ordem em
+    /// que pegou: o primeiro a entrar vem primeiro
+    pub holders: Vec<(usize, f64)>,
+}
+
+impl ResourceInner {
+    /// Ver `Resource::busy_fraction` (aqui, para quem já segura o lock de `data`).
+    pub fn busy_fraction(&self, counts: LockCounts, now: f64) -> Option<f32> {
+        if counts.write_count == 0 {
+            return Some(0.0);
+        }
+        match self.write_hold {
+            Some((started, Some(until))) if until > started => {
+                Some(((now - started) / (until - started)).clamp(0.0, 1.0) as f32)
+            }
+            Some((_, Some(_))) => Some(1.0),
+            _ => None,
+        }
+    }
+}
+
+/// Ocupação de um recurso num instante
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResourceStatus {
+    Writing,
+    /// Número de leitores ativos
+    Reading(u32),
+    Idle,
+}
+
+/// Leitores e escritores ativos de um recurso, lidos juntos num instante.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LockCounts {
     /// Quantas leituras estão em uso simultaneamente
     pub read_count: u32,
-
     /// Quantas escritas (normalmente 0 ou 1) estão em uso
     pub write_count: u32,
 }
 
-/// Invólucro com Arc<RwLock<...>>
+impl LockCounts {
+    /// Ocupação: escritor tem prioridade sobre leitores na exibição.
+    pub fn status(self) -> ResourceStatus {
+        if self.write_count > 0 {
+            ResourceStatus::Writing
+        } else if self.read_count > 0 {
+            ResourceStatus::Reading(self.read_count)
+        } else {
+            ResourceStatus::Idle
+        }
+    }
+
+    /// Os dois contadores numa palavra só, para um único compare-and-swap:
+    /// leitores nos 32 bits de baixo, escritores nos de cima.
+    fn pack(self) -> u64 {
+        (self.write_count as u64) << 32 | self.read_count as u64
+    }
+
+    fn unpack(bits: u64) -> Self {
+        Self {
+            read_count: bits as u32,
+            write_count: (bits >> 32) as u32,
+        }
+    }
+}
+
+/// O que a política vê numa tentativa de aquisição: os contadores daquele instante,
+/// o limite de leitores e a fila de espera do recurso (ver `Resource::set_waiting`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LockSnapshot {
+    /// Quantas leituras estão em uso simultaneamente
+    pub read_count: u32,
+    /// Quantas escritas estão em uso
+    pub write_count: u32,
+    /// Máximo de leitores simultâneos (0 = sem limite)
+    pub max_readers: u32,
+    /// Threads esperando para ler
+    pub waiting_readers: u32,
+    /// Threads esperando para escrever
+    pub waiting_writers: u32,
+}
+
+impl LockSnapshot {
+    pub fn counts(self) -> LockCounts {
+        LockCounts {
+            read_count: self.read_count,
+            write_count: self.write_count,
+        }
+    }
+
+    /// Ocupação, como em `LockCounts::status`.
+    pub fn status(self) -> ResourceStatus {
+        self.counts().status()
+    }
+}
+
+impl ResourceInner {
+    /// Valor de `contention` decaído até `now`.
+    pub fn contention_at(&self, now: f64) -> f32 {
+        let elapsed = (now - self.contention_time).max(0.0) as f32;
+        self.contention * (-self.contention_decay * elapsed).exp()
+    }
+}
+
+/// Invólucro com Arc<RwLock<...>>, mais a política que decide quem pode entrar.
+///
+/// Os contadores são atômicos: leitores concorrentes não se serializam num lock,
+/// e cada aquisição é um laço de compare-and-swap que consulta a política com os
+/// valores vistos e só conta se ninguém os mudou no meio.
 #[derive(Clone)]
 pub struct Resource {
     pub data: Arc<RwLock<ResourceInner>>,
+    pub policy: Arc<dyn LockPolicy>,
+    /// `LockCounts` empacotado (ver `LockCounts::pack`)
+    counts: Arc<AtomicU64>,
+    /// Máximo de leitores simultâneos (0 = sem limite)
+    max_readers: Arc<AtomicU32>,
+    /// Máximo de escritores simultâneos (1 = escrita exclusiva, o normal)
+    max_writers: Arc<AtomicU32>,
+    /// Registrar quem lê, para a mesma thread poder ler de novo (ver `try_set_reading`)
+    reentrant_reads: Arc<AtomicBool>,
+    /// O envenenamento já foi para o log pela leitura (ver `read_inner`)
+    poison_reported: Arc<AtomicBool>,
+    /// Fila de espera: leitores e escritores que aguardam o recurso (ver `set_waiting`)
+    waiting_readers: Arc<AtomicU32>,
+    waiting_writers: Arc<AtomicU32>,
 }
 
 impl Resource {
     /// Cria um novo `Resource` com contadores zerados.
-    pub fn new(name: &str, pos: Vec2, width: f32, height: f32) -> Self {
+    pub fn new(
+        name: &str,
+        pos: Vec2,
+        width: f32,
+        height: f32,
+        policy: Box<dyn LockPolicy>,
+    ) -> Self {
         let inner = ResourceInner {
             name: name.to_string(),
             pos,
             width,
             height,
-            read_count: 0,
-            write_count: 0,
+            weight: 1.0,
+            category: None,
+            contention: 0.0,
+            contention_time: 0.0,
+            contention_decay: 0.5,
+            write_hold: None,
+            hold_histogram: [0; HOLD_BUCKETS],
+            frozen: false,
+            read_holders: HashMap::new(),
+            holders: Vec::new(),
         };
         Resource {
             data: Arc::new(RwLock::new(inner)),
+            policy: Arc::from(policy),
+            counts: Arc::new(AtomicU64::new(0)),
+            max_readers: Arc::new(AtomicU32::new(0)),
+            max_writers: Arc::new(AtomicU32::new(1)),
+            reentrant_reads: Arc::new(AtomicBool::new(false)),
+            poison_reported: Arc::new(AtomicBool::new(false)),
+            waiting_readers: Arc::new(AtomicU32::new(0)),
+            waiting_writers: Arc::new(AtomicU32::new(0)),
+        }
+    }
+
+    /// O lock foi envenenado (alguma thread entrou em pânico segurando-o)?
+    pub fn is_poisoned(&self) -> bool {
+        self.data.is_poisoned()
+    }
+
+    /// Guard de leitura dos dados internos.
+    ///
+    /// Se o lock estiver envenenado, recuperamos o guard com `into_inner`: cada operação
+    /// aqui altera um único campo por vez, então os dados continuam consistentes.
+    /// Como a leitura é chamada todo frame (o `draw` mostra o aviso na tela), ela só
+    /// registra o envenenamento no log uma vez por recurso.
+    pub fn read_inner(&self) -> RwLockReadGuard<'_, ResourceInner> {
+        self.data.read().unwrap_or_else(|poisoned| {
+            let inner = poisoned.into_inner();
+            if !self.poison_reported.swap(true, Ordering::Relaxed) {
+                logger::log(Level::Error, || format!("lock do recurso \"{}\" envenenado (leitura)", inner.name));
+            }
+            inner
+        })
+    }
+
+    /// Guard de escrita dos dados internos, recuperado como em `read_inner`.
+    /// Como só acontece em mudanças pontuais (nome, contenção, posição), o envenenamento
+    /// vai para o log (nível error).
+    pub fn write_inner(&self) -> RwLockWriteGuard<'_, ResourceInner> {
+        self.data.write().unwrap_or_else(|poisoned| {
+            let inner = poisoned.into_inner();
+            logger::log(Level::Error, || {
+                format!("lock do recurso \"{}\" envenenado (escrita); usando os dados recuperados", inner.name)
+            });
+            inner
+        })
+    }
+
+    /// Leitores e escritores ativos agora.
+    pub fn counts(&self) -> LockCounts {
+        LockCounts::unpack(self.counts.load(Ordering::Acquire))
+    }
+
+    /// Limite de leitores simultâneos (0 = sem limite).
+    pub fn max_readers(&self) -> u32 {
+        self.max_readers.load(Ordering::Relaxed)
+    }
+
+    pub fn set_max_readers(&self, max_readers: u32) {
+        self.max_readers.store(max_readers, Ordering::Relaxed);
+    }
+
+    pub fn max_writers(&self) -> u32 {
+        self.max_writers.load(Ordering::Relaxed)
+    }
+
+    /// Muda o limite de escritores simultâneos (mínimo 1).
+    pub fn set_max_writers(&self, max_writers: u32) {
+        self.max_writers.store(max_writers.max(1), Ordering::Relaxed);
+    }
+
+    pub fn reentrant_reads(&self) -> bool {
+        self.reentrant_reads.load(Ordering::Relaxed)
+    }
+
+    /// Liga ou desliga as leituras reentrantes. Ligadas, cada leitura passa pelo
+    /// lock de `data` para registrar a thread; desligadas, `read_count` só conta
+    /// aquisições (como sempre foi) e o registro é esquecido. Melhor mudar antes
+    /// de alguém estar lendo.
+    pub fn set_reentrant_reads(&self, reentrant: bool) {
+        self.reentrant_reads.store(reentrant, Ordering::Relaxed);
+        if !reentrant {
+            self.write_inner().read_holders.clear();
         }
     }
 
-    /// Tenta ativar leitura (retorna `true` se conseguiu).
-    /// Regra: não pode haver escritor ativo.
-    pub fn try_set_reading(&self) -> bool {
-        if let Ok(mut inner) = self.data.write() {
-            // Se já tiver writer, falha
-            if inner.write_count > 0 {
-                return false;
+    /// Leitores e escritores esperando pelo recurso agora.
+    pub fn waiting(&self) -> (u32, u32) {
+        (self.waiting_readers.load(Ordering::Relaxed), self.waiting_writers.load(Ordering::Relaxed))
+    }
+
+    /// Troca a fila de espera inteira (a simulação a recalcula a cada mudança,
+    /// ver `ThreadsVisualizer::publish_waiting`).
+    pub fn set_waiting(&self, readers: u32, writers: u32) {
+        self.waiting_readers.store(readers, Ordering::Relaxed);
+        self.waiting_writers.store(writers, Ordering::Relaxed);
+    }
+
+    /// Uma thread entra (`joined`) ou sai da fila de espera, para quem não recalcula
+    /// a fila inteira (os workers).
+    pub fn update_waiting(&self, writing: bool, joined: bool) {
+        let queue = if writing { &self.waiting_writers } else { &self.waiting_readers };
+        let _ = queue.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+            Some(if joined { n + 1 } else { n.saturating_sub(1) })
+        });
+    }
+
+    /// O que a política vê com os contadores `counts`.
+    fn snapshot(&self, counts: LockCounts) -> LockSnapshot {
+        let (waiting_readers, waiting_writers) = self.waiting();
+        LockSnapshot {
+            read_count: counts.read_count,
+            write_count: counts.write_count,
+            max_readers: self.max_readers(),
+            waiting_readers,
+            waiting_writers,
+        }
+    }
+
+    /// Troca os contadores por `update(atual)` enquanto ele devolver `Some`,
+    /// repetindo se outra thread mexeu neles no meio. Retorna se houve troca.
+    fn update_counts(&self, update: impl Fn(LockCounts) -> Option<LockCounts>) -> bool {
+        self.counts
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |bits| {
+                update(LockCounts::unpack(bits)).map(LockCounts::pack)
+            })
+            .is_ok()
+    }
+
+    /// Congela (ou descongela) o recurso: congelado, `try_set_reading` e
+    /// `try_set_writing` sempre falham e quem pede fica esperando.
+    pub fn set_frozen(&self, frozen: bool) {
+        self.write_inner().frozen = frozen;
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.read_inner().frozen
+    }
+
+    /// Tenta ativar leitura para a thread `thread` (retorna `true` se conseguiu).
+    /// Regra: a da política (por padrão, não pode haver escritor ativo),
+    /// respeitando o limite de `max_readers`. Recurso congelado recusa sempre.
+    /// Com `reentrant_reads`, se `thread` já lê o recurso a leitura é reentrante:
+    /// sucesso sem mexer em `read_count` (e sem consultar a política, a thread não
+    /// espera por si mesma). Sem, `thread` não é usado.
+    pub fn try_set_reading(&self, thread: usize) -> bool {
+        if self.is_frozen() {
+            logger::log(Level::Debug, || format!("{}: leitura recusada (congelado)", self.name()));
+            return false;
+        }
+        let reentrant = self.reentrant_reads();
+        if reentrant {
+            if let Some(depth) = self.write_inner().read_holders.get_mut(&thread) {
+                *depth += 1;
+                return true;
             }
-            // Caso contrário, incrementa contagem de leitura
-            inner.read_count += 1;
-            return true;
         }
-        false
+        let max_readers = self.max_readers();
+        let ok = self.update_counts(|counts| {
+            if max_readers > 0 && counts.read_count >= max_readers {
+                return None;
+            }
+            if !self.policy.can_read(&self.snapshot(counts)) {
+                return None;
+            }
+            Some(LockCounts {
+                read_count: counts.read_count + 1,
+                ..counts
+            })
+        });
+        if ok && reentrant {
+            self.write_inner().read_holders.insert(thread, 1);
+        }
+        if !ok {
+            logger::log(Level::Debug, || self.refusal("leitura"));
+        }
+        ok
+    }
+
+    /// Aquisição recusada, com os contadores e a política que recusaram (para o log).
+    fn refusal(&self, what: &str) -> String {
+        let counts = self.counts();
+        format!(
+            "{}: {} recusada ({} leitores, {} escritores, política {})",
+            self.name(),
+            what,
+            counts.read_count,
+            counts.write_count,
+            self.policy.name()
+        )
+    }
+
+    /// Quantas leituras de `thread` estão abertas neste recurso (0 se ela não lê).
+    pub fn read_depth(&self, thread: usize) -> u32 {
+        self.read_inner().read_holders.get(&thread).copied().unwrap_or(0)
     }
 
     /// Tenta ativar escrita (retorna `true` se conseguiu).
-    /// Regra: não pode haver nenhum escritor nem leitores.
+    /// Regra: a da política (por padrão, não pode haver nenhum escritor nem leitores).
+    /// Com `max_writers > 1`, um escritor pode se juntar aos que já estão escrevendo
+    /// enquanto não houver leitores e o limite não for atingido. Recurso congelado recusa sempre.
     pub fn try_set_writing(&self) -> bool {
-        if let Ok(mut inner) = self.data.write() {
-            // Se houver readers ou já um writer, falha
-            if inner.read_count > 0 || inner.write_count > 0 {
-                return false;
+        if self.is_frozen() {
+            logger::log(Level::Debug, || format!("{}: escrita recusada (congelado)", self.name()));
+            return false;
+        }
+        let max_writers = self.max_writers();
+        let ok = self.update_counts(|counts| {
+            let allowed = if counts.write_count == 0 {
+                self.policy.can_write(&self.snapshot(counts))
+            } else {
+                counts.read_count == 0 && counts.write_count < max_writers
+            };
+            if !allowed {
+                return None;
             }
-            // Caso contrário, pode escrever
-            inner.write_count += 1;
-            return true;
+            Some(LockCounts {
+                write_count: counts.write_count + 1,
+                ..counts
+            })
+        });
+        if !ok {
+            logger::log(Level::Debug, || self.refusal("escrita"));
         }
-        false
+        ok
     }
 
-    /// Sai do modo de leitura (decrementa read_count).
-    pub fn remove_reading(&self) {
-        if let Ok(mut inner) = self.data.write() {
-            if inner.read_count > 0 {
-                inner.read_count -= 1;
+    /// Ocupação atual, dos dois contadores lidos de uma vez.
+    pub fn state(&self) -> ResourceStatus {
+        self.counts().status()
+    }
+
+    /// Troca o nome exibido do recurso.
+    pub fn set_name(&self, name: &str) {
+        self.write_inner().name = name.to_string();
+    }
+
+    /// Nome atual do recurso.
+    pub fn name(&self) -> String {
+        self.read_inner().name.clone()
+    }
+
+    /// Conta uma tentativa de aquisição que falhou em `now`.
+    pub fn record_failure(&self, now: f64) {
+        let mut inner = self.write_inner();
+        inner.contention = inner.contention_at(now) + 1.0;
+        inner.contention_time = now;
+    }
+
+    /// Contenção recente (já com decaimento) em `now`.
+    pub fn contention(&self, now: f64) -> f32 {
+        self.read_inner().contention_at(now)
+    }
+
+    /// Fecha uma leitura de `thread`. Só a última das leituras reentrantes dela
+    /// decrementa `read_count` (quem não estava registrado decrementa direto).
+    pub fn remove_reading(&self, thread: usize) {
+        if self.reentrant_reads() {
+            let mut inner = self.write_inner();
+            match inner.read_holders.get_mut(&thread) {
+                Some(depth) if *depth > 1 => {
+                    *depth -= 1;
+                    return;
+                }
+                Some(_) => {
+                    inner.read_holders.remove(&thread);
+                }
+                None => {}
             }
         }
+        self.update_counts(|counts| {
+            Some(LockCounts {
+                read_count: counts.read_count.saturating_sub(1),
+                ..counts
+            })
+        });
     }
 
     /// Sai do modo de escrita (decrementa write_count).
     pub fn remove_writing(&self) {
-        if let Ok(mut inner) = self.data.write() {
-            if inner.write_count > 0 {
-                inner.write_count -= 1;
-            }
+        self.update_counts(|counts| {
+            Some(LockCounts {
+                write_count: counts.write_count.saturating_sub(1),
+                ..counts
+            })
+        });
+        if self.counts().write_count == 0 {
+            self.write_inner().write_hold = None;
         }
     }
+
+    /// Troca os contadores de uma vez, sem passar pela política
+    /// (só para consertar desvios, ver `ThreadsVisualizer::reconcile`).
+    pub fn set_counts(&self, counts: LockCounts) {
+        self.counts.store(counts.pack(), Ordering::Release);
+    }
+
+    /// Zera os dois contadores, não importa quem segura o recurso.
+    pub fn force_release(&self) {
+        self.counts.store(0, Ordering::Release);
+        let mut inner = self.write_inner();
+        inner.write_hold = None;
+        inner.read_holders.clear();
+        inner.holders.clear();
+    }
+
+    /// Registra que `thread` pegou o recurso em `now`, no fim da fila de `holders`
+    /// (uma thread que já estava lá mantém o lugar).
+    pub fn add_holder(&self, thread: usize, now: f64) {
+        let mut inner = self.write_inner();
+        if inner.holders.iter().all(|&(t, _)| t != thread) {
+            inner.holders.push((thread, now));
+        }
+    }
+
+    /// Tira `thread` de `holders`; quem pegou depois sobe uma posição.
+    pub fn remove_holder(&self, thread: usize) {
+        self.write_inner().holders.retain(|&(t, _)| t != thread);
+    }
+
+    /// Quem segura o recurso, na ordem de aquisição, com o instante de cada um.
+    pub fn holders(&self) -> Vec<(usize, f64)> {
+        self.read_inner().holders.clone()
+    }
+
+    /// Marca o começo de uma escrita e quando ela deve acabar (None = sem prazo).
+    pub fn set_write_hold(&self, started: f64, until: Option<f64>) {
+        self.write_inner().write_hold = Some((started, until));
+    }
+
+    /// Conta uma posse de `duration` segundos na faixa correspondente do histograma.
+    pub fn record_hold(&self, duration: f64) {
+        let bucket = ((duration.max(0.0) / HOLD_BUCKET_WIDTH) as usize).min(HOLD_BUCKETS - 1);
+        self.write_inner().hold_histogram[bucket] += 1;
+    }
+
+    /// Quanto da escrita em andamento já passou, de 0 a 1: 0 sem escritor,
+    /// None se a escrita não tem prazo conhecido.
+    pub fn busy_fraction(&self, now: f64) -> Option<f32> {
+        let counts = self.counts();
+        self.read_inner().busy_fraction(counts, now)
+    }
+
+    /// Texto do recurso na caixa, uma informação por linha: nome, estado,
+    /// leitores/escritores e o progresso da escrita em `now`.
+    pub fn display_label(&self, now: f64) -> String {
+        self.label_with(&self.read_inner(), now)
+    }
+
+    /// Como `display_label`, para quem já segura o lock de `data`
+    fn label_with(&self, inner: &ResourceInner, now: f64) -> String {
+        let counts = self.counts();
+        let state_text = if self.is_poisoned() {
+            "POISONED"
+        } else {
+            match counts.status() {
+                ResourceStatus::Writing => "Writing",
+                ResourceStatus::Reading(_) => "Reading",
+                ResourceStatus::Idle => "Idle",
+            }
+        };
+        let (max_readers, max_writers) = (self.max_readers(), self.max_writers());
+        let readers_str = if max_readers > 0 {
+            format!("Readers: {}/{}", counts.read_count, max_readers)
+        } else {
+            format!("Readers: {}", counts.read_count)
+        };
+        let writers_str = if max_writers > 1 {
+            format!("Writers: {}/{}", counts.write_count, max_writers)
+        } else {
+            format!("Writers: {}", counts.write_count)
+        };
+        // Progresso da escrita em andamento, pelo tempo de posse sorteado
+        let busy_str = match inner.busy_fraction(counts, now) {
+            Some(fraction) => format!("Busy: {:.0}%", fraction * 100.0),
+            None => "Busy: --".to_string(),
+        };
+        format!("{}\nState: {}\n{}, {}\n{}", inner.name, state_text, readers_str, writers_str, busy_str)
+    }
 }
 
 /// Uma caixa que contém vários recursos e os desenha.
 pub struct ResourceBox {
     pub pos: Vec2,
+    /// Largura e altura da caixa (ver `set_bounds`)
+    pub size: Vec2,
+    /// Margem entre a borda da caixa e os recursos (ver `set_border_size`)
+    pub border_size: f32,
+    /// Cor da borda externa (None = `theme.border`)
+    pub border_color: Option<Color>,
     pub resources: Vec<Resource>,
+    /// Modo compacto: só os retângulos coloridos, sem texto
+    pub compact: bool,
+    /// Recurso selecionado pelo teclado (para os controles manuais)
+    pub selected_resource: Option<usize>,
+    /// Recurso que a thread selecionada vai pedir (ver `cycle_candidate`)
+    pub candidate_resource: Option<usize>,
+    /// Recurso sob o mouse, posto pelo `main` a cada frame
+    pub hovered_resource: Option<usize>,
+    /// Recursos seguros pela thread em foco, contornados no `draw`
+    /// (ver `ThreadsVisualizer::sync_sharing_highlight`)
+    pub highlighted_resources: Vec<usize>,
+    /// Trecho de nome buscado, posto pelo `main` (vazio = sem busca, ver `matches_search`)
+    pub search: String,
+    /// Contenção recente mínima para um recurso ser destacado como HOTSPOT
+    pub hotspot_threshold: f32,
+    /// Recursos em grade (linhas e colunas) em vez de uma faixa só (ver `set_grid`)
+    pub grid: bool,
+    /// Threads esperando por cada recurso, posto pelo `main` a cada frame
+    /// (ver `ThreadsVisualizer::waiting_per_resource`); deixa a cor pulsando
+    pub waiting: Vec<usize>,
+    /// Dependências (a, b): quem pede `a` precisa pegar `b` antes (ver `acquisition_chain`)
+    pub dependencies: Vec<(usize, usize)>,
+    /// Retângulos desenhados no último frame e de onde parte a animação do layout
+    /// atual, com o instante (de `get_time`) em que ela começou (ver `animate_layout`)
+    shown_rects: Vec<Rect>,
+    layout_from: Vec<Rect>,
+    layout_started: Option<f64>,
 }
 
-const RESOURCE_BOX_WIDTH: f32 = 500.0;
-const RESOURCE_BOX_HEIGHT: f32 = 300.0;
-const RESOURCE_BOX_BORDER_SIZE: f32 = 5.0;
+/// Tamanho e margem padrão de `new` (o `Layout` pode mudar o tamanho com `set_bounds`)
+pub const RESOURCE_BOX_WIDTH: f32 = 500.0;
+pub const RESOURCE_BOX_HEIGHT: f32 = 300.0;
+pub const RESOURCE_BOX_BORDER_SIZE: f32 = 5.0;
+
+/// Duração do deslize dos recursos até o lugar novo depois de um `relayout`
+const LAYOUT_ANIMATION_SECONDS: f32 = 0.3;
+
+/// Largura mínima de cada recurso; se não couberem, a caixa fica mais larga
+pub const MIN_RESOURCE_WIDTH: f32 = 24.0;
+
+/// Faixa dos cabeçalhos de categoria e espaço entre grupos
+const CATEGORY_HEADER_HEIGHT: f32 = 20.0;
+const CATEGORY_GAP: f32 = 10.0;
+
+/// Nome do grupo dos recursos sem categoria
+const DEFAULT_CATEGORY: &str = "Other";
 
 impl ResourceBox {
-    pub fn new(pos: Vec2, resources_len: u32) -> Self {
-        let usable_width = RESOURCE_BOX_WIDTH - (RESOURCE_BOX_BORDER_SIZE * 2.0);
-        let usable_height = RESOURCE_BOX_HEIGHT - (RESOURCE_BOX_BORDER_SIZE * 2.0);
+    /// Cria a caixa com `resources_len` recursos, cada um com uma política nova de `policy`,
+    /// no tamanho e margem padrão (`RESOURCE_BOX_WIDTH` x `RESOURCE_BOX_HEIGHT`).
+    pub fn new(pos: Vec2, resources_len: u32, policy: PolicyFactory) -> Self {
+        Self::with_size(
+            pos,
+            vec2(RESOURCE_BOX_WIDTH, RESOURCE_BOX_HEIGHT),
+            RESOURCE_BOX_BORDER_SIZE,
+            resources_len,
+            policy,
+        )
+    }
 
-        let resource_width = if resources_len > 0 {
-            usable_width / resources_len as f32
-        } else {
-            0.0
-        };
-        let resource_height = usable_height;
-
-        let mut resources = Vec::with_capacity(resources_len as usize);
-        for i in 0..resources_len {
-            let x_offset = RESOURCE_BOX_BORDER_SIZE + (i as f32 * resource_width);
-            let resource_pos = Vec2::new(pos.x + x_offset, pos.y + RESOURCE_BOX_BORDER_SIZE);
-
-            resources.push(Resource::new(
-                &format!("Resource {}", i + 1),
-                resource_pos,
-                resource_width,
-                resource_height,
-            ));
+    /// Como `new`, com tamanho e margem próprios (para caixas de tamanhos diferentes).
+    /// Se os recursos não couberem com `MIN_RESOURCE_WIDTH`, a caixa é alargada (com um aviso).
+    pub fn with_size(pos: Vec2, size: Vec2, border_size: f32, resources_len: u32, policy: PolicyFactory) -> Self {
+        let categories = vec![None; resources_len as usize];
+        let min_width = min_box_width(&categories, false, border_size);
+        if min_width > size.x {
+            logger::log(Level::Error, || {
+                format!("{} recursos não cabem na caixa; alargando-a para {:.0} px", resources_len, min_width)
+            });
         }
-        Self { pos, resources }
+        let size = vec2(size.x.max(min_width), size.y);
+        let resources = resource_slots(pos, size, &categories, false, border_size)
+            .into_iter()
+            .enumerate()
+            .map(|(i, (slot_pos, width, height))| {
+                Resource::new(&format!("Resource {}", i + 1), slot_pos, width, height, policy())
+            })
+            .collect();
+        Self {
+            pos,
+            size,
+            border_size,
+            border_color: None,
+            resources,
+            compact: false,
+            selected_resource: None,
+            candidate_resource: None,
+            hovered_resource: None,
+            highlighted_resources: Vec::new(),
+            search: String::new(),
+            hotspot_threshold: 2.0,
+            grid: false,
+            waiting: Vec::new(),
+            dependencies: Vec::new(),
+            shown_rects: Vec::new(),
+            layout_from: Vec::new(),
+            layout_started: None,
+        }
+    }
+
+    /// Faz o recurso `resource` exigir `requires` antes dele. Ciclos são aceitos
+    /// (são o que permite montar um deadlock).
+    pub fn add_dependency(&mut self, resource: usize, requires: usize) -> Result<(), String> {
+        let n = self.resources.len();
+        if resource >= n || requires >= n {
+            return Err(format!("dependência R{} -> R{}: só há {} recursos", resource + 1, requires + 1, n));
+        }
+        if resource == requires {
+            return Err(format!("R{} não pode depender de si mesmo", resource + 1));
+        }
+        if !self.dependencies.contains(&(resource, requires)) {
+            self.dependencies.push((resource, requires));
+        }
+        Ok(())
+    }
+
+    /// Ordem em que uma thread pega os recursos para chegar a `target`: os
+    /// pré-requisitos (e os deles, primeiro) e por último o próprio `target`.
+    /// Num ciclo, o recurso que já está no caminho não entra de novo.
+    pub fn acquisition_chain(&self, target: usize) -> Vec<usize> {
+        fn visit(deps: &[(usize, usize)], r: usize, seen: &mut Vec<usize>, chain: &mut Vec<usize>) {
+            if seen.contains(&r) {
+                return;
+            }
+            seen.push(r);
+            for &(_, required) in deps.iter().filter(|(a, _)| *a == r) {
+                visit(deps, required, seen, chain);
+            }
+            chain.push(r);
+        }
+        let mut chain = Vec::new();
+        visit(&self.dependencies, target, &mut Vec::new(), &mut chain);
+        chain
+    }
+
+    /// Seleciona o próximo recurso (volta ao primeiro depois do último).
+    pub fn select_next_resource(&mut self) {
+        let n = self.resources.len();
+        if n == 0 {
+            self.selected_resource = None;
+            return;
+        }
+        self.selected_resource = Some(match self.selected_resource {
+            Some(idx) => (idx + 1) % n,
+            None => 0,
+        });
+    }
+
+    /// Anda `delta` recursos com o candidato, dando a volta nas pontas.
+    /// Sem candidato, começa pelo primeiro (ou pelo último, andando para trás).
+    pub fn cycle_candidate(&mut self, delta: isize) {
+        let n = self.resources.len();
+        if n == 0 {
+            self.candidate_resource = None;
+            return;
+        }
+        self.candidate_resource = Some(match self.candidate_resource {
+            Some(idx) => (idx as isize + delta).rem_euclid(n as isize) as usize,
+            None if delta < 0 => n - 1,
+            None => 0,
+        });
     }
 
-    /// Tenta ativar leitura em `idx`. Retorna `true` se conseguiu.
-    pub fn try_set_reading(&self, idx: usize) -> bool {
+    /// Tenta ativar leitura em `idx` para a thread `thread`. Retorna `true` se conseguiu.
+    pub fn try_set_reading(&self, idx: usize, thread: usize) -> bool {
         if let Some(r) = self.resources.get(idx) {
-            return r.try_set_reading();
+            return r.try_set_reading(thread);
         }
         false
     }
@@ -139,10 +771,31 @@ impl ResourceBox {
         false
     }
 
-    /// Sai de leitura
-    pub fn remove_reading(&self, idx: usize) {
+    /// Congela ou descongela o recurso `idx` (ver `Resource::set_frozen`).
+    pub fn set_frozen(&self, idx: usize, frozen: bool) {
+        if let Some(r) = self.resources.get(idx) {
+            r.set_frozen(frozen);
+        }
+    }
+
+    /// O nome do recurso `idx` contém `search`, sem diferenciar maiúsculas?
+    /// Com a busca vazia todos batem (false se o recurso não existir).
+    pub fn matches_search(&self, idx: usize) -> bool {
+        let query = self.search.to_lowercase();
+        self.resources
+            .get(idx)
+            .is_some_and(|r| r.read_inner().name.to_lowercase().contains(&query))
+    }
+
+    /// Recurso `idx` congelado? (false se não existir)
+    pub fn is_frozen(&self, idx: usize) -> bool {
+        self.resources.get(idx).is_some_and(Resource::is_frozen)
+    }
+
+    /// Sai de uma leitura de `thread`
+    pub fn remove_reading(&self, idx: usize, thread: usize) {
         if let Some(r) = self.resources.get(idx) {
-            r.remove_reading();
+            r.remove_reading(thread);
         }
     }
 
@@ -153,176 +806,873 @@ impl ResourceBox {
         }
     }
 
-    /// Desenha a caixa e seus recursos.
-    pub fn draw(&self) {
-        // Borda externa
-        draw_rectangle_lines(
-            self.pos.x,
-            self.pos.y,
-            RESOURCE_BOX_WIDTH,
-            RESOURCE_BOX_HEIGHT,
-            2.0,
-            BLACK,
-        );
-
-        for resource in &self.resources {
-            if let Ok(inner) = resource.data.read() {
-                let readers = inner.read_count;
-                let writers = inner.write_count;
-
-                // Cor: se writers>0 => vermelho, senão se readers>0 => verde, senão cinza
-                let background_color = if writers > 0 {
-                    Color::new(0.9, 0.4, 0.4, 1.0)
-                } else if readers > 0 {
-                    Color::new(0.4, 0.8, 0.4, 1.0)
-                } else {
-                    Color::new(0.7, 0.7, 0.7, 1.0)
-                };
+    /// Ver `Resource::add_holder`.
+    pub fn add_holder(&self, idx: usize, thread: usize, now: f64) {
+        if let Some(r) = self.resources.get(idx) {
+            r.add_holder(thread, now);
+        }
+    }
 
-                // Retângulo do recurso
-                draw_rectangle(
-                    inner.pos.x,
-                    inner.pos.y,
-                    inner.width,
-                    inner.height,
-                    background_color,
-                );
-                draw_rectangle_lines(
-                    inner.pos.x,
-                    inner.pos.y,
-                    inner.width,
-                    inner.height,
-                    2.0,
-                    BLACK,
-                );
+    /// Ver `Resource::remove_holder`.
+    pub fn remove_holder(&self, idx: usize, thread: usize) {
+        if let Some(r) = self.resources.get(idx) {
+            r.remove_holder(thread);
+        }
+    }
 
-                // Faixa branca para texto
-                let text_bg_height = 30.0;
-                draw_rectangle(inner.pos.x, inner.pos.y, inner.width, text_bg_height, WHITE);
+    /// Quem segura o recurso `idx`, na ordem de aquisição (vazio se não existir).
+    pub fn holders(&self, idx: usize) -> Vec<(usize, f64)> {
+        self.resources.get(idx).map_or_else(Vec::new, Resource::holders)
+    }
 
-                // Nome, estado e contadores
-                let resource_state_text = if writers > 0 {
-                    "Writing"
-                } else if readers > 0 {
-                    "Reading"
-                } else {
-                    "Idle"
-                };
-                let counters_str = format!("Readers: {}, Writers: {}", readers, writers);
-                let full_text = format!(
-                    "{}\nState: {}\n{}",
-                    inner.name, resource_state_text, counters_str
-                );
+    /// Leitores ativos no recurso `idx` (0 se não existir).
+    pub fn readers(&self, idx: usize) -> u32 {
+        self.resources.get(idx).map_or(0, |r| r.counts().read_count)
+    }
+
+    /// Escritores ativos no recurso `idx` (0 se não existir).
+    pub fn writers(&self, idx: usize) -> u32 {
+        self.resources.get(idx).map_or(0, |r| r.counts().write_count)
+    }
 
-                // Desenhar texto com wrap
-                let font_size = 18.0;
-                let left_margin = 5.0;
-                let top_margin = 5.0;
-                let text_start_x = inner.pos.x + left_margin;
-                let text_start_y = inner.pos.y + top_margin;
-                let max_text_width = inner.width - 2.0 * left_margin;
-                let max_text_height = inner.height - top_margin;
+    /// Ver `Resource::set_write_hold`.
+    pub fn set_write_hold(&self, idx: usize, started: f64, until: Option<f64>) {
+        if let Some(r) = self.resources.get(idx) {
+            r.set_write_hold(started, until);
+        }
+    }
 
+    /// Ver `Resource::record_hold`.
+    pub fn record_hold(&self, idx: usize, duration: f64) {
+        if let Some(r) = self.resources.get(idx) {
+            r.record_hold(duration);
+        }
+    }
+
+    /// Histograma das durações de posse do recurso `idx`: para cada faixa, o início
+    /// dela em segundos e quantas posses caíram ali (vazio se o recurso não existir).
+    pub fn hold_duration_histogram(&self, idx: usize) -> Vec<(f64, usize)> {
+        self.resources.get(idx).map_or_else(Vec::new, |r| {
+            r.read_inner()
+                .hold_histogram
+                .iter()
+                .enumerate()
+                .map(|(i, &count)| (i as f64 * HOLD_BUCKET_WIDTH, count))
+                .collect()
+        })
+    }
+
+    /// Esquece as estatísticas de todos os recursos: posses registradas e contenção
+    /// (o que acende o hotspot). Contadores e configuração ficam como estão.
+    pub fn reset_stats(&self) {
+        for r in &self.resources {
+            let mut inner = r.write_inner();
+            inner.hold_histogram = [0; HOLD_BUCKETS];
+            inner.contention = 0.0;
+        }
+    }
+
+    /// Zera à força os contadores do recurso `idx`, não importa quem o segura.
+    /// Quem chama deve também tirar as threads do recurso
+    /// (ver `ThreadsVisualizer::force_release_resource`).
+    pub fn force_release(&self, idx: usize) {
+        if let Some(r) = self.resources.get(idx) {
+            r.force_release();
+        }
+    }
+
+    /// Limita os leitores simultâneos do recurso `idx` (0 = sem limite).
+    pub fn set_max_readers(&self, idx: usize, max_readers: u32) {
+        if let Some(r) = self.resources.get(idx) {
+            r.set_max_readers(max_readers);
+        }
+    }
+
+    /// Liga ou desliga as leituras reentrantes do recurso `idx` (ver `Resource::set_reentrant_reads`).
+    pub fn set_reentrant_reads(&self, idx: usize, reentrant: bool) {
+        if let Some(r) = self.resources.get(idx) {
+            r.set_reentrant_reads(reentrant);
+        }
+    }
+
+    pub fn set_max_writers(&self, idx: usize, max_writers: u32) {
+        if let Some(r) = self.resources.get(idx) {
+            r.set_max_writers(max_writers);
+        }
+    }
+
+    /// Define o peso do recurso `idx` no sorteio (valores negativos viram 0).
+    pub fn set_weight(&self, idx: usize, weight: f32) {
+        if let Some(r) = self.resources.get(idx) {
+            r.write_inner().weight = weight.max(0.0);
+        }
+    }
+
+    /// Sorteia um recurso proporcionalmente aos pesos.
+    /// Se todos os pesos forem zero, o sorteio é uniforme.
+    pub fn pick_weighted(&self, rng: &mut impl Rng) -> Option<usize> {
+        let weights: Vec<f32> = self
+            .resources
+            .iter()
+            .map(|r| r.read_inner().weight)
+            .collect();
+        let total: f32 = weights.iter().sum();
+        if total > 0.0 {
+            weighted_index(&weights, rng.random_range(0.0..total))
+        } else if !weights.is_empty() {
+            Some(rng.random_range(0..weights.len()))
+        } else {
+            None
+        }
+    }
+
+    /// Conta uma tentativa que falhou no recurso `idx`.
+    pub fn record_failure(&self, idx: usize, now: f64) {
+        if let Some(r) = self.resources.get(idx) {
+            r.record_failure(now);
+        }
+    }
+
+    /// Taxa de decaimento (por segundo) da contenção de todos os recursos.
+    pub fn set_contention_decay(&self, decay: f32) {
+        for r in &self.resources {
+            r.write_inner().contention_decay = decay.max(0.0);
+        }
+    }
+
+    /// Recurso com mais falhas recentes, se passar de `hotspot_threshold`.
+    pub fn hotspot(&self, now: f64) -> Option<usize> {
+        self.resources
+            .iter()
+            .map(|r| r.contention(now))
+            .enumerate()
+            .filter(|&(_, c)| c >= self.hotspot_threshold)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(idx, _)| idx)
+    }
+
+    /// Recursos (com seus índices) que têm algum leitor ou escritor ativo.
+    pub fn resources_in_use(&self) -> impl Iterator<Item = (usize, &Resource)> {
+        self.resources
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| r.state() != ResourceStatus::Idle)
+    }
+
+    /// Fração dos recursos em uso (lendo ou escrevendo) agora, de 0 a 1 (0 sem recursos).
+    pub fn utilization(&self) -> f32 {
+        if self.resources.is_empty() {
+            return 0.0;
+        }
+        self.resources_in_use().count() as f32 / self.resources.len() as f32
+    }
+
+    /// Confere as regras que nenhuma política pode quebrar: nunca leitores junto com
+    /// escritor e no máximo `max_writers` escritores. Devolve uma descrição de cada violação.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut violations = Vec::new();
+        for (idx, resource) in self.resources.iter().enumerate() {
+            let counts = resource.counts();
+            if counts.read_count > 0 && counts.write_count > 0 {
+                violations.push(format!(
+                    "R{}: {} readers and {} writers at the same time",
+                    idx + 1,
+                    counts.read_count,
+                    counts.write_count
+                ));
+            }
+            if counts.write_count > resource.max_writers() {
+                violations.push(format!(
+                    "R{}: {} writers at the same time (max {})",
+                    idx + 1,
+                    counts.write_count,
+                    resource.max_writers()
+                ));
+            }
+        }
+        logger::log(Level::Trace, || {
+            format!("validate: {} recursos, violações: {:?}", self.resources.len(), violations)
+        });
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    /// Retângulo da caixa inteira.
+    pub fn bounds(&self) -> Rect {
+        Rect::new(self.pos.x, self.pos.y, self.size.x, self.size.y)
+    }
+
+    /// Move/redimensiona a caixa para `bounds`, redistribuindo os recursos nela.
+    /// A caixa não fica mais estreita do que os recursos precisam (ver `MIN_RESOURCE_WIDTH`).
+    pub fn set_bounds(&mut self, bounds: Rect) {
+        let min_width = min_box_width(&self.categories(), self.grid, self.border_size);
+        let size = vec2(bounds.w.max(min_width), bounds.h);
+        if self.pos == bounds.point() && self.size == size {
+            return;
+        }
+        self.pos = bounds.point();
+        self.size = size;
+        self.relayout();
+    }
+
+    /// Retângulo do recurso `idx` dentro da caixa.
+    pub fn resource_rect(&self, idx: usize) -> Option<Rect> {
+        self.resources.get(idx).map(|r| {
+            let inner = r.read_inner();
+            Rect::new(inner.pos.x, inner.pos.y, inner.width, inner.height)
+        })
+    }
+
+    /// Recurso mais próximo de `point`, se estiver a até `max_distance` do seu retângulo
+    /// (0 se o ponto estiver dentro).
+    pub fn nearest_resource(&self, point: Vec2, max_distance: f32) -> Option<usize> {
+        (0..self.resources.len())
+            .filter_map(|idx| {
+                let rect = self.resource_rect(idx)?;
+                let closest = point.clamp(rect.point(), rect.point() + rect.size());
+                Some((idx, closest.distance(point)))
+            })
+            .filter(|&(_, distance)| distance <= max_distance)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(idx, _)| idx)
+    }
+
+    /// Muda a margem entre a borda e os recursos e redistribui a caixa.
+    pub fn set_border_size(&mut self, border_size: f32) {
+        let border_size = border_size.max(0.0);
+        if self.border_size != border_size {
+            self.border_size = border_size;
+            self.relayout();
+        }
+    }
+
+    /// Troca entre a faixa e a grade e redistribui a caixa. Na grade os grupos
+    /// de `set_category` não são separados nem ganham cabeçalho.
+    pub fn set_grid(&mut self, grid: bool) {
+        if self.grid != grid {
+            self.grid = grid;
+            self.relayout();
+        }
+    }
+
+    /// Põe o recurso `idx` no grupo `category` (None = grupo padrão) e redistribui a caixa.
+    pub fn set_category(&mut self, idx: usize, category: Option<&str>) {
+        if let Some(r) = self.resources.get(idx) {
+            r.write_inner().category = category.map(str::to_string);
+            self.relayout();
+        }
+    }
+
+    fn categories(&self) -> Vec<Option<String>> {
+        self.resources
+            .iter()
+            .map(|r| r.read_inner().category.clone())
+            .collect()
+    }
+
+    /// Recalcula posição e tamanho de cada recurso dentro da caixa
+    /// (alargando-a se os espaços entre grupos não couberem mais).
+    fn relayout(&mut self) {
+        // A animação parte de onde os recursos estão na tela agora
+        self.layout_from = self.shown_rects.clone();
+        self.layout_started = None;
+        let categories = self.categories();
+        self.size.x = self.size.x.max(min_box_width(&categories, self.grid, self.border_size));
+        let slots = resource_slots(self.pos, self.size, &categories, self.grid, self.border_size);
+        for (resource, (pos, width, height)) in self.resources.iter().zip(slots) {
+            let mut inner = resource.write_inner();
+            inner.pos = pos;
+            inner.width = width;
+            inner.height = height;
+        }
+    }
+
+    /// Retângulos na tela em `time` (de `get_time`): do layout anterior ao atual em
+    /// `LAYOUT_ANIMATION_SECONDS`, começando no primeiro frame depois do `relayout`.
+    /// Só o desenho usa esses retângulos; cliques e arrastos usam o layout atual.
+    fn animate_layout(&mut self, time: f64) -> Vec<Rect> {
+        let started = *self.layout_started.get_or_insert(time);
+        let t = ((time - started) as f32 / LAYOUT_ANIMATION_SECONDS).clamp(0.0, 1.0);
+        // Sai e chega devagar (smoothstep)
+        let t = t * t * (3.0 - 2.0 * t);
+        let rects: Vec<Rect> = (0..self.resources.len())
+            .filter_map(|idx| {
+                let target = self.resource_rect(idx)?;
+                // Recurso sem posição anterior (recém-criado) já aparece no lugar
+                let from = self.layout_from.get(idx).copied().unwrap_or(target);
+                let lerp = |a: f32, b: f32| a + (b - a) * t;
+                Some(Rect::new(
+                    lerp(from.x, target.x),
+                    lerp(from.y, target.y),
+                    lerp(from.w, target.w),
+                    lerp(from.h, target.h),
+                ))
+            })
+            .collect();
+        if t >= 1.0 {
+            self.layout_from.clear();
+        }
+        self.shown_rects = rects.clone();
+        rects
+    }
+
+    /// Uma seta em arco por cima da caixa para cada dependência, de quem exige
+    /// até o recurso exigido.
+    fn draw_dependencies(&self, rects: &[Rect], theme: &Theme) {
+        for &(from, to) in &self.dependencies {
+            let (Some(&a), Some(&b)) = (rects.get(from), rects.get(to)) else {
+                continue;
+            };
+            let start = vec2(a.center().x, a.y);
+            let end = vec2(b.center().x, b.y);
+            let lift = 20.0 + start.distance(end) * 0.15;
+            let control = vec2((start.x + end.x) * 0.5, start.y.min(end.y) - lift);
+            let point = |t: f32| start * (1.0 - t) * (1.0 - t) + control * 2.0 * t * (1.0 - t) + end * t * t;
+            let segments = 16;
+            for i in 0..segments {
+                let (p, q) = (point(i as f32 / segments as f32), point((i + 1) as f32 / segments as f32));
+                draw_line(p.x, p.y, q.x, q.y, 2.0, theme.highlight);
+            }
+            // Ponta da seta na direção do fim da curva
+            let tip = end;
+            let dir = (end - control).normalize_or_zero();
+            let side = vec2(-dir.y, dir.x);
+            let back = tip - dir * 10.0;
+            draw_triangle(tip, back + side * 5.0, back - side * 5.0, theme.highlight);
+        }
+    }
+
+    /// Barra de `utilization` abaixo da caixa, à direita da política: verde com
+    /// pouca coisa em uso, passando pelo amarelo até o vermelho com tudo ocupado.
+    fn draw_utilization_gauge(&self, theme: &Theme) {
+        let utilization = self.utilization();
+        let width = GAUGE_WIDTH.min(self.size.x * 0.5);
+        let x = self.pos.x + self.size.x - width;
+        let y = self.pos.y + self.size.y + 8.0;
+        let color = if utilization < 0.5 {
+            mix(GREEN, YELLOW, utilization * 2.0)
+        } else {
+            mix(YELLOW, RED, utilization * 2.0 - 1.0)
+        };
+        draw_rectangle(x, y, width, GAUGE_HEIGHT, theme.text_background);
+        draw_rectangle(x, y, width * utilization, GAUGE_HEIGHT, color);
+        draw_rectangle_lines(x, y, width, GAUGE_HEIGHT, 1.0, theme.border);
+        if !self.compact {
+            let label = format!("Busy {:.0}%", utilization * 100.0);
+            draw_label(&label, x - text_width(&label, 16.0) - 6.0, y + GAUGE_HEIGHT, 16.0, theme.text);
+        }
+    }
+
+    /// Desenha a caixa e seus recursos (`now` decide o destaque do HOTSPOT). Depois
+    /// de um `relayout` os retângulos deslizam até o lugar novo (ver `animate_layout`).
+    pub fn draw(&mut self, theme: &Theme, now: f64) {
+        // Borda externa
+        let border = self.border_color.unwrap_or(theme.border);
+        draw_rectangle_lines(self.pos.x, self.pos.y, self.size.x, self.size.y, 2.0, border);
+
+        // Sem recursos, só um aviso no meio da caixa
+        if self.resources.is_empty() {
+            let label = "No resources";
+            let width = text_width(label, 20.0);
+            draw_label(
+                label,
+                self.pos.x + (self.size.x - width) * 0.5,
+                self.pos.y + self.size.y * 0.5,
+                20.0,
+                theme.text,
+            );
+            return;
+        }
+
+        let rects = self.animate_layout(get_time());
+        let hotspot = self.hotspot(now);
+
+        // Cabeçalho de cada grupo, no espaço reservado acima dos recursos
+        let categories = self.categories();
+        if !self.grid && categories.iter().any(Option::is_some) {
+            for (category, run) in category_runs(&categories) {
+                let (first, last) = (rects[run.start], rects[run.end - 1]);
+                let x = first.x;
+                let width = last.right() - x;
                 draw_wrapped_text(
-                    &full_text,
-                    text_start_x,
-                    text_start_y,
-                    max_text_width,
-                    max_text_height,
-                    font_size,
-                    BLACK,
+                    category.as_deref().unwrap_or(DEFAULT_CATEGORY),
+                    x + 2.0,
+                    self.pos.y + self.border_size,
+                    width - 4.0,
+                    CATEGORY_HEADER_HEIGHT,
+                    16.0,
+                    theme.text,
                 );
             }
         }
-    }
-}
 
-/// Desenha texto com wrap
-fn draw_wrapped_text(
-    text: &str,
-    start_x: f32,
-    start_y: f32,
-    max_width: f32,
-    max_height: f32,
-    font_size: f32,
-    color: Color,
-) {
-    let forced_lines: Vec<&str> = text.split('\n').collect();
-    let line_spacing = font_size + 5.0;
-    let mut cursor_y = start_y;
-
-    for line in forced_lines {
-        let words: Vec<&str> = line.split_whitespace().collect();
-        let mut current_line = String::new();
-        let mut current_line_width = 0.0;
-
-        for word in words {
-            let measure = measure_text(word, None, font_size as u16, 1.0);
-            let word_width = measure.width;
-
-            let space_width = if current_line.is_empty() {
-                0.0
+        // Política de aquisição, logo abaixo da caixa
+        if let Some(first) = self.resources.first() {
+            draw_label(
+                &format!("Policy: {}", first.policy.name()),
+                self.pos.x,
+                self.pos.y + self.size.y + 20.0,
+                18.0,
+                theme.text,
+            );
+        }
+
+        self.draw_utilization_gauge(theme);
+        self.draw_dependencies(&rects, theme);
+
+        let max_weight = self
+            .resources
+            .iter()
+            .map(|r| r.read_inner().weight)
+            .fold(0.0, f32::max);
+
+        for (idx, resource) in self.resources.iter().enumerate() {
+            let inner = resource.read_inner();
+            let rect = rects[idx];
+            let counts = resource.counts();
+            let readers = counts.read_count;
+            let status = counts.status();
+            let max_readers = resource.max_readers();
+
+            let waiting = self.waiting.get(idx).copied().unwrap_or(0);
+            let background_color = if inner.frozen {
+                theme.resource_frozen
             } else {
-                measure_text(" ", None, font_size as u16, 1.0).width
+                load_color(status, waiting, theme, now)
             };
-            let next_width = current_line_width + space_width + word_width;
-
-            // Se a palavra sozinha for maior que max_width, truncar
-            if word_width > max_width {
-                if !current_line.is_empty() {
-                    draw_text_line(&current_line, start_x, cursor_y, font_size, color);
-                    cursor_y += line_spacing;
-                    if cursor_y > start_y + max_height {
-                        return;
-                    }
-                }
-                let mut truncated = String::new();
-                for ch in word.chars() {
-                    let test_str = truncated.clone() + &ch.to_string();
-                    let w = measure_text(&test_str, None, font_size as u16, 1.0).width;
-                    if w > max_width {
-                        break;
-                    }
-                    truncated.push(ch);
+
+            // Retângulo do recurso
+            draw_rectangle(
+                rect.x,
+                rect.y,
+                rect.w,
+                rect.h,
+                background_color,
+            );
+            draw_rectangle_lines(
+                rect.x,
+                rect.y,
+                rect.w,
+                rect.h,
+                2.0,
+                theme.border,
+            );
+
+            // Congelado: hachurado por cima, com o aviso no meio
+            if inner.frozen {
+                draw_hatching(rect.point(), rect.w, rect.h, theme.border);
+                let label = "FROZEN";
+                let label_width = text_width(label, 18.0);
+                let (lx, ly) = (
+                    rect.x + (rect.w - label_width) * 0.5,
+                    rect.y + rect.h * 0.5,
+                );
+                draw_rectangle(lx - 3.0, ly - 16.0, label_width + 6.0, 22.0, theme.text_background);
+                draw_label(label, lx, ly, 18.0, theme.text);
+            }
+
+            // Brilho em volta do recurso mais disputado
+            if hotspot == Some(idx) {
+                let glow = Color::new(theme.alert.r, theme.alert.g, theme.alert.b, 0.35);
+                draw_rectangle_lines(
+                    rect.x - 3.0,
+                    rect.y - 3.0,
+                    rect.w + 6.0,
+                    rect.h + 6.0,
+                    8.0,
+                    glow,
+                );
+                draw_rectangle_lines(
+                    rect.x,
+                    rect.y,
+                    rect.w,
+                    rect.h,
+                    3.0,
+                    theme.alert,
+                );
+                let label = "HOTSPOT";
+                let label_width = text_width(label, 16.0);
+                draw_label(
+                    label,
+                    rect.x + (rect.w - label_width) * 0.5,
+                    rect.y - 6.0,
+                    16.0,
+                    theme.alert,
+                );
+            }
+
+            // Destaque do recurso selecionado
+            if self.selected_resource == Some(idx) {
+                draw_rectangle_lines(
+                    rect.x + 2.0,
+                    rect.y + 2.0,
+                    rect.w - 4.0,
+                    rect.h - 4.0,
+                    4.0,
+                    theme.highlight,
+                );
+            }
+
+            // Segurado pela thread em foco
+            if self.highlighted_resources.contains(&idx) {
+                draw_rectangle_lines(rect.x - 5.0, rect.y - 5.0, rect.w + 10.0, rect.h + 10.0, 3.0, theme.highlight);
+            }
+
+            // Alvo da thread selecionada, por dentro do destaque de seleção
+            if self.candidate_resource == Some(idx) {
+                draw_rectangle_lines(
+                    rect.x + 8.0,
+                    rect.y + 8.0,
+                    rect.w - 16.0,
+                    rect.h - 16.0,
+                    3.0,
+                    theme.waiting,
+                );
+            }
+
+            // Barra de peso no rodapé, proporcional ao maior peso da caixa
+            let bar_height = 8.0;
+            if max_weight > 0.0 {
+                let bar_width = (rect.w - 4.0) * (inner.weight / max_weight);
+                draw_rectangle(
+                    rect.x + 2.0,
+                    rect.y + rect.h - bar_height - 2.0,
+                    bar_width,
+                    bar_height,
+                    theme.highlight,
+                );
+            }
+            let mut footer_height = bar_height + 4.0;
+
+            // Vagas de leitura: uma célula por leitor permitido, acesas conforme `read_count`.
+            // Com escritor, o retângulo inteiro já fica vermelho e as células somem.
+            if max_readers > 0 && status != ResourceStatus::Writing {
+                let cell_height = 14.0;
+                let gap = 2.0;
+                let slots = max_readers as f32;
+                let cell_width = ((rect.w - 4.0 - gap * (slots - 1.0)) / slots).max(1.0);
+                let cell_y = rect.y + rect.h - footer_height - cell_height;
+                for slot in 0..max_readers {
+                    let cell_x = rect.x + 2.0 + slot as f32 * (cell_width + gap);
+                    let color = if slot < readers {
+                        theme.reading
+                    } else {
+                        theme.text_background
+                    };
+                    draw_rectangle(cell_x, cell_y, cell_width, cell_height, color);
+                    draw_rectangle_lines(cell_x, cell_y, cell_width, cell_height, 1.0, theme.border);
                 }
-                draw_text_line(&truncated, start_x, cursor_y, font_size, color);
-                cursor_y += line_spacing;
-                if cursor_y > start_y + max_height {
-                    return;
+                footer_height += cell_height + 4.0;
+            }
+
+            // Durações das posses: uma barrinha por faixa do histograma, da mais curta à mais longa
+            let most_holds = inner.hold_histogram.iter().copied().max().unwrap_or(0);
+            if most_holds > 0 {
+                let spark_height = 12.0;
+                let bar_width = (rect.w - 4.0) / HOLD_BUCKETS as f32;
+                let base_y = rect.y + rect.h - footer_height;
+                for (i, &count) in inner.hold_histogram.iter().enumerate() {
+                    let height = spark_height * count as f32 / most_holds as f32;
+                    draw_rectangle(
+                        rect.x + 2.0 + i as f32 * bar_width,
+                        base_y - height,
+                        (bar_width - 1.0).max(1.0),
+                        height,
+                        theme.text,
+                    );
                 }
-                current_line.clear();
-                current_line_width = 0.0;
+                footer_height += spark_height + 4.0;
+            }
+
+            if self.compact {
                 continue;
             }
 
-            if next_width <= max_width {
-                if !current_line.is_empty() {
-                    current_line.push(' ');
-                }
-                current_line.push_str(word);
-                current_line_width = next_width;
-            } else {
-                draw_text_line(&current_line, start_x, cursor_y, font_size, color);
-                cursor_y += line_spacing;
-                if cursor_y > start_y + max_height {
-                    return;
+            // Faixa para texto
+            let text_bg_height = 30.0;
+            draw_rectangle(
+                rect.x,
+                rect.y,
+                rect.w,
+                text_bg_height,
+                theme.text_background,
+            );
+
+            let full_text = resource.label_with(&inner, now);
+
+            // Desenhar texto com wrap
+            let font_size = 18.0;
+            let left_margin = 5.0;
+            let top_margin = 5.0;
+            let text_start_x = rect.x + left_margin;
+            let text_start_y = rect.y + top_margin;
+            let max_text_width = rect.w - 2.0 * left_margin;
+            // O texto para antes do rodapé (vagas e barra de peso), sem invadir a borda de baixo
+            let max_text_height = rect.h - top_margin - footer_height;
+
+            draw_wrapped_text(
+                &full_text,
+                text_start_x,
+                text_start_y,
+                max_text_width,
+                max_text_height,
+                font_size,
+                theme.text,
+            );
+        }
+
+        // Busca por nome: os que batem ganham contorno, os outros ficam apagados
+        if !self.search.is_empty() {
+            let dim = Color::new(theme.background.r, theme.background.g, theme.background.b, 0.65);
+            for (idx, rect) in rects.iter().enumerate() {
+                if self.matches_search(idx) {
+                    draw_rectangle_lines(rect.x - 3.0, rect.y - 3.0, rect.w + 6.0, rect.h + 6.0, 4.0, theme.highlight);
+                } else {
+                    draw_rectangle(rect.x, rect.y, rect.w, rect.h, dim);
                 }
-                current_line.clear();
-                current_line.push_str(word);
-                current_line_width = word_width;
             }
         }
+    }
+}
 
-        if !current_line.is_empty() {
-            draw_text_line(&current_line, start_x, cursor_y, font_size, color);
-            cursor_y += line_spacing;
-            if cursor_y > start_y + max_height {
-                return;
-            }
+/// Trechos seguidos de recursos com a mesma categoria, na ordem da caixa.
+fn category_runs(categories: &[Option<String>]) -> Vec<(Option<String>, Range<usize>)> {
+    let mut runs: Vec<(Option<String>, Range<usize>)> = Vec::new();
+    for (i, category) in categories.iter().enumerate() {
+        match runs.last_mut() {
+            Some((current, run)) if current == category => run.end = i + 1,
+            _ => runs.push((category.clone(), i..i + 1)),
+        }
+    }
+    runs
+}
+
+/// Leitores a partir dos quais o verde fica no tom mais escuro
+const DEEPEST_READERS: u32 = 4;
+/// Fila a partir da qual o pulso fica na força máxima, e pulsos por segundo
+const STRONGEST_QUEUE: usize = 3;
+const QUEUE_PULSE_RATE: f64 = 1.5;
+
+/// Tamanho da barra de `ResourceBox::utilization`
+const GAUGE_WIDTH: f32 = 120.0;
+const GAUGE_HEIGHT: f32 = 12.0;
+
+/// De `a` (t = 0) a `b` (t = 1)
+fn mix(a: Color, b: Color, t: f32) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    Color::new(
+        a.r + (b.r - a.r) * t,
+        a.g + (b.g - a.g) * t,
+        a.b + (b.b - a.b) * t,
+        a.a + (b.a - a.a) * t,
+    )
+}
+
+/// Cor do recurso conforme a carga: o verde escurece com mais leitores e, com
+/// threads na fila, a cor pulsa na direção de `theme.waiting` (mais forte com
+/// mais gente esperando). O cinza do recurso livre não muda.
+fn load_color(status: ResourceStatus, waiting: usize, theme: &Theme, now: f64) -> Color {
+    let base = match status {
+        ResourceStatus::Writing => theme.resource_writing,
+        ResourceStatus::Reading(readers) => {
+            let depth = (readers.saturating_sub(1) as f32 / (DEEPEST_READERS - 1) as f32).min(1.0);
+            let shade = 1.0 - 0.4 * depth;
+            let c = theme.resource_reading;
+            Color::new(c.r * shade, c.g * shade, c.b * shade, c.a)
+        }
+        ResourceStatus::Idle => return theme.resource_idle,
+    };
+    if waiting == 0 {
+        return base;
+    }
+    let strength = (waiting as f32 / STRONGEST_QUEUE as f32).min(1.0);
+    let pulse = (0.5 - 0.5 * (now * QUEUE_PULSE_RATE * std::f64::consts::TAU).cos()) as f32;
+    let t = 0.5 * strength * pulse;
+    Color { a: base.a, ..mix(base, theme.waiting, t) }
+}
+
+/// Linhas diagonais (a 45°) cobrindo o retângulo em `pos`, cortadas nas bordas.
+fn draw_hatching(pos: Vec2, width: f32, height: f32, color: Color) {
+    let step = 10.0;
+    let mut d = step;
+    while d < width + height {
+        // Reta x = pos.x + d - t, y = pos.y + t, cortada em [pos.x, pos.x + width]
+        let start = if d > width { vec2(pos.x + width, pos.y + d - width) } else { vec2(pos.x + d, pos.y) };
+        let end = if d > height { vec2(pos.x + d - height, pos.y + height) } else { vec2(pos.x, pos.y + d) };
+        draw_line(start.x, start.y, end.x, end.y, 1.0, color);
+        d += step;
+    }
+}
+
+/// Menor largura de caixa (com margem `border`) em que cada recurso ainda tem `MIN_RESOURCE_WIDTH`.
+fn min_box_width(categories: &[Option<String>], grid: bool, border: f32) -> f32 {
+    if grid {
+        return border * 2.0 + grid_columns(categories.len()) as f32 * MIN_RESOURCE_WIDTH;
+    }
+    let gaps = category_runs(categories).len().saturating_sub(1) as f32 * CATEGORY_GAP;
+    border * 2.0 + gaps + categories.len() as f32 * MIN_RESOURCE_WIDTH
+}
+
+/// Colunas da grade para `n` recursos: ceil(sqrt(n)), para ela ficar perto de um quadrado.
+fn grid_columns(n: usize) -> usize {
+    (n as f64).sqrt().ceil() as usize
+}
+
+/// Posição, largura e altura de cada recurso na caixa, a `border` da borda: numa faixa
+/// (com os grupos de `categories` separados) ou, com `grid`, em linhas e colunas.
+fn resource_slots(
+    pos: Vec2,
+    size: Vec2,
+    categories: &[Option<String>],
+    grid: bool,
+    border: f32,
+) -> Vec<(Vec2, f32, f32)> {
+    if grid {
+        return grid_slots(pos, size, categories.len(), border);
+    }
+    let n = categories.len();
+    let runs = category_runs(categories);
+    let header = if categories.iter().any(Option::is_some) {
+        CATEGORY_HEADER_HEIGHT
+    } else {
+        0.0
+    };
+    let gaps = runs.len().saturating_sub(1) as f32 * CATEGORY_GAP;
+    let usable_width = (size.x - border * 2.0 - gaps).max(0.0);
+    let usable_height = (size.y - border * 2.0 - header).max(0.0);
+    let width = if n > 0 { usable_width / n as f32 } else { 0.0 };
+
+    let mut slots = Vec::with_capacity(n);
+    for (group, (_, run)) in runs.into_iter().enumerate() {
+        for i in run {
+            let x_offset = border + i as f32 * width + group as f32 * CATEGORY_GAP;
+            slots.push((
+                vec2(pos.x + x_offset, pos.y + border + header),
+                width,
+                usable_height,
+            ));
+        }
+    }
+    slots
+}
+
+/// Células da grade, da esquerda para a direita e de cima para baixo.
+fn grid_slots(pos: Vec2, size: Vec2, n: usize, border: f32) -> Vec<(Vec2, f32, f32)> {
+    let columns = grid_columns(n).max(1);
+    let rows = n.div_ceil(columns).max(1);
+    let width = (size.x - border * 2.0).max(0.0) / columns as f32;
+    let height = (size.y - border * 2.0).max(0.0) / rows as f32;
+    (0..n)
+        .map(|i| {
+            let (column, row) = ((i % columns) as f32, (i / columns) as f32);
+            (
+                vec2(
+                    pos.x + border + column * width,
+                    pos.y + border + row * height,
+                ),
+                width,
+                height,
+            )
+        })
+        .collect()
+}
+
+/// Encontra o índice cujo intervalo acumulado de pesos contém `roll`
+/// (`roll` deve estar em `0.0..soma dos pesos`).
+pub(crate) fn weighted_index(weights: &[f32], roll: f32) -> Option<usize> {
+    let mut acc = 0.0;
+    let mut last_positive = None;
+    for (i, &w) in weights.iter().enumerate() {
+        if w <= 0.0 {
+            continue;
+        }
+        acc += w;
+        last_positive = Some(i);
+        if roll < acc {
+            return Some(i);
         }
     }
+    // Erro de arredondamento: fica com o último recurso de peso positivo
+    last_positive
 }
 
-fn draw_text_line(line: &str, x: f32, y: f32, font_size: f32, color: Color) {
-    draw_text(line, x, y + font_size, font_size, color);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::policy::ReaderPreferring;
+    use crate::threads::{ThreadState, ThreadsVisualizer};
+
+    fn resource_box(resources: u32) -> ResourceBox {
+        ResourceBox::new(Vec2::ZERO, resources, || Box::new(ReaderPreferring))
+    }
+
+    #[test]
+    fn resource_box_fits_zero_one_and_many_resources() {
+        let rb = resource_box(0);
+        assert!(rb.resources.is_empty());
+        assert_eq!(rb.size.x, RESOURCE_BOX_WIDTH);
+        // Sem recursos, nenhuma thread consegue pegar um
+        let mut vis = ThreadsVisualizer::new(2);
+        vis.clock = Arc::new(MockClock::new(0.0));
+        vis.seed = Some(1);
+        for step in 0..20 {
+            vis.update_threads_randomly(&rb, step as f64);
+        }
+        assert!(vis.threads.iter().all(|t| t.resource_in_use.is_none()));
+
+        for n in [1, 500] {
+            let rb = resource_box(n);
+            assert_eq!(rb.resources.len(), n as usize);
+            assert!(rb.size.x >= n as f32 * MIN_RESOURCE_WIDTH);
+            for resource in &rb.resources {
+                let inner = resource.read_inner();
+                assert!(inner.width >= MIN_RESOURCE_WIDTH && inner.height > 0.0);
+                assert!(inner.pos.x >= rb.pos.x && inner.pos.x + inner.width <= rb.pos.x + rb.size.x);
+            }
+        }
+    }
+
+    #[test]
+    fn hold_durations_land_in_their_buckets() {
+        let rb = resource_box(2);
+        for duration in [0.0, 0.49, HOLD_BUCKET_WIDTH, 1.2, -1.0, 100.0] {
+            rb.record_hold(0, duration);
+        }
+        let histogram = rb.hold_duration_histogram(0);
+        assert_eq!(histogram.len(), HOLD_BUCKETS);
+        assert_eq!(histogram[1].0, HOLD_BUCKET_WIDTH);
+        let counts: Vec<usize> = histogram.iter().map(|&(_, n)| n).collect();
+        // Negativas contam como zero e as longas demais vão para a última faixa
+        assert_eq!(&counts[..3], &[3, 1, 1]);
+        assert_eq!(counts[HOLD_BUCKETS - 1], 1);
+        assert_eq!(counts.iter().sum::<usize>(), 6);
+
+        // Uma posse de verdade é registrada quando o recurso é liberado
+        let mut vis = ThreadsVisualizer::new(1);
+        vis.clock = Arc::new(MockClock::new(0.0));
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Writing, Some(1), 0.0);
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Idle, None, 1.25);
+        let counts: Vec<usize> = rb.hold_duration_histogram(1).iter().map(|&(_, n)| n).collect();
+        assert_eq!(counts[2], 1);
+        assert_eq!(counts.iter().sum::<usize>(), 1);
+        assert!(rb.hold_duration_histogram(5).is_empty());
+    }
+
+    #[test]
+    fn resource_search_is_a_case_insensitive_substring_match() {
+        let mut rb = resource_box(3);
+        rb.resources[0].set_name("Disk A");
+        rb.resources[1].set_name("Network");
+        rb.resources[2].set_name("disk b");
+        assert!((0..3).all(|idx| rb.matches_search(idx)));
+
+        rb.search = "DISK".to_string();
+        let matching: Vec<usize> = (0..3).filter(|&idx| rb.matches_search(idx)).collect();
+        assert_eq!(matching, vec![0, 2]);
+        assert!(!rb.matches_search(3));
+    }
 }
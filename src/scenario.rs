@@ -0,0 +1,239 @@
+use std::fmt;
+
+use crate::threads::{ThreadState, MAX_PRIORITY};
+
+/// Uma ação roteirizada: em `time` segundos, a thread `thread` vai para `state`
+/// (usando `resource`, se for leitura/escrita).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScenarioStep {
+    pub time: f64,
+    pub thread: usize,
+    pub state: ThreadState,
+    pub resource: Option<usize>,
+    /// Com `Some`, o passo só muda a prioridade da thread (`state` e `resource` não contam)
+    pub priority: Option<u8>,
+    /// Com `Some`, o passo congela (`true`) ou descongela o recurso `resource`
+    /// (`thread` e `state` não contam)
+    pub frozen: Option<bool>,
+}
+
+/// A ação de volta no formato do roteiro (ver `Scenario::parse`). Leitura/escrita
+/// sem recurso não tem como ser escrita e sai como `release`.
+impl fmt::Display for ScenarioStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let (Some(frozen), Some(res_idx)) = (self.frozen, self.resource) {
+            let action = if frozen { "freeze" } else { "unfreeze" };
+            return write!(f, "{} resource {} {}", self.time, res_idx + 1, action);
+        }
+        write!(f, "{} thread {} ", self.time, self.thread + 1)?;
+        if let Some(priority) = self.priority {
+            return write!(f, "priority {}", priority);
+        }
+        match (self.state, self.resource) {
+            (ThreadState::Reading, Some(res_idx)) => write!(f, "read {}", res_idx + 1),
+            (ThreadState::Writing, Some(res_idx)) => write!(f, "write {}", res_idx + 1),
+            (ThreadState::Waiting, _) => write!(f, "wait"),
+            (ThreadState::Crashed, _) => write!(f, "crash"),
+            _ => write!(f, "release"),
+        }
+    }
+}
+
+/// Linha do tempo de ações, ordenada por tempo.
+///
+/// Formato (uma ação por linha, `#` inicia comentário, índices começam em 1
+/// como na tela):
+///
+/// ```text
+/// 1.0 thread 2 write 1
+/// 2.5 thread 3 read 1
+/// 3.0 thread 2 release
+/// 4.0 thread 3 wait
+/// 5.0 thread 1 crash
+/// 0.0 thread 4 priority 9
+/// 5.5 resource 2 freeze
+/// 7.0 resource 2 unfreeze
+/// 6.0 caption Texto que aparece na tela a partir deste instante
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Scenario {
+    pub steps: Vec<ScenarioStep>,
+    /// Legendas (instante, texto), ordenadas por tempo; cada uma fica até a próxima
+    pub captions: Vec<(f64, String)>,
+}
+
+/// Erro de leitura do roteiro, com a linha problemática.
+#[derive(Debug)]
+pub struct ScenarioError {
+    pub line_number: usize,
+    pub line: String,
+    pub message: String,
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "linha {}: {} (\"{}\")",
+            self.line_number, self.message, self.line
+        )
+    }
+}
+
+/// Roteiros de exemplo que vêm com o programa
+pub const BUNDLED_SCENARIOS: &[(&str, &str)] = &[
+    (
+        "writer-blocked",
+        include_str!("../scenarios/writer_blocked.txt"),
+    ),
+    (
+        "writer-starvation",
+        include_str!("../scenarios/writer_starvation.txt"),
+    ),
+    (
+        "priority-inversion",
+        include_str!("../scenarios/priority_inversion.txt"),
+    ),
+];
+
+impl Scenario {
+    /// Carrega um roteiro pelo nome de um exemplo embutido ou por um caminho de arquivo.
+    pub fn load(name_or_path: &str) -> Result<Self, String> {
+        let text = match BUNDLED_SCENARIOS.iter().find(|(name, _)| *name == name_or_path) {
+            Some((_, text)) => text.to_string(),
+            None => std::fs::read_to_string(name_or_path)
+                .map_err(|e| format!("não foi possível ler {}: {}", name_or_path, e))?,
+        };
+        Self::parse(&text).map_err(|e| format!("{}: {}", name_or_path, e))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ScenarioError> {
+        let mut steps = Vec::new();
+        let mut captions = Vec::new();
+
+        for (i, raw_line) in text.lines().enumerate() {
+            let line = raw_line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: &str| ScenarioError {
+                line_number: i + 1,
+                line: raw_line.trim().to_string(),
+                message: message.to_string(),
+            };
+
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let time: f64 = match tokens.first() {
+                Some(tok) => tok.parse().map_err(|_| error("tempo inválido"))?,
+                None => continue,
+            };
+            if time < 0.0 {
+                return Err(error("tempo não pode ser negativo"));
+            }
+
+            // "<tempo> caption <texto>": o resto da linha, do jeito que foi escrito
+            if tokens.get(1) == Some(&"caption") {
+                let text = line[tokens[0].len()..].trim_start()["caption".len()..].trim();
+                if text.is_empty() {
+                    return Err(error("caption precisa de um texto"));
+                }
+                captions.push((time, text.to_string()));
+                continue;
+            }
+
+            // "<tempo> resource <n> freeze|unfreeze"
+            if tokens.get(1) == Some(&"resource") {
+                let resource = tokens
+                    .get(2)
+                    .and_then(|tok| parse_index(tok))
+                    .ok_or_else(|| error("número de recurso inválido"))?;
+                let frozen = match tokens.get(3) {
+                    Some(&"freeze") => true,
+                    Some(&"unfreeze") => false,
+                    _ => return Err(error("resource precisa de freeze ou unfreeze")),
+                };
+                if tokens.len() > 4 {
+                    return Err(error("argumentos sobrando"));
+                }
+                steps.push(ScenarioStep {
+                    time,
+                    thread: 0,
+                    state: ThreadState::Idle,
+                    resource: Some(resource),
+                    priority: None,
+                    frozen: Some(frozen),
+                });
+                continue;
+            }
+
+            if tokens.len() < 4 || tokens[1] != "thread" {
+                return Err(error("esperado \"<tempo> thread <n> <ação> [recurso]\""));
+            }
+            let thread = parse_index(tokens[2]).ok_or_else(|| error("número de thread inválido"))?;
+
+            if tokens[3] == "priority" {
+                let priority = match tokens.get(4).and_then(|tok| tok.parse::<u8>().ok()) {
+                    Some(p) if p <= MAX_PRIORITY => p,
+                    _ => return Err(error("priority precisa de um número de 0 a 9")),
+                };
+                if tokens.len() > 5 {
+                    return Err(error("argumentos sobrando"));
+                }
+                steps.push(ScenarioStep {
+                    time,
+                    thread,
+                    state: ThreadState::Idle,
+                    resource: None,
+                    priority: Some(priority),
+                    frozen: None,
+                });
+                continue;
+            }
+
+            let (state, needs_resource) = match tokens[3] {
+                "read" => (ThreadState::Reading, true),
+                "write" => (ThreadState::Writing, true),
+                "wait" => (ThreadState::Waiting, false),
+                "release" | "idle" => (ThreadState::Idle, false),
+                "crash" => (ThreadState::Crashed, false),
+                _ => {
+                    return Err(error(
+                        "ação desconhecida (use read, write, wait, release, crash ou priority)",
+                    ))
+                }
+            };
+
+            let resource = match (needs_resource, tokens.get(4)) {
+                (true, Some(tok)) => {
+                    Some(parse_index(tok).ok_or_else(|| error("número de recurso inválido"))?)
+                }
+                (true, None) => return Err(error("read/write precisa de um recurso")),
+                (false, None) => None,
+                (false, Some(_)) => return Err(error("argumentos sobrando")),
+            };
+            if tokens.len() > 5 {
+                return Err(error("argumentos sobrando"));
+            }
+
+            steps.push(ScenarioStep {
+                time,
+                thread,
+                state,
+                resource,
+                priority: None,
+                frozen: None,
+            });
+        }
+
+        // Ordenação estável: ações no mesmo instante mantêm a ordem do arquivo
+        steps.sort_by(|a, b| a.time.total_cmp(&b.time));
+        captions.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(Scenario { steps, captions })
+    }
+}
+
+/// Converte um índice da tela (começando em 1) para índice interno.
+pub(crate) fn parse_index(token: &str) -> Option<usize> {
+    let n: usize = token.trim_start_matches('R').parse().ok()?;
+    n.checked_sub(1)
+}
@@ -0,0 +1,101 @@
+use macroquad::color::hsl_to_rgb;
+use macroquad::prelude::*;
+
+use crate::theme::Theme;
+use crate::threads::{ThreadState, ThreadsVisualizer};
+
+/// Segundos parados até o descanso começar, quando ligado pela tecla sem `--screensaver`
+pub const DEFAULT_SCREENSAVER_DELAY: f64 = 30.0;
+/// Segundos para uma volta completa nas cores
+const CYCLE_PERIOD: f64 = 20.0;
+
+/// Descanso de tela para demonstrações: depois de `delay` segundos sem nenhuma thread
+/// ativa (lendo, escrevendo ou esperando), as cores de Idle das threads e dos recursos
+/// passam a girar devagar. É só desenho: a simulação não muda, e qualquer atividade
+/// desliga o efeito no mesmo frame, então as cores dos estados reais nunca são trocadas.
+#[derive(Debug)]
+pub struct Screensaver {
+    pub enabled: bool,
+    pub delay: f64,
+    /// Desde quando está tudo parado (None se há atividade)
+    quiet_since: Option<f64>,
+}
+
+impl Screensaver {
+    /// Nenhuma thread de `threads_vis` lendo, escrevendo ou esperando
+    pub fn is_quiet(threads_vis: &ThreadsVisualizer) -> bool {
+        !threads_vis
+            .threads
+            .iter()
+            .any(|t| matches!(t.state, ThreadState::Reading | ThreadState::Writing | ThreadState::Waiting))
+    }
+
+    pub fn new(enabled: bool, delay: f64) -> Self {
+        Self {
+            enabled,
+            delay,
+            quiet_since: None,
+        }
+    }
+
+    /// Atualiza o cronômetro de parado com a situação em `now` (ver `is_quiet`).
+    pub fn update(&mut self, quiet: bool, now: f64) {
+        self.quiet_since = if quiet { self.quiet_since.or(Some(now)) } else { None };
+    }
+
+    /// O descanso está aparecendo em `now`?
+    pub fn is_active(&self, now: f64) -> bool {
+        self.enabled && self.quiet_since.is_some_and(|since| now - since >= self.delay)
+    }
+
+    /// `theme` para desenhar este frame: com o descanso ativo, as cores de Idle giram
+    /// no círculo de cores (começando devagar a partir da cor do tema); senão, o próprio `theme`.
+    pub fn theme(&self, theme: &Theme, now: f64) -> Theme {
+        let Some(since) = self.quiet_since.filter(|_| self.is_active(now)) else {
+            return *theme;
+        };
+        let elapsed = now - since - self.delay;
+        let hue = (elapsed / CYCLE_PERIOD).fract() as f32;
+        // Entra aos poucos no primeiro segundo, para não piscar
+        let fade = (elapsed as f32).min(1.0);
+        let cycle = |base: Color, offset: f32| {
+            let target = hsl_to_rgb((hue + offset).fract(), 0.5, 0.6);
+            Color::new(
+                base.r + (target.r - base.r) * fade,
+                base.g + (target.g - base.g) * fade,
+                base.b + (target.b - base.b) * fade,
+                base.a,
+            )
+        };
+        Theme {
+            idle: cycle(theme.idle, 0.0),
+            resource_idle: cycle(theme.resource_idle, 0.5),
+            ..*theme
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::ReaderPreferring;
+    use crate::resource_box::ResourceBox;
+
+    #[test]
+    fn screensaver_only_recolors_a_quiet_simulation() {
+        let rb = ResourceBox::new(Vec2::ZERO, 1, || Box::new(ReaderPreferring));
+        let mut vis = ThreadsVisualizer::new(2);
+        let theme = Theme::light();
+        let mut saver = Screensaver::new(true, 5.0);
+        saver.update(Screensaver::is_quiet(&vis), 0.0);
+        assert!(!saver.is_active(4.0));
+        saver.update(Screensaver::is_quiet(&vis), 6.0);
+        assert!(saver.is_active(6.0));
+        assert_ne!(saver.theme(&theme, 9.0).idle, theme.idle);
+
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Reading, Some(0), 9.0);
+        saver.update(Screensaver::is_quiet(&vis), 9.0);
+        assert!(!saver.is_active(9.0));
+        assert_eq!(saver.theme(&theme, 9.0).idle, theme.idle);
+    }
+}
@@ -0,0 +1,42 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use macroquad::prelude::*;
+
+/// Nome de arquivo com o horário atual, para capturas seguidas não se sobrescreverem.
+pub fn timestamped_path() -> String {
+    timestamped_file("screenshot", "png")
+}
+
+/// `<prefix>-<milissegundos>.<extension>`, com o horário atual.
+pub fn timestamped_file(prefix: &str, extension: &str) -> String {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    format!("{}-{}.{}", prefix, millis, extension)
+}
+
+/// Salva o frame atual em PNG.
+///
+/// Deve ser chamada depois de tudo ter sido desenhado e antes do `next_frame().await`,
+/// para a imagem ser exatamente o que vai aparecer na tela.
+pub fn save_screenshot(path: &str) -> Result<(), String> {
+    let screen = get_screen_data();
+    let (width, height) = (screen.width as usize, screen.height as usize);
+    let row_len = width * 4;
+
+    // O framebuffer vem de baixo para cima; invertemos as linhas
+    let mut bytes = Vec::with_capacity(screen.bytes.len());
+    for row in screen.bytes.chunks_exact(row_len).rev() {
+        bytes.extend_from_slice(row);
+    }
+
+    image::save_buffer(
+        path,
+        &bytes,
+        width as u32,
+        height as u32,
+        image::ColorType::Rgba8,
+    )
+    .map_err(|e| e.to_string())
+}
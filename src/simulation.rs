@@ -0,0 +1,77 @@
+use macroquad::prelude::*;
+
+use crate::config::Config;
+use crate::layout::Layout;
+use crate::logger::{self, Level};
+use crate::policy::{policy_factory, ReaderPreferring};
+use crate::resource_box::ResourceBox;
+use crate::threads::ThreadsVisualizer;
+
+/// Monta a caixa de recursos (com a política `policy`) e as threads conforme a configuração.
+pub fn build_simulation(config: &Config, policy: &str, seed: Option<u64>) -> (ResourceBox, ThreadsVisualizer) {
+    let policy = policy_factory(policy).unwrap_or(|| Box::new(ReaderPreferring));
+    let box_pos = Layout::default().resource_box.point();
+    let mut resource_box = ResourceBox::new(box_pos, config.resources, policy);
+    for (idx, &weight) in config.weights.iter().enumerate() {
+        resource_box.set_weight(idx, weight);
+    }
+    for idx in 0..resource_box.resources.len() {
+        resource_box.set_max_readers(idx, config.max_readers);
+        resource_box.set_max_writers(idx, config.max_writers);
+        resource_box.set_reentrant_reads(idx, config.reentrant_reads);
+    }
+    for &(resource, requires) in &config.dependencies {
+        if let Err(err) = resource_box.add_dependency(resource, requires) {
+            logger::log(Level::Error, || err);
+        }
+    }
+    for (idx, category) in config.categories.iter().enumerate() {
+        let category = Some(category.as_str()).filter(|c| !c.is_empty());
+        resource_box.set_category(idx, category);
+    }
+    resource_box.set_grid(config.grid);
+    resource_box.set_contention_decay(config.hotspot_decay);
+    resource_box.hotspot_threshold = config.hotspot_threshold;
+
+    let mut threads_vis = ThreadsVisualizer::new(config.threads);
+    threads_vis.throughput.window = config.throughput_window;
+    threads_vis.read_hold_range = config.read_hold;
+    threads_vis.write_hold_range = config.write_hold;
+    threads_vis.state_weights = config.state_weights;
+    threads_vis.preset = config.preset;
+    threads_vis.starvation_threshold = config.starvation_threshold;
+    threads_vis.idle_timeout = config.idle_timeout;
+    threads_vis.cooldown = config.cooldown;
+    threads_vis.retry_backoff = config.retry_backoff;
+    threads_vis.backoff_factor = config.backoff_factor;
+    threads_vis.affinity_strength = config.affinity_strength;
+    for &(thread, resource) in &config.affinities {
+        threads_vis.threads[thread].affinity = Some(resource);
+    }
+    threads_vis.set_log_overflow(config.log_overflow);
+    threads_vis.set_log_capacity(config.log_capacity);
+    threads_vis.tree_style.vertical_line = config.tree_lines;
+    threads_vis.tree_style.horizontal_line = config.tree_lines;
+    threads_vis.tree_style.fios = config.fios;
+    threads_vis.seed = seed;
+    (resource_box, threads_vis)
+}
+
+/// Um frame da simulação sem workers. Com roteiro carregado, ele substitui o sorteio
+/// aleatório; senão, liberar as posses expiradas e, quando `tick_due`, sortear de novo.
+pub fn step_simulation(threads_vis: &mut ThreadsVisualizer, resource_box: &ResourceBox, now: f64, tick_due: bool) {
+    threads_vis.release_expired_holds(resource_box, now);
+    if threads_vis.scenario.is_some() {
+        threads_vis.apply_scenario_step(now, resource_box);
+        return;
+    }
+    threads_vis.nudge_idle_threads(resource_box, now);
+    if tick_due {
+        if threads_vis.burst_mode {
+            let n = threads_vis.burst_size;
+            threads_vis.update_threads_burst(resource_box, n, now);
+        } else {
+            threads_vis.update_threads_randomly(resource_box, now);
+        }
+    }
+}
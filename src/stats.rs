@@ -0,0 +1,144 @@
+use std::collections::VecDeque;
+
+use crate::resource_box::ResourceBox;
+use crate::threads::ThreadsVisualizer;
+
+/// Taxa de aquisições bem-sucedidas numa janela deslizante de tempo.
+///
+/// Guarda o instante de cada aquisição e descarta os que saíram da janela,
+/// então a memória fica limitada ao que cabe em `window` segundos.
+#[derive(Clone, Debug)]
+pub struct Throughput {
+    /// Tamanho da janela, em segundos
+    pub window: f64,
+    samples: VecDeque<f64>,
+    /// Aquisições desde o início da execução ou o último `clear`
+    total: u64,
+}
+
+impl Throughput {
+    pub fn new(window: f64) -> Self {
+        Self {
+            window: window.max(f64::EPSILON),
+            samples: VecDeque::new(),
+            total: 0,
+        }
+    }
+
+    /// Registra uma aquisição em `now`.
+    pub fn record(&mut self, now: f64) {
+        self.samples.push_back(now);
+        self.total += 1;
+        self.evict(now);
+    }
+
+    /// Descarta as amostras mais antigas que a janela.
+    pub fn evict(&mut self, now: f64) {
+        while let Some(&oldest) = self.samples.front() {
+            if now - oldest > self.window {
+                self.samples.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Aquisições por segundo na janela que termina em `now`.
+    pub fn rate(&self, now: f64) -> f64 {
+        let recent = self
+            .samples
+            .iter()
+            .rev()
+            .take_while(|&&t| now - t <= self.window)
+            .count();
+        recent as f64 / self.window
+    }
+
+    /// Aquisições desde o início da execução (ou o último `clear`), inclusive as que
+    /// já saíram da janela.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.total = 0;
+    }
+}
+
+/// Latência de aquisição: quanto tempo cada aquisição passou em Waiting, do
+/// primeiro pedido que falhou até conseguir. Aquisições de primeira contam como 0.
+#[derive(Clone, Debug, Default)]
+pub struct AcquireLatency {
+    pub count: u64,
+    pub total: f64,
+    /// Pior caso: (thread, segundos)
+    pub worst: Option<(usize, f64)>,
+}
+
+impl AcquireLatency {
+    /// Registra uma aquisição da thread `thread` depois de `latency` segundos de espera.
+    pub fn record(&mut self, thread: usize, latency: f64) {
+        self.count += 1;
+        self.total += latency;
+        if self.worst.is_none_or(|(_, worst)| latency > worst) {
+            self.worst = Some((thread, latency));
+        }
+    }
+
+    /// Média das latências (None sem nenhuma aquisição).
+    pub fn average(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.total / self.count as f64)
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.worst.map(|(_, latency)| latency)
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Números da sessão inteira, juntados a cada frame por `observe`, para o resumo
+/// mostrado ao sair.
+#[derive(Clone, Debug, Default)]
+pub struct SessionStats {
+    /// Máximo de leitores ao mesmo tempo, somando todos os recursos
+    pub peak_readers: u32,
+    /// Quantas vezes apareceu um deadlock (ver `ThreadsVisualizer::deadlocked_threads`)
+    pub deadlocks: usize,
+    /// Espera mais longa vista: (thread, segundos)
+    pub longest_wait: Option<(usize, f64)>,
+    /// Havia deadlock no último `observe`
+    in_deadlock: bool,
+}
+
+impl SessionStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Atualiza os números com o estado em `now`. Um deadlock só conta de novo
+    /// depois de se desfazer.
+    pub fn observe(&mut self, threads_vis: &ThreadsVisualizer, resource_box: &ResourceBox, now: f64) {
+        let readers = (0..resource_box.resources.len()).map(|idx| resource_box.readers(idx)).sum();
+        self.peak_readers = self.peak_readers.max(readers);
+
+        let deadlocked = !threads_vis.deadlocked_threads().is_empty();
+        if deadlocked && !self.in_deadlock {
+            self.deadlocks += 1;
+        }
+        self.in_deadlock = deadlocked;
+
+        for (i, thread) in threads_vis.threads.iter().enumerate() {
+            let Some(since) = thread.waiting_since else {
+                continue;
+            };
+            let wait = now - since;
+            if self.longest_wait.is_none_or(|(_, longest)| wait > longest) {
+                self.longest_wait = Some((i, wait));
+            }
+        }
+    }
+}
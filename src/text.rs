@@ -0,0 +1,309 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use macroquad::prelude::*;
+
+thread_local! {
+    /// Fonte de todos os textos da cena (ver `set_font`); None usa a padrão do macroquad.
+    /// Só a thread do loop desenha, então basta uma por thread.
+    static FONT: RefCell<Option<Font>> = const { RefCell::new(None) };
+    /// Multiplicador de todos os tamanhos de fonte (ver `set_text_scale`)
+    static TEXT_SCALE: Cell<f32> = const { Cell::new(1.0) };
+    /// Caches de layout (ver `set_text_cache`): largura por tamanho e texto (em dois
+    /// níveis, para buscar com `&str` sem alocar) e linhas já quebradas de
+    /// `draw_wrapped_text` por texto e retângulo
+    static WIDTHS: RefCell<HashMap<u16, HashMap<String, f32>>> = RefCell::new(HashMap::new());
+    static WRAPS: RefCell<HashMap<WrapKey, Rc<[String]>>> = RefCell::new(HashMap::new());
+    static TEXT_CACHE: Cell<bool> = const { Cell::new(true) };
+    /// Medição no lugar do `measure_text` (ver `set_measure`)
+    static MEASURE: Cell<Option<MeasureFn>> = const { Cell::new(None) };
+    static CACHE_STATS: Cell<TextCacheStats> = const { Cell::new(TextCacheStats { hits: 0, misses: 0 }) };
+}
+
+/// Texto, largura, altura e tamanho (já na escala) de uma chamada de `draw_wrapped_text`
+type WrapKey = (String, u32, u32, u16);
+
+/// Largura de um texto num tamanho de fonte já na escala
+pub type MeasureFn = fn(&str, u16) -> f32;
+
+/// Acima disso os caches são esvaziados, para textos que mudam sempre (cronômetros)
+/// não crescerem sem limite
+const MAX_CACHE_ENTRIES: usize = 4096;
+
+/// Acertos e faltas dos caches desde o início (ver `text_cache_stats`)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TextCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Troca a fonte de `draw_label`, `text_width` e `draw_wrapped_text`.
+pub fn set_font(font: Option<Font>) {
+    FONT.set(font);
+    clear_text_cache();
+}
+
+/// Liga ou desliga os caches de layout. Ligado, medir o mesmo texto no mesmo tamanho
+/// e quebrar o mesmo texto no mesmo retângulo só custam na primeira vez; como a chave
+/// é o próprio texto, um rótulo que muda (estado novo da thread) é refeito no mesmo frame.
+pub fn set_text_cache(enabled: bool) {
+    TEXT_CACHE.set(enabled);
+    clear_text_cache();
+}
+
+/// Troca a medição de largura do `measure_text` do macroquad por `measure` (None
+/// volta ao macroquad). Serve para medir o layout sem janela (`benches/text_layout.rs`).
+pub fn set_measure(measure: Option<MeasureFn>) {
+    MEASURE.set(measure);
+    clear_text_cache();
+}
+
+pub fn text_cache_enabled() -> bool {
+    TEXT_CACHE.get()
+}
+
+pub fn text_cache_stats() -> TextCacheStats {
+    CACHE_STATS.get()
+}
+
+fn clear_text_cache() {
+    WIDTHS.with_borrow_mut(HashMap::clear);
+    WRAPS.with_borrow_mut(HashMap::clear);
+}
+
+fn count_lookup(hit: bool) {
+    let mut stats = CACHE_STATS.get();
+    if hit {
+        stats.hits += 1;
+    } else {
+        stats.misses += 1;
+    }
+    CACHE_STATS.set(stats);
+}
+
+/// Escalas de texto que a tecla de acessibilidade percorre
+pub const TEXT_SCALES: &[f32] = &[1.0, 1.25, 1.5, 2.0];
+
+/// Aumenta (ou diminui) todo texto desenhado por este módulo: o `font_size` que os
+/// chamadores passam é multiplicado por `scale`, inclusive no wrap e no corte.
+pub fn set_text_scale(scale: f32) {
+    TEXT_SCALE.set(scale);
+}
+
+pub fn text_scale() -> f32 {
+    TEXT_SCALE.get()
+}
+
+/// Próxima escala de `TEXT_SCALES` depois da atual (volta à primeira depois da última).
+pub fn next_text_scale() -> f32 {
+    let current = text_scale();
+    TEXT_SCALES
+        .iter()
+        .copied()
+        .find(|&scale| scale > current)
+        .unwrap_or(TEXT_SCALES[0])
+}
+
+/// `size` na escala atual, para quem espaça linhas pelo tamanho da fonte.
+pub fn scaled(size: f32) -> f32 {
+    size * text_scale()
+}
+
+/// Marca de texto cortado. Usamos pontos ASCII porque a fonte padrão
+/// do macroquad não garante o glifo "…".
+const ELLIPSIS: &str = "...";
+
+/// Desenha texto com wrap dentro do retângulo (`start_x`, `start_y`, `max_width`, `max_height`).
+///
+/// Uma linha só é desenhada se couber inteira acima de `start_y + max_height`;
+/// quando sobra conteúdo, a última linha desenhada termina em reticências.
+/// `font_size` é multiplicado pela escala de texto, então texto maior quebra e
+/// corta mais cedo no mesmo retângulo.
+pub fn draw_wrapped_text(
+    text: &str,
+    start_x: f32,
+    start_y: f32,
+    max_width: f32,
+    max_height: f32,
+    font_size: f32,
+    color: Color,
+) {
+    let font_size = scaled(font_size);
+    let line_spacing = font_size + 5.0;
+    for (i, line) in wrapped_lines(text, max_width, max_height, font_size).iter().enumerate() {
+        draw_text_at(line, start_x, start_y + i as f32 * line_spacing + font_size, font_size, color);
+    }
+}
+
+/// Linhas de `draw_wrapped_text`, pelo cache de layout quando ligado
+/// (`font_size` já na escala).
+pub fn wrapped_lines(text: &str, max_width: f32, max_height: f32, font_size: f32) -> Rc<[String]> {
+    if !text_cache_enabled() {
+        return layout_wrapped(text, max_width, max_height, font_size).into();
+    }
+    let key = (text.to_string(), max_width.to_bits(), max_height.to_bits(), font_size as u16);
+    let cached = WRAPS.with_borrow(|wraps| wraps.get(&key).cloned());
+    count_lookup(cached.is_some());
+    cached.unwrap_or_else(|| {
+        let lines: Rc<[String]> = layout_wrapped(text, max_width, max_height, font_size).into();
+        WRAPS.with_borrow_mut(|wraps| {
+            if wraps.len() >= MAX_CACHE_ENTRIES {
+                wraps.clear();
+            }
+            wraps.insert(key, Rc::clone(&lines));
+        });
+        lines
+    })
+}
+
+/// Linhas que `draw_wrapped_text` desenha, já cortadas e com reticências
+/// (`font_size` já na escala).
+fn layout_wrapped(text: &str, max_width: f32, max_height: f32, font_size: f32) -> Vec<String> {
+    let line_spacing = font_size + 5.0;
+    let lines = wrap_lines(text, max_width, font_size);
+
+    // Quantas linhas cabem inteiras na altura disponível
+    let fitting = if max_height >= font_size {
+        (((max_height - font_size) / line_spacing).floor() as usize + 1).min(lines.len())
+    } else {
+        0
+    };
+
+    lines
+        .iter()
+        .take(fitting)
+        .enumerate()
+        .map(|(i, (line, cut))| {
+            let is_last = i + 1 == fitting;
+            if *cut || (is_last && fitting < lines.len()) {
+                with_ellipsis(line, max_width, font_size)
+            } else {
+                line.clone()
+            }
+        })
+        .collect()
+}
+
+/// Quebra o texto em linhas que cabem em `max_width`.
+/// O `bool` indica que a linha teve uma palavra cortada por não caber sozinha.
+fn wrap_lines(text: &str, max_width: f32, font_size: f32) -> Vec<(String, bool)> {
+    let mut lines = Vec::new();
+    let space_width = measure(" ", font_size);
+
+    for forced_line in text.split('\n') {
+        let mut current_line = String::new();
+        let mut current_line_width = 0.0;
+
+        for word in forced_line.split_whitespace() {
+            let word_width = measure(word, font_size);
+
+            // Se a palavra sozinha for maior que max_width, truncar
+            if word_width > max_width {
+                if !current_line.is_empty() {
+                    lines.push((std::mem::take(&mut current_line), false));
+                }
+                lines.push((truncate_to_width(word, max_width, font_size), true));
+                current_line_width = 0.0;
+                continue;
+            }
+
+            let space = if current_line.is_empty() { 0.0 } else { space_width };
+            let next_width = current_line_width + space + word_width;
+
+            if next_width <= max_width {
+                if !current_line.is_empty() {
+                    current_line.push(' ');
+                }
+                current_line.push_str(word);
+                current_line_width = next_width;
+            } else {
+                lines.push((std::mem::replace(&mut current_line, word.to_string()), false));
+                current_line_width = word_width;
+            }
+        }
+
+        if !current_line.is_empty() {
+            lines.push((current_line, false));
+        }
+    }
+    lines
+}
+
+/// Maior prefixo de `text` que cabe em `max_width`.
+fn truncate_to_width(text: &str, max_width: f32, font_size: f32) -> String {
+    let mut truncated = String::new();
+    for ch in text.chars() {
+        truncated.push(ch);
+        if measure(&truncated, font_size) > max_width {
+            truncated.pop();
+            break;
+        }
+    }
+    truncated
+}
+
+/// Encurta a linha até caber com as reticências no final.
+fn with_ellipsis(line: &str, max_width: f32, font_size: f32) -> String {
+    let mut base = line.to_string();
+    loop {
+        let candidate = format!("{}{}", base, ELLIPSIS);
+        if base.is_empty() || measure(&candidate, font_size) <= max_width {
+            return candidate;
+        }
+        base.pop();
+    }
+}
+
+/// Largura com `font_size` já na escala
+fn measure(text: &str, font_size: f32) -> f32 {
+    let uncached = || match MEASURE.get() {
+        Some(measure) => measure(text, font_size as u16),
+        None => FONT.with_borrow(|font| measure_text(text, font.as_ref(), font_size as u16, 1.0).width),
+    };
+    if !text_cache_enabled() {
+        return uncached();
+    }
+    let size = font_size as u16;
+    let cached = WIDTHS.with_borrow(|widths| widths.get(&size).and_then(|sized| sized.get(text)).copied());
+    count_lookup(cached.is_some());
+    cached.unwrap_or_else(|| {
+        let width = uncached();
+        WIDTHS.with_borrow_mut(|widths| {
+            let sized = widths.entry(size).or_default();
+            if sized.len() >= MAX_CACHE_ENTRIES {
+                sized.clear();
+            }
+            sized.insert(text.to_string(), width);
+        });
+        width
+    })
+}
+
+/// Desenha uma linha com a fonte configurada (`y` é a linha de base, como no `draw_text`).
+pub fn draw_label(text: &str, x: f32, y: f32, font_size: f32, color: Color) {
+    draw_text_at(text, x, y, scaled(font_size), color);
+}
+
+/// Largura de `text` com a fonte configurada.
+pub fn text_width(text: &str, font_size: f32) -> f32 {
+    measure(text, scaled(font_size))
+}
+
+/// Como `draw_label`, com `font_size` já na escala
+fn draw_text_at(text: &str, x: f32, y: f32, font_size: f32, color: Color) {
+    FONT.with_borrow(|font| {
+        draw_text_ex(
+            text,
+            x,
+            y,
+            TextParams {
+                font: font.as_ref(),
+                font_size: font_size as u16,
+                color,
+                ..Default::default()
+            },
+        );
+    });
+}
+
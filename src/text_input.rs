@@ -0,0 +1,52 @@
+use macroquad::prelude::*;
+
+/// Resultado de um frame de digitação
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextInputEvent {
+    Editing,
+    Submitted,
+    Cancelled,
+}
+
+/// Campo de texto simples lido do teclado (`get_char_pressed`, Backspace, Enter, Escape).
+pub struct TextInput {
+    pub buffer: String,
+    pub max_len: usize,
+}
+
+impl TextInput {
+    /// Começa a digitação com `initial` no campo.
+    /// Descarta os caracteres pendentes (incluindo a tecla que abriu o campo).
+    pub fn new(initial: &str, max_len: usize) -> Self {
+        clear_input_queue();
+        Self {
+            buffer: initial.to_string(),
+            max_len,
+        }
+    }
+
+    /// Lê as teclas deste frame e atualiza o campo.
+    pub fn update(&mut self) -> TextInputEvent {
+        if is_key_pressed(KeyCode::Escape) {
+            return TextInputEvent::Cancelled;
+        }
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter) {
+            return TextInputEvent::Submitted;
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            self.buffer.pop();
+        }
+
+        // `get_char_pressed` desempilha do fim; juntamos e invertemos para manter a ordem
+        let mut typed = Vec::new();
+        while let Some(ch) = get_char_pressed() {
+            typed.push(ch);
+        }
+        for ch in typed.into_iter().rev() {
+            if !ch.is_control() && self.buffer.chars().count() < self.max_len {
+                self.buffer.push(ch);
+            }
+        }
+        TextInputEvent::Editing
+    }
+}
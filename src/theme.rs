@@ -0,0 +1,83 @@
+use macroquad::prelude::*;
+
+/// Cores usadas em todos os desenhos.
+#[derive(Clone, Copy, Debug)]
+pub struct Theme {
+    pub name: &'static str,
+    pub background: Color,
+    pub border: Color,
+    pub text: Color,
+    /// Faixa atrás do texto dos recursos
+    pub text_background: Color,
+    /// Destaques (seleção, barra de peso)
+    pub highlight: Color,
+    /// Alertas (congestionamento, X da thread morta)
+    pub alert: Color,
+
+    // Estados das threads
+    pub reading: Color,
+    pub writing: Color,
+    pub waiting: Color,
+    pub idle: Color,
+    pub crashed: Color,
+
+    // Fundo dos recursos
+    pub resource_reading: Color,
+    pub resource_writing: Color,
+    pub resource_idle: Color,
+    /// Recurso congelado (fora do ar, ver `Resource::set_frozen`)
+    pub resource_frozen: Color,
+}
+
+impl Theme {
+    pub fn light() -> Self {
+        Self {
+            name: "light",
+            background: WHITE,
+            border: BLACK,
+            text: BLACK,
+            text_background: WHITE,
+            highlight: BLUE,
+            alert: RED,
+            reading: GREEN,
+            writing: RED,
+            waiting: ORANGE,
+            idle: GRAY,
+            crashed: Color::new(0.15, 0.15, 0.15, 1.0),
+            resource_reading: Color::new(0.4, 0.8, 0.4, 1.0),
+            resource_writing: Color::new(0.9, 0.4, 0.4, 1.0),
+            resource_idle: Color::new(0.7, 0.7, 0.7, 1.0),
+            resource_frozen: Color::new(0.5, 0.5, 0.55, 1.0),
+        }
+    }
+
+    pub fn dark() -> Self {
+        Self {
+            name: "dark",
+            background: Color::new(0.08, 0.08, 0.1, 1.0),
+            border: Color::new(0.85, 0.85, 0.85, 1.0),
+            text: Color::new(0.95, 0.95, 0.95, 1.0),
+            text_background: Color::new(0.18, 0.18, 0.22, 1.0),
+            highlight: Color::new(0.35, 0.65, 1.0, 1.0),
+            alert: Color::new(1.0, 0.35, 0.35, 1.0),
+            reading: Color::new(0.3, 0.9, 0.4, 1.0),
+            writing: Color::new(1.0, 0.35, 0.35, 1.0),
+            waiting: Color::new(1.0, 0.7, 0.2, 1.0),
+            idle: Color::new(0.55, 0.55, 0.6, 1.0),
+            crashed: Color::new(0.35, 0.2, 0.2, 1.0),
+            resource_reading: Color::new(0.15, 0.45, 0.2, 1.0),
+            resource_writing: Color::new(0.55, 0.15, 0.15, 1.0),
+            resource_idle: Color::new(0.3, 0.3, 0.34, 1.0),
+            resource_frozen: Color::new(0.2, 0.2, 0.22, 1.0),
+        }
+    }
+
+    /// Alterna entre o tema claro e o escuro.
+    pub fn toggled(&self) -> Self {
+        if self.name == "light" {
+            Self::dark()
+        } else {
+            Self::light()
+        }
+    }
+}
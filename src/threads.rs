@@ -1,7 +1,56 @@
 use macroquad::prelude::*;
-use ::rand::random_range;
+use ::rand::rngs::StdRng;
+use ::rand::seq::IndexedRandom;
+use ::rand::{Rng, SeedableRng};
+use std::sync::Arc;
 
-use crate::resource_box::ResourceBox;
+use crate::clock::{Clock, RealClock};
+use crate::layout::{Layout, THREADS_TOP_RATIO};
+use crate::logger::{self, Level};
+use crate::preset::Preset;
+use crate::recording::Recorder;
+use crate::resource_box::{weighted_index, LockCounts, ResourceBox};
+use crate::scenario::{Scenario, ScenarioStep};
+use crate::stats::{AcquireLatency, Throughput};
+use crate::text::{draw_label, draw_wrapped_text, scaled, text_width};
+use crate::theme::Theme;
+use crate::transitions::{LogOverflow, Transition, TransitionLog, DEFAULT_LOG_CAPACITY};
+
+/// Duração (segundos) do pulso desenhado a cada tentativa de aquisição
+const PULSE_DURATION: f64 = 0.5;
+
+/// Sorteios separados de uma mesma thread num tick (ver `decision_rng`)
+const DRAW_STATE: u64 = 0;
+const DRAW_RESOURCE: u64 = 1;
+const DRAW_MODE: u64 = 2;
+const DRAW_HOLD: u64 = 3;
+const DRAW_BURST: u64 = 4;
+
+/// Raio do círculo de estado de cada thread (maior com o número dentro, ver `show_ids`)
+const THREAD_CIRCLE_RADIUS: f32 = 8.0;
+const THREAD_ID_CIRCLE_RADIUS: f32 = 12.0;
+
+/// Com `activity_radius`: quanto o círculo cresce segurando um recurso e encolhe
+/// parado em Idle (fração do raio normal), e quanto tempo leva a transição
+const ACTIVE_RADIUS_SCALE: f32 = 1.4;
+const IDLE_RADIUS_SCALE: f32 = 0.75;
+const RADIUS_EASE_TIME: f64 = 0.3;
+
+/// Caixa de texto de cada thread
+const TEXT_BOX_WIDTH: f32 = 180.0;
+const TEXT_BOX_HEIGHT: f32 = 200.0;
+
+/// Espaço mínimo entre fios. Com fatias mais estreitas que a caixa de texto,
+/// as caixas se alternam em duas fileiras (ver `ThreadLayout::text_box`), então
+/// cada uma pode usar a largura de duas fatias.
+/// Se as threads não couberem, a fileira rola com as setas.
+const MIN_THREAD_SPACING: f32 = TEXT_BOX_WIDTH * 0.5;
+
+/// Deslocamento vertical da segunda fileira de caixas (cabe o nome e o estado)
+const STAGGER_ROW_HEIGHT: f32 = 50.0;
+
+/// Folga entre caixas de texto vizinhas
+const TEXT_BOX_GAP: f32 = 4.0;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ThreadState {
@@ -9,6 +58,54 @@ pub enum ThreadState {
     Writing,
     Waiting,
     Idle,
+    /// A thread morreu: libera o que segurava e não participa mais do sorteio
+    Crashed,
+}
+
+/// Distância mínima da linha horizontal às bordas de cima e de baixo, como fração da tela
+const MIN_VERTICAL_END_RATIO: f32 = 0.05;
+
+/// Maior prioridade de uma thread (um dígito, para caber no distintivo)
+pub const MAX_PRIORITY: u8 = 9;
+
+/// Chance padrão de uma thread com afinidade sortear o recurso preferido
+pub const DEFAULT_AFFINITY_STRENGTH: f64 = 0.7;
+
+/// Quanto o recuo cresce a cada falha quando `--backoff-factor` não é passado
+pub const DEFAULT_BACKOFF_FACTOR: f64 = 2.0;
+/// Maior espera (segundos) entre duas tentativas com recuo
+pub const MAX_BACKOFF: f64 = 30.0;
+
+/// Estados sorteados em `update_threads_randomly`, na ordem de `state_weights`
+pub const RANDOM_STATES: [ThreadState; 4] = [
+    ThreadState::Idle,
+    ThreadState::Waiting,
+    ThreadState::Reading,
+    ThreadState::Writing,
+];
+
+impl ThreadState {
+    /// Posição do estado nos contadores de `count_by_state`
+    pub fn index(self) -> usize {
+        match self {
+            ThreadState::Reading => 0,
+            ThreadState::Writing => 1,
+            ThreadState::Waiting => 2,
+            ThreadState::Idle => 3,
+            ThreadState::Crashed => 4,
+        }
+    }
+
+    /// Nome do estado como aparece no rótulo da thread
+    pub fn label(self) -> &'static str {
+        match self {
+            ThreadState::Reading => "Reading",
+            ThreadState::Writing => "Writing",
+            ThreadState::Waiting => "Waiting",
+            ThreadState::Idle => "Idle",
+            ThreadState::Crashed => "Crashed",
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -16,127 +113,996 @@ pub struct ThreadInfo {
     pub name: String,
     pub state: ThreadState,
     pub resource_in_use: Option<usize>,
+    /// Instantes (em segundos) em que a thread pegou e em que vai liberar o recurso que está segurando
+    pub hold_started: Option<f64>,
+    pub hold_until: Option<f64>,
+    /// Última tentativa de pegar um recurso: (instante, conseguiu?)
+    pub last_attempt: Option<(f64, bool)>,
+    /// O que a thread está tentando pegar (recurso e Reading/Writing).
+    /// No modo demanda uma thread bloqueada insiste nesse alvo a cada tick.
+    pub intended_resource: Option<usize>,
+    pub intended_mode: Option<ThreadState>,
+    /// Desde quando a thread está em Waiting sem parar (None se não está esperando)
+    pub waiting_since: Option<f64>,
+    /// Desde quando a thread está em Idle (None se não está, ou se ainda não foi notada)
+    pub idle_since: Option<f64>,
+    /// Prioridade na fila de espera (maior passa na frente; empate é por ordem de chegada)
+    pub priority: u8,
+    /// Pré-requisitos já pegos a caminho de `intended_resource` (ver
+    /// `ResourceBox::dependencies`), com o modo em que foram pegos
+    pub held_dependencies: Vec<(usize, ThreadState)>,
+    /// Última vez que o estado mudou (None se nunca mudou), para o `cooldown`
+    pub last_change_time: Option<f64>,
+    /// Recurso preferido: o sorteio cai nele com chance `affinity_strength`
+    /// (ver `ThreadsVisualizer::pick_resource`)
+    pub affinity: Option<usize>,
+    /// Recuo entre tentativas de quem está preso a um alvo (ver `retry_backoff`):
+    /// quando pode tentar de novo e o intervalo atual (0 = sem recuo)
+    pub next_retry_time: Option<f64>,
+    pub backoff: f64,
+}
+
+impl ThreadInfo {
+    /// Rótulo desenhado sob a thread: "nome (Rn)" (ou "nome (-> Rn)" esperando por
+    /// um recurso, ou só o nome sem recurso) e o estado na linha de baixo.
+    pub fn display_label(&self) -> String {
+        self.label_with_state(self.state.label())
+    }
+
+    /// Como `display_label`, com `state_text` na segunda linha
+    fn label_with_state(&self, state_text: &str) -> String {
+        let resource_str = match (self.resource_in_use, self.state) {
+            (Some(res_idx), _) => Some(format!("(R{})", res_idx + 1)),
+            (None, ThreadState::Waiting) => self.intended_resource.map(|res_idx| format!("(-> R{})", res_idx + 1)),
+            _ => None,
+        };
+        match resource_str {
+            Some(resource_str) => format!("{} {}\n{}", self.name, resource_str, state_text),
+            None => format!("{}\n{}", self.name, state_text),
+        }
+    }
+
+    /// Thread `index` recém-criada: Idle, sem recurso, com o nome "Thread {index + 1}".
+    fn idle(index: usize) -> Self {
+        Self {
+            name: format!("Thread {}", index + 1),
+            state: ThreadState::Idle,
+            resource_in_use: None,
+            hold_started: None,
+            hold_until: None,
+            last_attempt: None,
+            intended_resource: None,
+            intended_mode: None,
+            waiting_since: None,
+            idle_since: None,
+            priority: 0,
+            held_dependencies: Vec::new(),
+            last_change_time: None,
+            affinity: None,
+            next_retry_time: None,
+            backoff: 0.0,
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct ThreadsVisualizer {
     pub threads: Vec<ThreadInfo>,
+    /// Zona da tela das threads (ver `Layout::compute`): a linha horizontal fica
+    /// no topo e as caixas de texto não passam do fundo
+    pub area: Rect,
+    /// Altura da linha horizontal como fração da tela, usada para calcular `area`
+    /// (ver `Layout::with_threads_top` e `set_vertical_end_ratio`)
     pub vertical_end_ratio: f32,
+    /// Meia largura máxima da linha horizontal (ver `half_length`)
     pub horizontal_half_length: f32,
     pub thread_line_length: f32,
+    /// Quais linhas da "árvore" desenhar, e com que cor e espessura
+    pub tree_style: TreeStyle,
+    /// Intervalos (min, max) em segundos do tempo que uma thread segura um recurso
+    /// lendo e escrevendo (ver `set_read_hold_range`/`set_write_hold_range`)
+    pub read_hold_range: (f64, f64),
+    pub write_hold_range: (f64, f64),
+    /// Peso de cada estado de `RANDOM_STATES` no sorteio (ver `set_state_weights`)
+    pub state_weights: [f32; 4],
+    /// Última carga aplicada com `apply_preset` (mostrada na tela)
+    pub preset: Option<Preset>,
+    /// Roteiro carregado (substitui o sorteio aleatório), o próximo passo a aplicar
+    /// e o instante em que o roteiro começou
+    pub scenario: Option<Scenario>,
+    pub scenario_cursor: usize,
+    pub scenario_start: f64,
+    /// Passos do roteiro que chegaram enquanto a thread estava preemptada (ver
+    /// `is_preempted`); são aplicados, na ordem, assim que ela puder rodar
+    pub scenario_deferred: Vec<ScenarioStep>,
+    /// Thread selecionada pelo teclado (para os controles manuais)
+    pub selected_thread: Option<usize>,
+    /// Thread sob o mouse, posta pelo `main` a cada frame
+    pub hovered_thread: Option<usize>,
+    /// Threads que seguram o recurso em foco, contornadas no `draw`
+    /// (ver `sync_sharing_highlight`)
+    pub highlighted_threads: Vec<usize>,
+    /// Modo compacto: só os círculos coloridos, sem texto
+    pub compact: bool,
+    /// Número da thread dentro do círculo (que cresce para caber)
+    pub show_ids: bool,
+    /// Círculo maior para quem segura um recurso e menor para quem está parado
+    /// (ver `activity_radius_at`)
+    pub activity_radius: bool,
+    /// Primeira thread visível quando nem todas cabem na linha horizontal
+    pub scroll_offset: usize,
+    /// Modo rajada: a cada tick só `burst_size` threads mudam
+    pub burst_mode: bool,
+    pub burst_size: usize,
+    /// Modo demanda: cada thread mantém o alvo até conseguir, em vez de sortear outro
+    pub demand_mode: bool,
+    /// Aquisição bloqueante: quem falhou fica preso ao mesmo recurso e modo, tentando
+    /// de novo a cada tick até conseguir. Desligado, a espera é só um try-lock que
+    /// falhou e o próximo sorteio pode abandoná-la (ver `is_blocked_waiter`)
+    pub blocking_mode: bool,
+    /// Herança de prioridade: quem segura um recurso roda com a maior prioridade
+    /// entre ele e quem espera por esse recurso (ver `effective_priority`)
+    pub priority_inheritance: bool,
+    /// Espera (segundos) a partir da qual uma thread conta como em inanição
+    pub starvation_threshold: f64,
+    /// Depois de quantos segundos parada em Idle uma thread tenta pegar um recurso
+    /// sozinha (ver `nudge_idle_threads`); None desliga
+    pub idle_timeout: Option<f64>,
+    /// Segundos depois de mudar de estado em que a thread fica fora do sorteio
+    /// (ver `in_cooldown`); 0 desliga
+    pub cooldown: f64,
+    /// Chance (0 a 1) de uma thread com `affinity` sortear o recurso preferido
+    pub affinity_strength: f64,
+    /// Recuo exponencial de quem insiste num alvo (modo bloqueante ou demanda):
+    /// a primeira falha espera `retry_backoff` segundos antes de tentar de novo e
+    /// cada nova falha multiplica a espera por `backoff_factor`, até `MAX_BACKOFF`.
+    /// Conseguir o recurso zera o recuo. 0 desliga (tenta a cada tick)
+    pub retry_backoff: f64,
+    pub backoff_factor: f64,
+    /// Semente dos sorteios: com ela, cada thread faz as mesmas escolhas a cada tick,
+    /// não importa o que as outras fizeram (ver `decision_rng`); None sorteia de verdade
+    pub seed: Option<u64>,
+    /// Rodadas de sorteio já feitas (`update_threads_randomly`/`update_threads_burst`);
+    /// zera no `reset`, que com `seed` recomeça a mesma sequência
+    pub tick: u64,
+    /// Aquisições por segundo (janela deslizante)
+    pub throughput: Throughput,
+    /// Tentativas de aquisição que falharam desde o início da execução; zera no `reset`,
+    /// como `throughput`
+    pub failed_attempts: u64,
+    /// Quanto as aquisições esperaram em Waiting (ver `AcquireLatency`); zera no `reset`
+    pub latency: AcquireLatency,
+    /// Últimas mudanças de estado, com o motivo das aquisições que falharam
+    pub log: TransitionLog,
+    /// Gravação em andamento das decisões (ver `record_to`)
+    pub recorder: Option<Recorder>,
+    /// De onde vem o `now` da simulação e do desenho (`MockClock` nos testes)
+    pub clock: Arc<dyn Clock>,
+}
+
+/// Aparência da "árvore": a linha vertical central, a linha horizontal e os fios
+/// de cada thread. Desligadas, as threads ficam no mesmo lugar, sobre uma linha invisível.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TreeStyle {
+    pub vertical_line: bool,
+    pub horizontal_line: bool,
+    pub fios: bool,
+    /// Cor das linhas e dos fios; None usa `theme.border`
+    pub color: Option<Color>,
+    /// Espessura das linhas vertical e horizontal
+    pub thickness: f32,
+    pub fio_thickness: f32,
+}
+
+impl Default for TreeStyle {
+    fn default() -> Self {
+        Self {
+            vertical_line: true,
+            horizontal_line: true,
+            fios: true,
+            color: None,
+            thickness: 3.0,
+            fio_thickness: 2.0,
+        }
+    }
+}
+
+/// Posição dos fios na linha horizontal: quais threads aparecem e onde.
+pub struct ThreadLayout {
+    pub left_x: f32,
+    pub segment_width: f32,
+    pub first_visible: usize,
+    pub visible_count: usize,
+    /// Altura da linha horizontal (topo dos fios) e da ponta de baixo dos fios
+    pub y_top: f32,
+    pub y_bottom: f32,
+    /// Limite de baixo das caixas de texto (fundo da zona das threads)
+    pub max_y: f32,
+    /// Largura das caixas de texto e se elas se alternam em duas fileiras
+    pub text_box_width: f32,
+    pub staggered: bool,
+}
+
+impl ThreadLayout {
+    /// A thread `i` está entre as desenhadas?
+    pub fn is_visible(&self, i: usize) -> bool {
+        i >= self.first_visible && i < self.first_visible + self.visible_count
+    }
+
+    /// X do fio da thread `i` (cada fio fica no meio da sua fatia).
+    pub fn fio_x(&self, i: usize) -> f32 {
+        self.left_x + self.segment_width * ((i - self.first_visible) as f32 + 0.5)
+    }
+
+    /// Centro do círculo da thread `i`, no meio do fio.
+    pub fn circle_center(&self, i: usize) -> Vec2 {
+        vec2(self.fio_x(i), (self.y_top + self.y_bottom) * 0.5)
+    }
+
+    /// Caixa de texto da thread `i`, logo abaixo do fio. Nas fileiras alternadas,
+    /// a de cima fica com pouco menos de `STAGGER_ROW_HEIGHT` de altura para não
+    /// encostar na de baixo, que começa logo depois.
+    pub fn text_box(&self, i: usize) -> Rect {
+        let (y_offset, height) = if !self.staggered {
+            (0.0, TEXT_BOX_HEIGHT)
+        } else if (i - self.first_visible).is_multiple_of(2) {
+            (0.0, STAGGER_ROW_HEIGHT - TEXT_BOX_GAP)
+        } else {
+            (STAGGER_ROW_HEIGHT, TEXT_BOX_HEIGHT - STAGGER_ROW_HEIGHT)
+        };
+        let y = self.y_bottom + 20.0 + y_offset;
+        Rect::new(
+            self.fio_x(i) - self.text_box_width * 0.5,
+            y,
+            self.text_box_width,
+            height.min(self.max_y - y).max(0.0),
+        )
+    }
 }
 
 impl ThreadsVisualizer {
     pub fn new(num_threads: usize) -> Self {
-        let mut threads = Vec::with_capacity(num_threads);
-        for i in 0..num_threads {
-            threads.push(ThreadInfo {
-                name: format!("Thread {}", i + 1),
-                state: ThreadState::Idle,
-                resource_in_use: None,
-            });
-        }
+        let threads = (0..num_threads).map(ThreadInfo::idle).collect();
         Self {
             threads,
-            vertical_end_ratio: 0.45,
+            area: Layout::default().threads,
+            vertical_end_ratio: THREADS_TOP_RATIO,
             horizontal_half_length: 800.0,
             thread_line_length: 100.0,
+            tree_style: TreeStyle::default(),
+            read_hold_range: (1.0, 4.0),
+            write_hold_range: (1.5, 6.0),
+            state_weights: [1.0; 4],
+            preset: None,
+            scenario: None,
+            scenario_cursor: 0,
+            scenario_start: 0.0,
+            scenario_deferred: Vec::new(),
+            selected_thread: None,
+            hovered_thread: None,
+            highlighted_threads: Vec::new(),
+            compact: false,
+            show_ids: false,
+            activity_radius: false,
+            scroll_offset: 0,
+            burst_mode: false,
+            burst_size: 2,
+            demand_mode: false,
+            blocking_mode: false,
+            priority_inheritance: false,
+            starvation_threshold: 8.0,
+            idle_timeout: None,
+            cooldown: 0.0,
+            retry_backoff: 0.0,
+            backoff_factor: DEFAULT_BACKOFF_FACTOR,
+            affinity_strength: DEFAULT_AFFINITY_STRENGTH,
+            seed: None,
+            tick: 0,
+            throughput: Throughput::new(1.0),
+            failed_attempts: 0,
+            latency: AcquireLatency::default(),
+            log: TransitionLog::new(DEFAULT_LOG_CAPACITY),
+            recorder: None,
+            clock: Arc::new(RealClock),
+        }
+    }
+
+    /// Cria as threads já nos estados pedidos, passando cada aquisição pelo caminho
+    /// normal (`set_thread_resource_state`) para os contadores dos recursos baterem
+    /// desde o primeiro frame. Uma aquisição em conflito vira Waiting, como sempre.
+    /// As posses criadas assim não expiram sozinhas.
+    pub fn from_states(states: Vec<(ThreadState, Option<usize>)>, resource_box: &ResourceBox) -> Self {
+        let mut vis = Self::new(states.len());
+        for (i, (state, resource)) in states.into_iter().enumerate() {
+            vis.set_thread_resource_state(resource_box, i, state, resource, 0.0);
+            let thread = &mut vis.threads[i];
+            thread.hold_started = None;
+            thread.hold_until = None;
+            thread.last_attempt = None;
+            if let (ThreadState::Writing, Some(res_idx)) = (thread.state, thread.resource_in_use) {
+                resource_box.set_write_hold(res_idx, 0.0, None);
+            }
+        }
+        vis.throughput.clear();
+        vis.log.clear();
+        vis
+    }
+
+    /// Altura da linha horizontal como fração da tela, mantida dentro de (0, 1).
+    pub fn set_vertical_end_ratio(&mut self, ratio: f32) {
+        self.vertical_end_ratio = ratio.clamp(MIN_VERTICAL_END_RATIO, 1.0 - MIN_VERTICAL_END_RATIO);
+    }
+
+    /// Comprimento dos fios (não negativo).
+    pub fn set_thread_line_length(&mut self, length: f32) {
+        self.thread_line_length = length.max(0.0);
+    }
+
+    /// Intervalo das posses de leitura; recusa `min > max` e valores negativos.
+    pub fn set_read_hold_range(&mut self, min: f64, max: f64) -> Result<(), String> {
+        validate_hold_range(min, max)?;
+        self.read_hold_range = (min, max);
+        Ok(())
+    }
+
+    /// Intervalo das posses de escrita, validado como em `set_read_hold_range`.
+    pub fn set_write_hold_range(&mut self, min: f64, max: f64) -> Result<(), String> {
+        validate_hold_range(min, max)?;
+        self.write_hold_range = (min, max);
+        Ok(())
+    }
+
+    /// Quantas transições o `log` guarda no máximo (ver `TransitionLog::set_capacity`).
+    pub fn set_log_capacity(&mut self, capacity: usize) {
+        self.log.set_capacity(capacity);
+    }
+
+    /// O que o `log` faz quando enche: sair a mais antiga ou parar de registrar.
+    pub fn set_log_overflow(&mut self, overflow: LogOverflow) {
+        self.log.overflow = overflow;
+    }
+
+    /// Troca de uma vez os pesos de estado e os tempos de posse pelos da carga `preset`.
+    pub fn apply_preset(&mut self, preset: Preset) {
+        let (read_hold_range, write_hold_range) = preset.hold_ranges();
+        self.state_weights = preset.state_weights();
+        self.read_hold_range = read_hold_range;
+        self.write_hold_range = write_hold_range;
+        self.preset = Some(preset);
+    }
+
+    /// Meia largura efetiva da linha horizontal: `horizontal_half_length`,
+    /// limitada à metade da largura de `area`.
+    pub fn half_length(&self) -> f32 {
+        self.horizontal_half_length.min(self.area.w * 0.5)
+    }
+
+    /// Pesos do sorteio de estado (Idle, Waiting, Reading, Writing);
+    /// recusa pesos negativos ou todos zero.
+    pub fn set_state_weights(&mut self, weights: [f32; 4]) -> Result<(), String> {
+        validate_state_weights(&weights)?;
+        self.state_weights = weights;
+        Ok(())
+    }
+
+    /// Sorteia um dos `RANDOM_STATES` conforme `state_weights`.
+    pub fn roll_state(&self, rng: &mut impl Rng) -> ThreadState {
+        let total: f32 = self.state_weights.iter().sum();
+        weighted_index(&self.state_weights, rng.random_range(0.0..total))
+            .map_or(ThreadState::Idle, |i| RANDOM_STATES[i])
+    }
+
+    /// Gerador para o sorteio `draw` da thread `thread` no tick atual. Com `seed`, ele
+    /// depende só desses três números: duas simulações com a mesma semente recebem as
+    /// mesmas decisões, mesmo que a ordem de atendimento ou os resultados sejam outros.
+    fn decision_rng(&self, thread: usize, draw: u64) -> StdRng {
+        match self.seed {
+            Some(seed) => {
+                let mixed = [self.tick, thread as u64, draw]
+                    .into_iter()
+                    .fold(seed, |acc, value| splitmix64(acc ^ value));
+                StdRng::seed_from_u64(mixed)
+            }
+            None => StdRng::from_rng(&mut ::rand::rng()),
+        }
+    }
+
+    /// Quantas threads cabem na linha horizontal respeitando `MIN_THREAD_SPACING`.
+    pub fn max_visible_threads(&self) -> usize {
+        let available = self.half_length() * 2.0;
+        ((available / MIN_THREAD_SPACING).floor() as usize).max(1)
+    }
+
+    /// Calcula quais threads são desenhadas e o espaçamento entre elas.
+    pub fn layout(&self) -> ThreadLayout {
+        let center_x = self.area.center().x;
+        let y_top = self.area.y;
+        let n = self.threads.len();
+        let visible_count = n.min(self.max_visible_threads());
+        let first_visible = self.scroll_offset.min(n - visible_count);
+        let half_length = self.half_length();
+        let segment_width = (half_length * 2.0) / visible_count.max(1) as f32;
+
+        // Caixas mais largas que a fatia: alterna as fileiras (cada caixa ganha duas
+        // fatias) ou, com uma thread só, encolhe a caixa para caber
+        let staggered = visible_count > 1 && segment_width < TEXT_BOX_WIDTH;
+        let text_box_width = if segment_width >= TEXT_BOX_WIDTH {
+            TEXT_BOX_WIDTH
+        } else if staggered {
+            (segment_width * 2.0 - TEXT_BOX_GAP).min(TEXT_BOX_WIDTH)
+        } else {
+            segment_width
+        };
+        ThreadLayout {
+            left_x: center_x - half_length,
+            segment_width,
+            first_visible,
+            visible_count,
+            y_top,
+            y_bottom: y_top + self.thread_line_length,
+            max_y: self.area.bottom(),
+            text_box_width,
+            staggered,
+        }
+    }
+
+    /// Raio do círculo de estado, conforme `show_ids`.
+    pub fn circle_radius(&self) -> f32 {
+        if self.show_ids {
+            THREAD_ID_CIRCLE_RADIUS
+        } else {
+            THREAD_CIRCLE_RADIUS
+        }
+    }
+
+    /// Raio do círculo da thread `i` em `now` com `activity_radius`: vai do normal ao
+    /// ativo (ou ao parado) em `RADIUS_EASE_TIME` depois de pegar o recurso (ou de
+    /// ficar Idle), sem passar de `max_radius` para não encostar nos vizinhos.
+    pub fn activity_radius_at(&self, i: usize, now: f64, max_radius: f32) -> f32 {
+        let base = self.circle_radius();
+        let Some(thread) = self.threads.get(i).filter(|_| self.activity_radius) else {
+            return base;
+        };
+        // Posse roteirizada não tem `hold_started`: conta da aquisição bem-sucedida
+        let acquired = thread
+            .hold_started
+            .or(thread.last_attempt.filter(|&(_, ok)| ok).map(|(t, _)| t));
+        let (since, scale) = match (thread.state, acquired, thread.idle_since) {
+            (ThreadState::Reading | ThreadState::Writing, Some(start), _) => (start, ACTIVE_RADIUS_SCALE),
+            (ThreadState::Idle, _, Some(since)) => (since, IDLE_RADIUS_SCALE),
+            _ => return base.min(max_radius),
+        };
+        let t = ((now - since) / RADIUS_EASE_TIME).clamp(0.0, 1.0) as f32;
+        (base + (base * scale - base) * t).min(max_radius)
+    }
+
+    /// Acrescenta uma thread Idle no fim da fileira, com o próximo nome da sequência.
+    pub fn add_thread(&mut self) {
+        self.threads.push(ThreadInfo::idle(self.threads.len()));
+    }
+
+    /// Tira a última thread, devolvendo antes o recurso que ela segura para os
+    /// contadores continuarem certos. A última thread que sobra não sai; retorna se tirou.
+    pub fn remove_thread(&mut self, resource_box: &ResourceBox, now: f64) -> bool {
+        if self.threads.len() <= 1 {
+            return false;
+        }
+        let index = self.threads.len() - 1;
+        self.set_thread_resource_state(resource_box, index, ThreadState::Idle, None, now);
+        self.threads.pop();
+        // Só a última sai, então os outros índices continuam valendo
+        if self.selected_thread == Some(index) {
+            self.selected_thread = None;
         }
+        if self.hovered_thread == Some(index) {
+            self.hovered_thread = None;
+        }
+        self.scroll_threads(0);
+        true
+    }
+
+    /// Thread visível cujo círculo está sob `point` (com uma folga para facilitar o clique).
+    pub fn thread_at(&self, point: Vec2) -> Option<usize> {
+        let layout = self.layout();
+        (0..self.threads.len())
+            .filter(|&i| layout.is_visible(i))
+            .find(|&i| layout.circle_center(i).distance(point) <= self.circle_radius() + 6.0)
+    }
+
+    /// Rola a fileira de threads em `delta` posições (negativo = para a esquerda).
+    pub fn scroll_threads(&mut self, delta: isize) {
+        let hidden = self.threads.len().saturating_sub(self.max_visible_threads());
+        self.scroll_offset = self.scroll_offset.saturating_add_signed(delta).min(hidden);
     }
 
     /// Desenha as threads (com bounding box e wrap)
-    pub fn draw(&self) {
-        let sw = screen_width();
-        let sh = screen_height();
+    pub fn draw(&self, theme: &Theme) {
+        let center_x = self.area.center().x;
+        let vertical_end_y = self.area.y;
 
-        let center_x = sw * 0.5;
-        let vertical_end_y = sh * self.vertical_end_ratio;
+        let style = self.tree_style;
+        let line_color = style.color.unwrap_or(theme.border);
 
-        // Linha vertical
-        draw_line(center_x, 0.0, center_x, vertical_end_y, 3.0, BLACK);
+        // Linha vertical, do topo da tela até a zona das threads
+        if style.vertical_line {
+            draw_line(center_x, 0.0, center_x, vertical_end_y, style.thickness, line_color);
+        }
 
         // Linha horizontal
-        let left_x = center_x - self.horizontal_half_length;
-        let right_x = center_x + self.horizontal_half_length;
-        draw_line(left_x, vertical_end_y, right_x, vertical_end_y, 3.0, BLACK);
+        let half_length = self.half_length();
+        let left_x = center_x - half_length;
+        let right_x = center_x + half_length;
+        if style.horizontal_line {
+            draw_line(left_x, vertical_end_y, right_x, vertical_end_y, style.thickness, line_color);
+        }
 
         let n = self.threads.len();
         if n == 0 {
             return;
         }
 
-        let segment_width = (self.horizontal_half_length * 2.0) / (n as f32 + 1.0);
-        let text_box_width = 180.0;
-        let text_box_height = 200.0;
+        let now = self.clock.now();
+        let layout = self.layout();
+        let first = layout.first_visible;
+        let last = first + layout.visible_count;
+
+        // Setas indicando threads escondidas de cada lado
+        if first > 0 {
+            draw_label("<", left_x - 20.0, vertical_end_y + 8.0, 30.0, theme.text);
+        }
+        if last < n {
+            draw_label(">", right_x + 8.0, vertical_end_y + 8.0, 30.0, theme.text);
+        }
 
-        for (i, thread_info) in self.threads.iter().enumerate() {
-            let x_fio = left_x + segment_width * (i as f32 + 1.0);
-            let y_top = vertical_end_y;
-            let y_bottom = vertical_end_y + self.thread_line_length;
+        for (i, thread_info) in self.threads.iter().enumerate().take(last).skip(first) {
+            let x_fio = layout.fio_x(i);
+            let y_top = layout.y_top;
+            let y_bottom = layout.y_bottom;
 
             // Fio
-            draw_line(x_fio, y_top, x_fio, y_bottom, 2.0, BLACK);
+            if style.fios {
+                draw_line(x_fio, y_top, x_fio, y_bottom, style.fio_thickness, line_color);
+            }
 
             // Cor / texto
-            let (state_text, state_color) = match thread_info.state {
-                ThreadState::Reading => ("Reading", GREEN),
-                ThreadState::Writing => ("Writing", RED),
-                ThreadState::Waiting => ("Waiting", ORANGE),
-                ThreadState::Idle => ("Idle", GRAY),
+            let state_color = match thread_info.state {
+                ThreadState::Reading => theme.reading,
+                ThreadState::Writing => theme.writing,
+                ThreadState::Waiting => theme.waiting,
+                ThreadState::Idle => theme.idle,
+                ThreadState::Crashed => theme.crashed,
             };
 
             // Círculo
-            let mid_y = (y_top + y_bottom) * 0.5;
-            draw_circle(x_fio, mid_y, 8.0, state_color);
+            let mid_y = layout.circle_center(i).y;
+            let radius = self.activity_radius_at(i, now, layout.segment_width * 0.5 - 2.0);
+            draw_circle(x_fio, mid_y, radius, state_color);
 
-            // Recurso
-            let resource_str = if let Some(res_idx) = thread_info.resource_in_use {
-                format!("(R{})", res_idx + 1)
-            } else {
-                "".to_string()
-            };
+            // Número da thread, centrado no círculo
+            if self.show_ids {
+                let label = (i + 1).to_string();
+                let font_size = if label.len() > 1 { 15.0 } else { 18.0 };
+                let width = text_width(&label, font_size);
+                draw_label(&label, x_fio - width * 0.5, mid_y + font_size * 0.3, font_size, WHITE);
+            }
+
+            // Espera bloqueante: um quadrado em volta, a thread não sai dali até conseguir.
+            // No recuo o quadrado fica apagado e um arco esvazia até a próxima tentativa
+            if self.is_backing_off(i, now) {
+                let side = radius * 2.0 + 8.0;
+                let faded = Color::new(state_color.r, state_color.g, state_color.b, 0.35);
+                draw_rectangle_lines(x_fio - side * 0.5, mid_y - side * 0.5, side, side, 2.0, faded);
+                if let Some(at) = thread_info.next_retry_time.filter(|_| thread_info.backoff > 0.0) {
+                    let left = ((at - now) / thread_info.backoff).clamp(0.0, 1.0) as f32;
+                    draw_arc(x_fio, mid_y, 32, side * 0.5 + 4.0, -90.0, 2.0, 360.0 * left, theme.border);
+                }
+            } else if self.is_blocked_waiter(i) {
+                let side = radius * 2.0 + 8.0;
+                draw_rectangle_lines(x_fio - side * 0.5, mid_y - side * 0.5, side, side, 2.0, state_color);
+            }
+
+            // Anel de progresso da posse: enche de cima, no sentido horário, até `hold_until`
+            if let (Some(start), Some(until)) = (thread_info.hold_started, thread_info.hold_until) {
+                if until > start {
+                    let progress = ((now - start) / (until - start)).clamp(0.0, 1.0) as f32;
+                    draw_arc(x_fio, mid_y, 32, radius + 3.0, -90.0, 3.0, 360.0 * progress, state_color);
+                }
+            }
+
+            // Pulso da última tentativa de aquisição: verde se conseguiu, vermelho se bloqueou
+            if let Some((attempt_time, ok)) = thread_info.last_attempt {
+                let t = ((now - attempt_time) / PULSE_DURATION) as f32;
+                if (0.0..1.0).contains(&t) {
+                    let base = if ok { theme.reading } else { theme.alert };
+                    let color = Color::new(base.r, base.g, base.b, 1.0 - t);
+                    draw_circle_lines(x_fio, mid_y, radius + 20.0 * t, 3.0, color);
+                }
+            }
+
+            // Thread morta: um X por cima do círculo
+            if thread_info.state == ThreadState::Crashed {
+                let r = radius;
+                draw_line(x_fio - r, mid_y - r, x_fio + r, mid_y + r, 2.0, theme.alert);
+                draw_line(x_fio - r, mid_y + r, x_fio + r, mid_y - r, 2.0, theme.alert);
+            }
+
+            // Prioridade: número num distintivo no canto do círculo (a herdada, em alerta)
+            let priority = self.effective_priority(i);
+            if priority > 0 {
+                let (bx, by) = (x_fio + radius + 1.0, mid_y - radius - 1.0);
+                let badge = if priority > thread_info.priority { theme.alert } else { theme.highlight };
+                draw_circle(bx, by, 7.0, badge);
+                let label = priority.to_string();
+                let width = text_width(&label, 14.0);
+                draw_label(&label, bx - width * 0.5, by + 4.5, 14.0, theme.background);
+            }
+
+            // Destaque da thread selecionada
+            if self.selected_thread == Some(i) {
+                draw_circle_lines(x_fio, mid_y, radius + 8.0, 2.0, theme.highlight);
+            }
+
+            // Segura o recurso em foco (ver `sync_sharing_highlight`)
+            if self.highlighted_threads.contains(&i) {
+                draw_circle_lines(x_fio, mid_y, radius + 12.0, 3.0, theme.highlight);
+            }
+
+            if self.compact {
+                continue;
+            }
 
-            let combined_text = format!("{} {}\n{}", thread_info.name, resource_str, state_text);
+            let combined_text = self.display_label(i);
 
-            let box_x = x_fio - (text_box_width * 0.5);
-            let box_y = y_bottom + 20.0;
+            let text_box = layout.text_box(i);
 
             draw_wrapped_text(
                 &combined_text,
-                box_x,
-                box_y,
-                text_box_width,
-                text_box_height,
+                text_box.x,
+                text_box.y,
+                text_box.w,
+                text_box.h,
                 18.0,
-                BLACK,
+                theme.text,
+            );
+        }
+    }
+
+    /// Quantas threads estão em cada estado, indexado por `ThreadState::index`.
+    pub fn count_by_state(&self) -> [usize; 5] {
+        let mut counts = [0; 5];
+        for thread in &self.threads {
+            counts[thread.state.index()] += 1;
+        }
+        counts
+    }
+
+    /// Cabeçalho com a pressão do sistema ("Waiting: 3 / 8").
+    /// Fica vermelho quando mais da metade das threads está esperando.
+    pub fn draw_contention_header(&self, resource_box: &ResourceBox, theme: &Theme) {
+        let counts = self.count_by_state();
+        let total = self.threads.len();
+        let waiting = counts[ThreadState::Waiting.index()];
+
+        let waiting_text = format!("Waiting: {} / {}", waiting, total);
+        let mut detail_lines = vec![
+            format!(
+                "Reading: {}  Writing: {}",
+                counts[ThreadState::Reading.index()],
+                counts[ThreadState::Writing.index()]
+            ),
+            format!(
+                "Busy resources: {} / {}",
+                resource_box.resources_in_use().count(),
+                resource_box.resources.len()
+            ),
+        ];
+        detail_lines.push(format!(
+            "Throughput: {:.1} ops/s",
+            self.throughput.rate(self.clock.now())
+        ));
+        detail_lines.push(match (self.latency.max(), self.latency.average()) {
+            (Some(max), Some(average)) => format!("Max wait: {:.1}s (avg {:.2}s)", max, average),
+            _ => "Max wait: --".to_string(),
+        });
+        if self.burst_mode {
+            detail_lines.push(format!("Burst mode: {} per tick", self.burst_size));
+        }
+        if self.demand_mode {
+            detail_lines.push("Demand mode".to_string());
+        }
+        if self.blocking_mode {
+            detail_lines.push("Blocking acquisition".to_string());
+        }
+        if self.priority_inheritance {
+            detail_lines.push("Priority inheritance".to_string());
+        }
+        if let Some(preset) = self.preset {
+            detail_lines.push(format!("Workload: {}", preset.name()));
+        }
+        let starving: Vec<&str> = self
+            .starving_threads(self.clock.now(), self.starvation_threshold)
+            .map(|i| self.threads[i].name.as_str())
+            .collect();
+        if !starving.is_empty() {
+            detail_lines.push(format!("Starving: {}", starving.join(", ")));
+        }
+        if self.scenario.is_some() {
+            for (waiter, holder, preemptor) in self.priority_inversions() {
+                detail_lines.push(format!(
+                    "Priority inversion: {} waits on {}, preempted by {}",
+                    self.threads[waiter].name, self.threads[holder].name, self.threads[preemptor].name
+                ));
+            }
+        }
+        if waiting > 0 {
+            let names: Vec<&str> = self
+                .threads_in_state(ThreadState::Waiting)
+                .map(|(_, thread)| thread.name.as_str())
+                .collect();
+            detail_lines.push(names.join(", "));
+        }
+        let waiting_color = if waiting * 2 > total { theme.alert } else { theme.text };
+
+        let font_size = 40.0;
+        let detail_font_size = 20.0;
+        let margin = 20.0;
+        let block_width = detail_lines
+            .iter()
+            .map(|line| text_width(line, detail_font_size))
+            .fold(text_width(&waiting_text, font_size), f32::max);
+        let x = screen_width() - block_width - margin;
+
+        draw_label(&waiting_text, x, margin + scaled(font_size), font_size, waiting_color);
+        for (i, line) in detail_lines.iter().enumerate() {
+            draw_label(
+                line,
+                x,
+                margin + scaled(font_size) + (scaled(detail_font_size) + 5.0) * (i as f32 + 1.0),
+                detail_font_size,
+                theme.text,
             );
         }
     }
 
+    /// Foto do estado atual em JSON, para ferramentas externas: todas as threads
+    /// (nome, estado, recurso) e todos os recursos (nome, leitores/escritores, posição).
+    /// Os contadores de cada recurso vêm de uma única leitura atômica, então leitores e
+    /// escritores são sempre do mesmo instante.
+    pub fn to_json(&self, resource_box: &ResourceBox) -> String {
+        let optional = |r: Option<usize>| r.map_or("null".to_string(), |r| r.to_string());
+        let threads: Vec<String> = self
+            .threads
+            .iter()
+            .map(|t| {
+                let resource = optional(t.resource_in_use);
+                format!(
+                    "    {{\"name\": {}, \"state\": \"{:?}\", \"resource\": {}, \"priority\": {}}}",
+                    json_string(&t.name),
+                    t.state,
+                    resource,
+                    t.priority
+                )
+            })
+            .collect();
+        let resources: Vec<String> = resource_box
+            .resources
+            .iter()
+            .map(|r| {
+                let counts = r.counts();
+                let inner = r.read_inner();
+                format!(
+                    "    {{\"name\": {}, \"readers\": {}, \"writers\": {}, \"x\": {}, \"y\": {}, \"width\": {}, \"height\": {}}}",
+                    json_string(&inner.name),
+                    counts.read_count,
+                    counts.write_count,
+                    inner.pos.x,
+                    inner.pos.y,
+                    inner.width,
+                    inner.height
+                )
+            })
+            .collect();
+        // O que o `log` ainda guarda (depende da capacidade e de `LogOverflow`)
+        let log: Vec<String> = self
+            .log
+            .entries()
+            .map(|t| {
+                let blockers: Vec<String> = t.blockers().iter().map(usize::to_string).collect();
+                format!(
+                    "    {{\"time\": {}, \"thread\": {}, \"from\": \"{:?}\", \"to\": \"{:?}\", \"released\": {}, \"requested\": {}, \"blocked_by\": [{}]}}",
+                    t.time,
+                    t.thread,
+                    t.from,
+                    t.to,
+                    optional(t.released),
+                    optional(t.requested.map(|(r, _)| r)),
+                    blockers.join(", ")
+                )
+            })
+            .collect();
+        format!(
+            "{{\n  \"time\": {},\n  \"threads\": [\n{}\n  ],\n  \"resources\": [\n{}\n  ],\n  \"log\": [\n{}\n  ],\n  \"log_dropped\": {}\n}}\n",
+            self.clock.now(),
+            threads.join(",\n"),
+            resources.join(",\n"),
+            log.join(",\n"),
+            self.log.dropped()
+        )
+    }
+
+    /// Se a thread `thread_idx` segura o recurso `resource_idx` (como alvo ou como
+    /// pré-requisito), em que modo (Reading/Writing).
+    pub fn holds(&self, thread_idx: usize, resource_idx: usize) -> Option<ThreadState> {
+        let thread = self.threads.get(thread_idx)?;
+        match thread.state {
+            ThreadState::Reading | ThreadState::Writing
+                if thread.resource_in_use == Some(resource_idx) =>
+            {
+                Some(thread.state)
+            }
+            _ => thread
+                .held_dependencies
+                .iter()
+                .find(|&&(r, _)| r == resource_idx)
+                .map(|&(_, mode)| mode),
+        }
+    }
+
+    /// Confere os contadores e a ordem de aquisição (`holders`) de cada recurso contra
+    /// as threads que dizem segurá-lo e, onde não batem, conserta. Devolve uma descrição de cada
+    /// desvio consertado (vazio = tudo certo). Não serve para o modo workers, em que
+    /// as threads são uma cópia atrasada do estado real.
+    pub fn reconcile(&self, resource_box: &ResourceBox) -> Vec<String> {
+        let mut expected = vec![LockCounts::default(); resource_box.resources.len()];
+        for thread in &self.threads {
+            let held = thread.resource_in_use.map(|r| (r, thread.state));
+            for (r, mode) in held.into_iter().chain(thread.held_dependencies.iter().copied()) {
+                let Some(counts) = expected.get_mut(r) else {
+                    continue;
+                };
+                match mode {
+                    ThreadState::Reading => counts.read_count += 1,
+                    ThreadState::Writing => counts.write_count += 1,
+                    _ => {}
+                }
+            }
+        }
+        let mut drifts = Vec::new();
+        logger::log(Level::Trace, || format!("reconcile: {} threads", self.threads.len()));
+        for (idx, (resource, expected)) in resource_box.resources.iter().zip(expected).enumerate() {
+            let actual = resource.counts();
+            if actual != expected {
+                drifts.push(format!(
+                    "R{}: counters say {} readers / {} writers, threads say {} / {} (repaired)",
+                    idx + 1,
+                    actual.read_count,
+                    actual.write_count,
+                    expected.read_count,
+                    expected.write_count
+                ));
+                logger::log(Level::Error, || format!("reconcile: {}", drifts[drifts.len() - 1]));
+                resource.set_counts(expected);
+            }
+            // A ordem de aquisição só vale se listar exatamente quem segura o recurso
+            let holding: Vec<usize> = (0..self.threads.len()).filter(|&i| self.holds(i, idx).is_some()).collect();
+            let listed = resource.holders();
+            let stale = listed.iter().any(|(t, _)| !holding.contains(t));
+            let missing: Vec<usize> = holding
+                .iter()
+                .copied()
+                .filter(|&i| listed.iter().all(|&(t, _)| t != i))
+                .collect();
+            if stale || !missing.is_empty() {
+                drifts.push(format!("R{}: holder list does not match the threads (repaired)", idx + 1));
+                logger::log(Level::Error, || format!("reconcile: {}", drifts[drifts.len() - 1]));
+                for &(t, _) in listed.iter().filter(|(t, _)| !holding.contains(t)) {
+                    resource.remove_holder(t);
+                }
+                for i in missing {
+                    resource.add_holder(i, self.threads[i].hold_started.unwrap_or(0.0));
+                }
+            }
+        }
+        drifts
+    }
+
+    /// Threads (com seus índices) que estão no estado `state`.
+    pub fn threads_in_state(
+        &self,
+        state: ThreadState,
+    ) -> impl Iterator<Item = (usize, &ThreadInfo)> {
+        self.threads
+            .iter()
+            .enumerate()
+            .filter(move |(_, thread)| thread.state == state)
+    }
+
     /// Se a thread estava lendo/escrevendo, removemos do recurso antigo.
     /// Tentamos setar o novo estado (Reading/Writing). Se falhar (retorno false), a thread fica WAITING.
+    /// Ao conseguir o recurso, sorteamos por quanto tempo (a partir de `now`) ela vai segurá-lo.
+    /// Se o recurso tem dependências, cada chamada pega só o próximo pré-requisito que
+    /// falta (a thread segue em Waiting, segurando os que já pegou) e o alvo vem por último.
     pub fn set_thread_resource_state(
         &mut self,
         resource_box: &ResourceBox,
         index: usize,
         new_state: ThreadState,
         new_resource: Option<usize>,
+        now: f64,
     ) {
+        let (read_hold_range, write_hold_range) = (self.read_hold_range, self.write_hold_range);
+        let mut hold_rng = self.decision_rng(index, DRAW_HOLD);
+        // Recurso que não existe (mais): a thread fica Idle em vez de esperar por ele para sempre
+        let (new_state, new_resource) = match new_resource {
+            Some(res_idx) if res_idx >= resource_box.resources.len() => (ThreadState::Idle, None),
+            _ => (new_state, new_resource),
+        };
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(err) = recorder.record(now, index, new_state, new_resource) {
+                logger::log(Level::Error, || format!("gravação interrompida: {}", err));
+                self.recorder = None;
+            }
+        }
+        let requested = match (new_state, new_resource) {
+            (ThreadState::Reading | ThreadState::Writing, Some(res_idx)) => Some((res_idx, new_state)),
+            _ => None,
+        };
+        // Recursos a pegar, na ordem: pré-requisitos e, por último, o pedido
+        let chain = requested.map_or_else(Vec::new, |(res_idx, _)| resource_box.acquisition_chain(res_idx));
+        // Quem segura cada recurso do caminho agora, para explicar uma eventual falha
+        let holders: Vec<(usize, usize, ThreadState)> = chain
+            .iter()
+            .flat_map(|&r| (0..self.threads.len()).filter(move |&j| j != index).map(move |j| (r, j)))
+            .filter_map(|(r, j)| self.holds(j, r).map(|mode| (r, j, mode)))
+            .collect();
         if let Some(thread) = self.threads.get_mut(index) {
-            if let Some(old_res) = thread.resource_in_use {
-                match thread.state {
-                    ThreadState::Reading => resource_box.remove_reading(old_res),
-                    ThreadState::Writing => resource_box.remove_writing(old_res),
-                    _ => {}
-                }
+            let from = thread.state;
+            let released = thread.resource_in_use;
+            let was_waiting = thread.state == ThreadState::Waiting;
+            if let (Some(res), Some(started)) = (thread.resource_in_use, thread.hold_started) {
+                resource_box.record_hold(res, now - started);
             }
-            thread.resource_in_use = None;
+            // Pré-requisitos que servem para o novo pedido continuam com a thread
+            let (kept, dropped): (Vec<_>, Vec<_>) = thread.held_dependencies.drain(..).partition(|&(r, mode)| {
+                Some(mode) == requested.map(|(_, m)| m) && chain.contains(&r) && chain.last() != Some(&r)
+            });
+            thread.held_dependencies = dropped;
+            release_held_resource(resource_box, index, thread);
+            thread.held_dependencies = kept;
             thread.state = new_state;
+            if matches!(new_state, ThreadState::Reading | ThreadState::Writing) && new_resource.is_some() {
+                thread.intended_resource = new_resource;
+                thread.intended_mode = Some(new_state);
+            } else {
+                thread.intended_resource = None;
+                thread.intended_mode = None;
+            }
 
-            if let Some(res_idx) = new_resource {
+            // Primeiro pré-requisito do caminho que a thread ainda não tem
+            let missing = chain[..chain.len().saturating_sub(1)]
+                .iter()
+                .copied()
+                .find(|r| thread.held_dependencies.iter().all(|(held, _)| held != r));
+            let mut failed_on = None;
+            if let (Some(dep), Some((_, mode))) = (missing, requested) {
+                let ok = match mode {
+                    ThreadState::Reading => resource_box.try_set_reading(dep, index),
+                    _ => resource_box.try_set_writing(dep),
+                };
+                if ok {
+                    thread.held_dependencies.push((dep, mode));
+                    resource_box.add_holder(dep, index, now);
+                } else {
+                    failed_on = Some(dep);
+                    self.failed_attempts += 1;
+                    resource_box.record_failure(dep, now);
+                }
+                thread.state = ThreadState::Waiting;
+                thread.last_attempt = Some((now, ok));
+            } else if let Some(res_idx) = new_resource {
                 match new_state {
                     ThreadState::Reading => {
-                        let ok = resource_box.try_set_reading(res_idx);
+                        let ok = resource_box.try_set_reading(res_idx, index);
                         if ok {
                             thread.resource_in_use = Some(res_idx);
                         } else {
@@ -154,111 +1120,1750 @@ impl ThreadsVisualizer {
                     }
                     _ => {}
                 }
+                if matches!(new_state, ThreadState::Reading | ThreadState::Writing) {
+                    let ok = thread.resource_in_use.is_some();
+                    thread.last_attempt = Some((now, ok));
+                    if ok {
+                        resource_box.add_holder(res_idx, index, now);
+                        self.throughput.record(now);
+                        let since = thread.waiting_since.filter(|_| was_waiting).unwrap_or(now);
+                        self.latency.record(index, now - since);
+                    } else {
+                        failed_on = Some(res_idx);
+                        self.failed_attempts += 1;
+                        resource_box.record_failure(res_idx, now);
+                    }
+                }
+            }
+
+            if thread.resource_in_use.is_some() {
+                let hold = if thread.state == ThreadState::Writing {
+                    sample_hold(write_hold_range, &mut hold_rng)
+                } else {
+                    sample_hold(read_hold_range, &mut hold_rng)
+                };
+                thread.hold_started = Some(now);
+                thread.hold_until = Some(now + hold);
+                if let (ThreadState::Writing, Some(res_idx)) = (thread.state, thread.resource_in_use) {
+                    resource_box.set_write_hold(res_idx, now, Some(now + hold));
+                }
+            }
+
+            // Recuo: quem insiste no alvo espera mais a cada falha; qualquer outro desfecho zera
+            let committed = self.blocking_mode || self.demand_mode;
+            if failed_on.is_some() && committed && self.retry_backoff > 0.0 {
+                thread.backoff = if thread.backoff > 0.0 {
+                    (thread.backoff * self.backoff_factor).min(MAX_BACKOFF)
+                } else {
+                    self.retry_backoff.min(MAX_BACKOFF)
+                };
+                thread.next_retry_time = Some(now + thread.backoff);
+            } else {
+                thread.backoff = 0.0;
+                thread.next_retry_time = None;
+            }
+
+            // Uma nova tentativa frustrada continua a mesma espera
+            thread.waiting_since = match (thread.state == ThreadState::Waiting, was_waiting) {
+                (true, true) => thread.waiting_since.or(Some(now)),
+                (true, false) => Some(now),
+                (false, _) => None,
+            };
+            if from != thread.state {
+                thread.last_change_time = Some(now);
+            }
+            thread.idle_since = match thread.state {
+                ThreadState::Idle if from == ThreadState::Idle => thread.idle_since.or(Some(now)),
+                ThreadState::Idle => Some(now),
+                _ => None,
+            };
+
+            if from != thread.state || requested.is_some() || released.is_some() {
+                let blocked_by: Vec<(usize, ThreadState)> = holders
+                    .into_iter()
+                    .filter(|&(r, _, _)| Some(r) == failed_on)
+                    .map(|(_, j, mode)| (j, mode))
+                    .collect();
+                if let Some(res_idx) = failed_on {
+                    logger::log(Level::Debug, || {
+                        format!("{:.3}: {} não conseguiu R{}, seguro por {:?}", now, thread.name, res_idx + 1, blocked_by)
+                    });
+                }
+                logger::log(Level::Info, || {
+                    format!(
+                        "{:.3}: {} {:?} -> {:?} (liberou {:?}, pediu {:?})",
+                        now, thread.name, from, thread.state, released, requested
+                    )
+                });
+                self.log.push(Transition {
+                    time: now,
+                    thread: index,
+                    from,
+                    to: thread.state,
+                    released,
+                    requested,
+                    blocked_by,
+                });
             }
         }
+        self.publish_waiting(resource_box);
     }
 
-    /// Sorteio aleatório do estado + recurso
-    pub fn update_threads_randomly(&mut self, resource_box: &ResourceBox) {
-       
-        let total = resource_box.resources.len();
-        if total == 0 {
-            return;
-        }
+    /// Threads esperando há pelo menos `threshold` segundos em `now` (inanição).
+    pub fn starving_threads(&self, now: f64, threshold: f64) -> impl Iterator<Item = usize> + '_ {
+        self.threads
+            .iter()
+            .enumerate()
+            .filter(move |(_, thread)| {
+                matches!(thread.waiting_since, Some(since) if now - since >= threshold)
+            })
+            .map(|(i, _)| i)
+    }
+
+    /// Atualização por frame: libera (volta para Idle) os recursos cujo tempo de posse expirou.
+    pub fn release_expired_holds(&mut self, resource_box: &ResourceBox, now: f64) {
         for i in 0..self.threads.len() {
-            let roll = random_range(0..4);
-            let new_state = match roll {
-                0 => ThreadState::Idle,
-                1 => ThreadState::Waiting,
-                2 => ThreadState::Reading,
-                3 => ThreadState::Writing,
-                _ => ThreadState::Idle,
-            };
-            let new_res = if new_state == ThreadState::Reading || new_state == ThreadState::Writing {
-                Some(random_range(0..total))
-            } else {
-                None
-            };
-            self.set_thread_resource_state(resource_box, i, new_state, new_res);
+            let expired = matches!(self.threads[i].hold_until, Some(until) if now >= until);
+            if expired {
+                self.set_thread_resource_state(resource_box, i, ThreadState::Idle, None, now);
+            }
         }
     }
-}
 
-// Mesma lógica de wrap
-fn draw_wrapped_text(
-    text: &str,
-    start_x: f32,
-    start_y: f32,
-    max_width: f32,
-    max_height: f32,
-    font_size: f32,
-    color: Color,
-) {
-    let forced_lines: Vec<&str> = text.split('\n').collect();
-    let line_spacing = font_size + 5.0;
-    let mut cursor_y = start_y;
-
-    for line in forced_lines {
-        let words: Vec<&str> = line.split_whitespace().collect();
-        let mut current_line = String::new();
-        let mut current_line_width = 0.0;
-
-        for word in words {
-            let measure = measure_text(word, None, font_size as u16, 1.0);
-            let word_width = measure.width;
-
-            let space_width = if current_line.is_empty() { 0.0 } else {
-                measure_text(" ", None, font_size as u16, 1.0).width
-            };
-            let next_width = current_line_width + space_width + word_width;
+    /// Volta a simulação ao estado inicial: libera todos os recursos segurados
+    /// (para os contadores voltarem a zero), deixa todas as threads em Idle e zera as
+    /// estatísticas das threads. As dos recursos ficam com `ResourceBox::reset_stats`.
+    pub fn reset(&mut self, resource_box: &ResourceBox) {
+        let now = self.clock.now();
+        for (i, thread) in self.threads.iter_mut().enumerate() {
+            if let (Some(recorder), false) = (&mut self.recorder, thread.state == ThreadState::Idle) {
+                let _ = recorder.record(now, i, ThreadState::Idle, None);
+            }
+            release_held_resource(resource_box, i, thread);
+            thread.state = ThreadState::Idle;
+            thread.last_attempt = None;
+            thread.waiting_since = None;
+            thread.idle_since = None;
+            thread.last_change_time = None;
+            thread.intended_resource = None;
+            thread.intended_mode = None;
+            thread.next_retry_time = None;
+            thread.backoff = 0.0;
+        }
+        self.scenario_cursor = 0;
+        self.scenario_deferred.clear();
+        self.tick = 0;
+        self.failed_attempts = 0;
+        self.throughput.clear();
+        self.latency.clear();
+        self.log.clear();
+        self.publish_waiting(resource_box);
+    }
 
-            if word_width > max_width {
-                if !current_line.is_empty() {
-                    draw_text_line(&current_line, start_x, cursor_y, font_size, color);
-                    cursor_y += line_spacing;
-                    if cursor_y > start_y + max_height { return; }
-                }
-                let mut truncated = String::new();
-                for ch in word.chars() {
-                    let test = truncated.clone() + &ch.to_string();
-                    let w = measure_text(&test, None, font_size as u16, 1.0).width;
-                    if w > max_width {
-                        break;
-                    }
-                    truncated.push(ch);
+    /// Fim da execução: devolve tudo o que as threads seguram, encerra a gravação e
+    /// confere que nenhum recurso ficou com leitor ou escritor. Retorna o que estiver
+    /// errado (vazio = tudo certo).
+    pub fn shutdown(&mut self, resource_box: &ResourceBox) -> Vec<String> {
+        // A gravação acaba antes: as liberações do encerramento não são decisões da execução
+        self.recorder = None;
+        self.reset(resource_box);
+        let mut problems = resource_box.validate().err().unwrap_or_default();
+        for (idx, resource) in resource_box.resources_in_use() {
+            let counts = resource.counts();
+            problems.push(format!(
+                "R{}: {} readers and {} writers left after shutdown",
+                idx + 1,
+                counts.read_count,
+                counts.write_count
+            ));
+        }
+        problems
+    }
+
+    /// "Admin derrubando o lock": manda para Idle, em `now`, todas as threads que seguram
+    /// o recurso `resource_idx` (como alvo ou como pré-requisito). Elas passam pela
+    /// transição normal, então soltam também o que mais seguravam e a mudança vai para
+    /// o log e a gravação. No fim o recurso é zerado, caso sobre alguma contagem.
+    pub fn force_release_resource(&mut self, resource_box: &ResourceBox, resource_idx: usize, now: f64) {
+        let holders: Vec<usize> = (0..self.threads.len())
+            .filter(|&i| self.holds(i, resource_idx).is_some())
+            .collect();
+        for i in holders {
+            self.set_thread_resource_state(resource_box, i, ThreadState::Idle, None, now);
+        }
+        resource_box.force_release(resource_idx);
+    }
+
+    /// Troca o estado das threads pelo publicado pelos workers,
+    /// contabilizando as aquisições que aconteceram desde o último frame.
+    pub fn sync_from_workers(&mut self, snapshot: Vec<ThreadInfo>) {
+        for (old, new) in self.threads.iter().zip(&snapshot) {
+            if let Some((t, true)) = new.last_attempt {
+                if old.last_attempt != new.last_attempt {
+                    self.throughput.record(t);
                 }
-                draw_text_line(&truncated, start_x, cursor_y, font_size, color);
-                cursor_y += line_spacing;
-                if cursor_y > start_y + max_height { return; }
-                current_line.clear();
-                current_line_width = 0.0;
-                continue;
             }
+        }
+        self.threads = snapshot;
+    }
+
+    /// Congela ou descongela o recurso `resource_idx` (ver `Resource::set_frozen`),
+    /// gravando a mudança se houver gravação.
+    pub fn set_resource_frozen(&mut self, resource_box: &ResourceBox, resource_idx: usize, frozen: bool, now: f64) {
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(err) = recorder.record_frozen(now, resource_idx, frozen) {
+                logger::log(Level::Error, || format!("gravação interrompida: {}", err));
+                self.recorder = None;
+            }
+        }
+        resource_box.set_frozen(resource_idx, frozen);
+    }
 
-            if next_width <= max_width {
-                if !current_line.is_empty() {
-                    current_line.push(' ');
+    /// Começa a gravar em `path` cada decisão passada a `set_thread_resource_state`
+    /// (sorteios, posses que expiram, controles manuais), como um roteiro. O estado
+    /// atual entra no começo do arquivo: primeiro quem segura recursos, depois quem
+    /// espera, para o replay partir do mesmo ponto. Liberações forçadas (tecla F)
+    /// entram como a volta a Idle de cada thread que segurava o recurso, e os
+    /// congelamentos (tecla K, caos) passam por `set_resource_frozen`.
+    pub fn record_to(&mut self, path: &str, resource_box: &ResourceBox, now: f64) -> Result<(), String> {
+        let mut recorder =
+            Recorder::create(path, now).map_err(|e| format!("não foi possível criar {}: {}", path, e))?;
+        for idx in (0..resource_box.resources.len()).filter(|&idx| resource_box.is_frozen(idx)) {
+            recorder
+                .record_frozen(now, idx, true)
+                .map_err(|e| format!("erro gravando {}: {}", path, e))?;
+        }
+        let holders = self.threads.iter().enumerate().filter(|(_, t)| t.resource_in_use.is_some());
+        let others = self.threads.iter().enumerate().filter(|(_, t)| t.resource_in_use.is_none());
+        for (i, thread) in holders.chain(others) {
+            let (state, resource) = match (thread.state, thread.resource_in_use, thread.intended_resource) {
+                (ThreadState::Idle, _, _) => continue,
+                (_, Some(res_idx), _) => (thread.state, Some(res_idx)),
+                (ThreadState::Waiting, None, Some(res_idx)) => {
+                    (thread.intended_mode.unwrap_or(ThreadState::Reading), Some(res_idx))
                 }
-                current_line.push_str(word);
-                current_line_width = next_width;
-            } else {
-                draw_text_line(&current_line, start_x, cursor_y, font_size, color);
-                cursor_y += line_spacing;
-                if cursor_y > start_y + max_height { return; }
-                current_line.clear();
-                current_line.push_str(word);
-                current_line_width = word_width;
+                (state, None, _) => (state, None),
+            };
+            recorder
+                .record(now, i, state, resource)
+                .map_err(|e| format!("erro gravando {}: {}", path, e))?;
+        }
+        self.recorder = Some(recorder);
+        Ok(())
+    }
+
+    /// Refaz uma execução gravada com `record_to`: zera a simulação e carrega o
+    /// arquivo como roteiro, que passa a dirigir as threads no lugar do sorteio.
+    pub fn replay_from(&mut self, path: &str, resource_box: &ResourceBox, now: f64) -> Result<(), String> {
+        let scenario = Scenario::load(path)?;
+        self.reset(resource_box);
+        self.load_scenario(scenario, now);
+        Ok(())
+    }
+
+    /// Carrega um roteiro que começa em `now`; a partir daí `apply_scenario_step` dirige as threads.
+    pub fn load_scenario(&mut self, scenario: Scenario, now: f64) {
+        self.scenario = Some(scenario);
+        self.scenario_cursor = 0;
+        self.scenario_start = now;
+        self.scenario_deferred.clear();
+    }
+
+    /// Aplica todos os passos do roteiro cujo tempo (relativo a `scenario_start`) já chegou.
+    /// As posses roteirizadas não expiram sozinhas: o roteiro decide quando liberar.
+    /// O passo de uma thread preemptada espera até ela poder rodar (ver `is_preempted`).
+    pub fn apply_scenario_step(&mut self, now: f64, resource_box: &ResourceBox) {
+        for step in std::mem::take(&mut self.scenario_deferred) {
+            self.apply_or_defer(step, resource_box, now);
+        }
+        let time = now - self.scenario_start;
+        loop {
+            let step = match &self.scenario {
+                Some(scenario) => match scenario.steps.get(self.scenario_cursor) {
+                    Some(step) if step.time <= time => step.clone(),
+                    _ => return,
+                },
+                None => return,
+            };
+            self.scenario_cursor += 1;
+            self.apply_or_defer(step, resource_box, now);
+        }
+    }
+
+    /// Aplica um passo do roteiro, ou o guarda em `scenario_deferred` se a thread está
+    /// preemptada ou já tem um passo adiado na frente (a ordem da thread se mantém).
+    /// Mudar a prioridade não precisa rodar, então nunca é adiado.
+    fn apply_or_defer(&mut self, step: ScenarioStep, resource_box: &ResourceBox, now: f64) {
+        if let (Some(frozen), Some(res_idx)) = (step.frozen, step.resource) {
+            self.set_resource_frozen(resource_box, res_idx, frozen, now);
+            return;
+        }
+        if let Some(priority) = step.priority {
+            if let Some(thread) = self.threads.get_mut(step.thread) {
+                thread.priority = priority;
             }
+            return;
+        }
+        let queued = self.scenario_deferred.iter().any(|s| s.thread == step.thread);
+        if queued || self.is_preempted(step.thread) {
+            self.scenario_deferred.push(step);
+            return;
         }
 
-        if !current_line.is_empty() {
-            draw_text_line(&current_line, start_x, cursor_y, font_size, color);
-            cursor_y += line_spacing;
-            if cursor_y > start_y + max_height { return; }
+        self.set_thread_resource_state(resource_box, step.thread, step.state, step.resource, now);
+        if let Some(thread) = self.threads.get_mut(step.thread) {
+            thread.hold_started = None;
+            thread.hold_until = None;
+            if let (ThreadState::Writing, Some(res_idx)) = (thread.state, thread.resource_in_use) {
+                resource_box.set_write_hold(res_idx, now, None);
+            }
         }
     }
-}
 
-fn draw_text_line(line: &str, x: f32, y: f32, font_size: f32, color: Color) {
-    draw_text(line, x, y + font_size, font_size, color);
+    /// Legenda do roteiro que vale agora (a última cujo instante já passou).
+    pub fn scenario_caption(&self, now: f64) -> Option<&str> {
+        let time = now - self.scenario_start;
+        self.scenario
+            .as_ref()?
+            .captions
+            .iter()
+            .rev()
+            .find(|(at, _)| *at <= time)
+            .map(|(_, text)| text.as_str())
+    }
+
+    /// Threads que seguram agora o recurso `resource_idx` (lendo, escrevendo ou
+    /// como pré-requisito, ver `holds`).
+    pub fn threads_using(&self, resource_idx: usize) -> Vec<usize> {
+        (0..self.threads.len())
+            .filter(|&i| self.holds(i, resource_idx).is_some())
+            .collect()
+    }
+
+    /// Recursos (dos `resources_len` da caixa) que a thread `thread_idx` segura agora.
+    pub fn resources_held_by(&self, thread_idx: usize, resources_len: usize) -> Vec<usize> {
+        (0..resources_len)
+            .filter(|&r| self.holds(thread_idx, r).is_some())
+            .collect()
+    }
+
+    /// Destaque de compartilhamento: o recurso em foco (sob o mouse ou selecionado)
+    /// acende quem o segura, e a thread em foco acende os recursos que ela segura.
+    pub fn sync_sharing_highlight(&mut self, resource_box: &mut ResourceBox) {
+        let resource = resource_box.hovered_resource.or(resource_box.selected_resource);
+        self.highlighted_threads = resource.map_or_else(Vec::new, |r| self.threads_using(r));
+        let thread = self.hovered_thread.or(self.selected_thread);
+        resource_box.highlighted_resources = thread
+            .map_or_else(Vec::new, |i| self.resources_held_by(i, resource_box.resources.len()));
+    }
+
+    /// Linhas do destaque de compartilhamento: de cada thread acesa até o recurso em
+    /// foco e da thread em foco até cada recurso que ela segura.
+    pub fn draw_sharing_lines(&self, resource_box: &ResourceBox, theme: &Theme) {
+        let layout = self.layout();
+        let mut pairs: Vec<(usize, usize)> = Vec::new();
+        if let Some(r) = resource_box.hovered_resource.or(resource_box.selected_resource) {
+            pairs.extend(self.highlighted_threads.iter().map(|&i| (i, r)));
+        }
+        if let Some(i) = self.hovered_thread.or(self.selected_thread) {
+            pairs.extend(resource_box.highlighted_resources.iter().map(|&r| (i, r)));
+        }
+        for (i, r) in pairs {
+            let Some(rect) = resource_box.resource_rect(r).filter(|_| layout.is_visible(i)) else {
+                continue;
+            };
+            let from = layout.circle_center(i);
+            let to = vec2(rect.center().x, rect.bottom());
+            draw_line(from.x, from.y, to.x, to.y, 2.0, theme.highlight);
+        }
+    }
+
+    /// Quantas threads estão esperando por cada um dos `resources_len` recursos.
+    pub fn waiting_per_resource(&self, resources_len: usize) -> Vec<usize> {
+        let mut waiting = vec![0; resources_len];
+        for thread in self.threads.iter().filter(|t| t.state == ThreadState::Waiting) {
+            if let Some(count) = thread.intended_resource.and_then(|r| waiting.get_mut(r)) {
+                *count += 1;
+            }
+        }
+        waiting
+    }
+
+    /// Passa para cada recurso quantas threads esperam para ler e para escrever nele,
+    /// para a política poder levar a fila em conta (ver `LockSnapshot`). Chamado a cada
+    /// mudança de estado, então a fila que a política vê nunca fica para trás.
+    pub fn publish_waiting(&self, resource_box: &ResourceBox) {
+        let mut queues = vec![(0, 0); resource_box.resources.len()];
+        for thread in self.threads.iter().filter(|t| t.state == ThreadState::Waiting) {
+            let Some(queue) = thread.intended_resource.and_then(|r| queues.get_mut(r)) else {
+                continue;
+            };
+            match thread.intended_mode {
+                Some(ThreadState::Writing) => queue.1 += 1,
+                Some(_) => queue.0 += 1,
+                None => {}
+            }
+        }
+        for (resource, (readers, writers)) in resource_box.resources.iter().zip(queues) {
+            resource.set_waiting(readers, writers);
+        }
+    }
+
+    /// Quem segura agora o recurso que a thread `thread_idx` está esperando
+    /// (vazio se ela não está esperando ou se o recurso está livre).
+    pub fn who_blocks(&self, thread_idx: usize) -> Vec<usize> {
+        let Some(thread) = self.threads.get(thread_idx) else {
+            return Vec::new();
+        };
+        let Some(res_idx) = thread.intended_resource.filter(|_| thread.state == ThreadState::Waiting) else {
+            return Vec::new();
+        };
+        (0..self.threads.len())
+            .filter(|&j| j != thread_idx && self.holds(j, res_idx).is_some())
+            .collect()
+    }
+
+    /// Threads presas num ciclo de espera (cada uma espera um recurso que outra do
+    /// ciclo segura, ver `who_blocks`): nenhuma delas vai sair sozinha.
+    pub fn deadlocked_threads(&self) -> Vec<usize> {
+        let blocks: Vec<Vec<usize>> = (0..self.threads.len()).map(|i| self.who_blocks(i)).collect();
+        (0..self.threads.len())
+            .filter(|&start| {
+                // A partir de quem bloqueia `start`, dá para voltar a `start`?
+                let mut seen = vec![false; blocks.len()];
+                let mut stack = blocks[start].clone();
+                while let Some(j) = stack.pop() {
+                    if j == start {
+                        return true;
+                    }
+                    if !std::mem::replace(&mut seen[j], true) {
+                        stack.extend(&blocks[j]);
+                    }
+                }
+                false
+            })
+            .collect()
+    }
+
+    /// Threads paradas em Idle há `idle_timeout` segundos tentam pegar um recurso
+    /// (modo e recurso sorteados), para a cena não ficar toda quieta entre os ticks.
+    pub fn nudge_idle_threads(&mut self, resource_box: &ResourceBox, now: f64) {
+        let Some(timeout) = self.idle_timeout else {
+            return;
+        };
+        for i in 0..self.threads.len() {
+            let thread = &mut self.threads[i];
+            if thread.state != ThreadState::Idle {
+                continue;
+            }
+            // Idle vindo de fora de `set_thread_resource_state` (reset, liberação forçada)
+            let since = *thread.idle_since.get_or_insert(now);
+            if now - since < timeout || self.in_cooldown(i, now) {
+                continue;
+            }
+            let mode = self.roll_mode(i);
+            let resource = self.pick_resource(resource_box, i);
+            self.set_thread_resource_state(resource_box, i, mode, resource, now);
+        }
+    }
+
+    /// Tira das threads os recursos (em uso ou como alvo) que não existem mais na caixa,
+    /// por exemplo depois de ela encolher. Quem segurava ou esperava por eles volta a Idle.
+    /// Os contadores não são mexidos: o recurso já foi embora.
+    pub fn drop_invalid_resources(&mut self, resource_box: &ResourceBox) {
+        let total = resource_box.resources.len();
+        for thread in &mut self.threads {
+            thread.held_dependencies.retain(|&(r, _)| r < total);
+            let held_invalid = matches!(thread.resource_in_use, Some(r) if r >= total);
+            let intent_invalid = matches!(thread.intended_resource, Some(r) if r >= total);
+            if intent_invalid {
+                thread.intended_resource = None;
+                thread.intended_mode = None;
+            }
+            if held_invalid || (intent_invalid && thread.state == ThreadState::Waiting) {
+                thread.resource_in_use = None;
+                thread.waiting_since = None;
+                thread.hold_started = None;
+                thread.hold_until = None;
+                thread.state = ThreadState::Idle;
+            }
+        }
+        self.publish_waiting(resource_box);
+    }
+
+    /// Sorteio aleatório do estado + recurso
+    pub fn update_threads_randomly(&mut self, resource_box: &ResourceBox, now: f64) {
+        self.tick += 1;
+        self.drop_invalid_resources(resource_box);
+        let total = resource_box.resources.len();
+        if total == 0 {
+            return;
+        }
+        // Threads mortas ficam fora do sorteio até serem revividas
+        let candidates: Vec<usize> = (0..self.threads.len())
+            .filter(|&i| self.threads[i].state != ThreadState::Crashed && !self.in_cooldown(i, now))
+            .collect();
+        for i in self.service_order(candidates) {
+            self.reroll_thread(resource_box, i, now);
+        }
+    }
+
+    /// Como `update_threads_randomly`, mas sorteia de novo só `n` threads distintas
+    /// escolhidas ao acaso; as outras continuam como estão.
+    pub fn update_threads_burst(&mut self, resource_box: &ResourceBox, n: usize, now: f64) {
+        self.tick += 1;
+        self.drop_invalid_resources(resource_box);
+        if resource_box.resources.is_empty() {
+            return;
+        }
+        let candidates: Vec<usize> = (0..self.threads.len())
+            .filter(|&i| self.threads[i].state != ThreadState::Crashed && !self.in_cooldown(i, now))
+            .collect();
+        let chosen: Vec<usize> = candidates
+            .choose_multiple(&mut self.decision_rng(usize::MAX, DRAW_BURST), n)
+            .copied()
+            .collect();
+        for i in self.service_order(chosen) {
+            self.reroll_thread(resource_box, i, now);
+        }
+    }
+
+    /// A thread `i` mudou de estado há menos de `cooldown` segundos em `now`?
+    pub fn in_cooldown(&self, i: usize, now: f64) -> bool {
+        let last_change = self.threads.get(i).and_then(|t| t.last_change_time);
+        matches!(last_change, Some(at) if now - at < self.cooldown)
+    }
+
+    /// Ordem de atendimento num tick: primeiro a fila de espera (maior `priority` antes,
+    /// empate por quem espera há mais tempo), depois as demais threads. Assim, quando um
+    /// recurso vaga, o primeiro da fila tenta antes de qualquer recém-chegado.
+    pub fn service_order(&self, mut indices: Vec<usize>) -> Vec<usize> {
+        indices.sort_by(|&a, &b| {
+            let (ta, tb) = (&self.threads[a], &self.threads[b]);
+            let (wa, wb) = (ta.waiting_since.is_some(), tb.waiting_since.is_some());
+            wb.cmp(&wa)
+                .then(tb.priority.cmp(&ta.priority))
+                .then(
+                    ta.waiting_since
+                        .unwrap_or(0.0)
+                        .total_cmp(&tb.waiting_since.unwrap_or(0.0)),
+                )
+                .then(a.cmp(&b))
+        });
+        indices
+    }
+
+    /// Até `count` threads Idle (na ordem) pedem escrita em `resource_idx` ao mesmo
+    /// tempo: uma consegue e o resto fica esperando. Retorna quantas pediram.
+    pub fn inject_write_storm(&mut self, resource_box: &ResourceBox, resource_idx: usize, count: usize, now: f64) -> usize {
+        if resource_idx >= resource_box.resources.len() {
+            return 0;
+        }
+        let idle: Vec<usize> = self
+            .threads_in_state(ThreadState::Idle)
+            .map(|(i, _)| i)
+            .take(count)
+            .collect();
+        for &i in &idle {
+            self.set_thread_resource_state(resource_box, i, ThreadState::Writing, Some(resource_idx), now);
+        }
+        idle.len()
+    }
+
+    /// Muda a prioridade da thread `index` em `delta`, sem sair de 0..=9.
+    pub fn adjust_priority(&mut self, index: usize, delta: i8) {
+        if let Some(thread) = self.threads.get_mut(index) {
+            thread.priority = thread.priority.saturating_add_signed(delta).min(MAX_PRIORITY);
+        }
+    }
+
+    /// Prioridade com que a thread `i` roda: a dela ou, com `priority_inheritance`, a
+    /// maior entre ela e as threads esperando pelo recurso que ela segura.
+    pub fn effective_priority(&self, i: usize) -> u8 {
+        let thread = &self.threads[i];
+        let held = thread
+            .resource_in_use
+            .filter(|_| self.priority_inheritance && is_holding(thread.state));
+        let Some(res_idx) = held else {
+            return thread.priority;
+        };
+        self.threads
+            .iter()
+            .filter(|t| t.state == ThreadState::Waiting && t.intended_resource == Some(res_idx))
+            .map(|t| t.priority)
+            .fold(thread.priority, u8::max)
+    }
+
+    /// Thread `i` segura um recurso, mas outra de prioridade (efetiva) maior está rodando
+    /// em outro recurso e toma a vez dela. Só o roteiro respeita isso: os passos de uma
+    /// thread preemptada esperam (ver `apply_scenario_step`).
+    pub fn is_preempted(&self, i: usize) -> bool {
+        let Some(thread) = self.threads.get(i).filter(|t| is_holding(t.state)) else {
+            return false;
+        };
+        let own = self.effective_priority(i);
+        self.threads.iter().enumerate().any(|(j, other)| {
+            j != i
+                && is_holding(other.state)
+                && other.resource_in_use != thread.resource_in_use
+                && self.effective_priority(j) > own
+        })
+    }
+
+    /// Inversões de prioridade em andamento, como (quem espera, quem segura, quem
+    /// preempta): uma thread espera por um recurso cujo dono está preemptado por outra
+    /// de prioridade menor que a de quem espera.
+    pub fn priority_inversions(&self) -> Vec<(usize, usize, usize)> {
+        let mut inversions = Vec::new();
+        for (waiter, w) in self.threads.iter().enumerate() {
+            let Some(res_idx) = w.intended_resource.filter(|_| w.state == ThreadState::Waiting) else {
+                continue;
+            };
+            for (holder, h) in self.threads.iter().enumerate() {
+                if !is_holding(h.state) || h.resource_in_use != Some(res_idx) || h.priority >= w.priority {
+                    continue;
+                }
+                let holder_priority = self.effective_priority(holder);
+                let preemptor = (0..self.threads.len()).find(|&j| {
+                    let other = &self.threads[j];
+                    j != holder
+                        && is_holding(other.state)
+                        && other.resource_in_use != Some(res_idx)
+                        && (holder_priority + 1..w.priority).contains(&self.effective_priority(j))
+                });
+                if let Some(preemptor) = preemptor {
+                    inversions.push((waiter, holder, preemptor));
+                }
+            }
+        }
+        inversions
+    }
+
+    /// Rótulo da thread `i` como `draw` desenha: o de `ThreadInfo::display_label`,
+    /// com "Blocked" no lugar de "Waiting" quando ela está presa a um alvo
+    /// ("Backing off" se ainda está no recuo, ver `is_backing_off`).
+    pub fn display_label(&self, i: usize) -> String {
+        let thread = &self.threads[i];
+        if self.is_backing_off(i, self.clock.now()) {
+            thread.label_with_state("Backing off")
+        } else if self.is_blocked_waiter(i) {
+            thread.label_with_state("Blocked")
+        } else {
+            thread.display_label()
+        }
+    }
+
+    /// Thread `i` esperando presa a um alvo, que vai insistir nele no próximo tick
+    /// (modo bloqueante ou modo demanda), e não só uma tentativa que falhou.
+    pub fn is_blocked_waiter(&self, i: usize) -> bool {
+        self.threads.get(i).is_some_and(|t| {
+            (self.blocking_mode || self.demand_mode)
+                && t.state == ThreadState::Waiting
+                && t.intended_resource.is_some()
+                && t.intended_mode.is_some()
+        })
+    }
+
+    /// Thread `i` presa a um alvo e ainda no recuo em `now`: não tenta de novo até
+    /// `next_retry_time` (ver `retry_backoff`).
+    pub fn is_backing_off(&self, i: usize, now: f64) -> bool {
+        self.is_blocked_waiter(i) && matches!(self.threads[i].next_retry_time, Some(at) if now < at)
+    }
+
+    /// Sorteia um novo estado (e recurso, se for ler/escrever) para a thread `i`.
+    /// No modo demanda quem ficou esperando tenta de novo o mesmo alvo; no modo
+    /// bloqueante também, mas o resto das threads sorteia como sempre.
+    fn reroll_thread(&mut self, resource_box: &ResourceBox, i: usize, now: f64) {
+        if self.is_backing_off(i, now) {
+            return;
+        }
+        if self.demand_mode {
+            self.demand_step(resource_box, i, now);
+            return;
+        }
+        if self.is_blocked_waiter(i) {
+            let thread = &self.threads[i];
+            if let (Some(mode), Some(res_idx)) = (thread.intended_mode, thread.intended_resource) {
+                self.set_thread_resource_state(resource_box, i, mode, Some(res_idx), now);
+                return;
+            }
+        }
+        let new_state = self.roll_state(&mut self.decision_rng(i, DRAW_STATE));
+        let new_res = if new_state == ThreadState::Reading || new_state == ThreadState::Writing {
+            self.pick_resource(resource_box, i)
+        } else {
+            None
+        };
+        self.set_thread_resource_state(resource_box, i, new_state, new_res, now);
+    }
+
+    /// Um tick do modo demanda para a thread `i`: quem está segurando continua até a
+    /// posse expirar, quem espera insiste no alvo e só quem está Idle escolhe um novo.
+    fn demand_step(&mut self, resource_box: &ResourceBox, i: usize, now: f64) {
+        let thread = &self.threads[i];
+        let (state, resource) = match (thread.state, thread.intended_mode, thread.intended_resource) {
+            (ThreadState::Reading | ThreadState::Writing, _, _) => return,
+            (ThreadState::Waiting, Some(mode), Some(res_idx)) => (mode, Some(res_idx)),
+            _ => (
+                self.roll_mode(i),
+                self.pick_resource(resource_box, i),
+            ),
+        };
+        self.set_thread_resource_state(resource_box, i, state, resource, now);
+    }
+
+    /// Recurso sorteado para a thread `i`: o preferido (`affinity`) com chance
+    /// `affinity_strength`, senão um qualquer pelos pesos da caixa.
+    pub fn pick_resource(&self, resource_box: &ResourceBox, i: usize) -> Option<usize> {
+        let mut rng = self.decision_rng(i, DRAW_RESOURCE);
+        let affinity = self.threads.get(i).and_then(|t| t.affinity);
+        match affinity.filter(|&r| r < resource_box.resources.len()) {
+            Some(r) if rng.random_bool(self.affinity_strength.clamp(0.0, 1.0)) => Some(r),
+            _ => resource_box.pick_weighted(&mut rng),
+        }
+    }
+
+    /// Uma linha fraca de cada thread visível até o seu recurso preferido.
+    pub fn draw_affinity_lines(&self, resource_box: &ResourceBox, theme: &Theme) {
+        let layout = self.layout();
+        let color = Color::new(theme.border.r, theme.border.g, theme.border.b, 0.25);
+        for (i, thread) in self.threads.iter().enumerate().filter(|&(i, _)| layout.is_visible(i)) {
+            let Some(rect) = thread.affinity.and_then(|r| resource_box.resource_rect(r)) else {
+                continue;
+            };
+            let from = layout.circle_center(i);
+            draw_line(from.x, from.y, rect.center().x, rect.bottom(), 1.0, color);
+        }
+    }
+
+    /// Ordem de aquisição dos recursos compartilhados (com dois ou mais donos): uma
+    /// linha fraca de cada dono até o recurso, com um distintivo numerado (1 = quem
+    /// pegou primeiro) perto do recurso (ver `Resource::holders`).
+    pub fn draw_acquisition_order(&self, resource_box: &ResourceBox, theme: &Theme) {
+        let layout = self.layout();
+        let color = Color::new(theme.border.r, theme.border.g, theme.border.b, 0.4);
+        for r in 0..resource_box.resources.len() {
+            let holders = resource_box.holders(r);
+            let Some(rect) = resource_box.resource_rect(r).filter(|_| holders.len() > 1) else {
+                continue;
+            };
+            let to = vec2(rect.center().x, rect.bottom());
+            for (order, &(i, _)) in holders.iter().enumerate().filter(|&(_, &(i, _))| layout.is_visible(i)) {
+                let from = layout.circle_center(i);
+                draw_line(from.x, from.y, to.x, to.y, 1.0, color);
+                let badge = to + (from - to).normalize_or_zero() * 28.0;
+                draw_circle(badge.x, badge.y, 8.0, theme.highlight);
+                let label = (order + 1).to_string();
+                let width = text_width(&label, 14.0);
+                draw_label(&label, badge.x - width * 0.5, badge.y + 4.5, 14.0, theme.background);
+            }
+        }
+    }
+
+    /// Leitura ou escrita, meio a meio, para a thread `i`.
+    fn roll_mode(&self, i: usize) -> ThreadState {
+        if self.decision_rng(i, DRAW_MODE).random_bool(0.5) {
+            ThreadState::Writing
+        } else {
+            ThreadState::Reading
+        }
+    }
+}
+
+/// Confere um intervalo (min, max) de posse em segundos.
+pub fn validate_hold_range(min: f64, max: f64) -> Result<(), String> {
+    if !(min >= 0.0 && max >= 0.0) {
+        return Err(format!("tempo de posse inválido: {}..{}", min, max));
+    }
+    if min > max {
+        return Err(format!("tempo de posse com mínimo maior que o máximo: {}..{}", min, max));
+    }
+    Ok(())
+}
+
+/// Confere os pesos do sorteio de estado.
+pub fn validate_state_weights(weights: &[f32; 4]) -> Result<(), String> {
+    if weights.iter().any(|&w| w < 0.0 || !w.is_finite()) {
+        return Err(format!("pesos de estado inválidos: {:?}", weights));
+    }
+    if weights.iter().all(|&w| w == 0.0) {
+        return Err("os pesos de estado não podem ser todos zero".to_string());
+    }
+    Ok(())
+}
+
+/// Sorteia um tempo de posse dentro de `(min, max)`.
+pub fn sample_hold((min, max): (f64, f64), rng: &mut impl Rng) -> f64 {
+    if max > min {
+        rng.random_range(min..=max)
+    } else {
+        min
+    }
+}
+
+/// Lendo ou escrevendo (segurando um recurso)
+fn is_holding(state: ThreadState) -> bool {
+    matches!(state, ThreadState::Reading | ThreadState::Writing)
+}
+
+/// Se a thread `index` estava lendo/escrevendo, devolve o recurso ao `ResourceBox`
+/// (junto com os pré-requisitos que ela segurava) e limpa o recurso em uso e o tempo de posse.
+fn release_held_resource(resource_box: &ResourceBox, index: usize, thread: &mut ThreadInfo) {
+    let held = thread.resource_in_use.map(|r| (r, thread.state));
+    for (res, mode) in held.into_iter().chain(thread.held_dependencies.drain(..)) {
+        match mode {
+            ThreadState::Reading => resource_box.remove_reading(res, index),
+            ThreadState::Writing => resource_box.remove_writing(res),
+            _ => {}
+        }
+        resource_box.remove_holder(res, index);
+    }
+    thread.resource_in_use = None;
+    thread.hold_started = None;
+    thread.hold_until = None;
+}
+
+/// Embaralhador de 64 bits (SplitMix64), para semente e tick virarem geradores independentes.
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Texto como string JSON, com aspas e escapes.
+fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chaos::{Chaos, ChaosChange};
+    use crate::clock::MockClock;
+    use crate::policy::ReaderPreferring;
+
+    fn setup(threads: usize, resources: u32) -> (ThreadsVisualizer, ResourceBox) {
+        let resource_box = ResourceBox::new(Vec2::ZERO, resources, || Box::new(ReaderPreferring));
+        let mut vis = ThreadsVisualizer::new(threads);
+        vis.clock = Arc::new(MockClock::new(0.0));
+        (vis, resource_box)
+    }
+
+    /// Os contadores batem com as threads (ver `reconcile`)
+    fn assert_consistent(vis: &ThreadsVisualizer, rb: &ResourceBox) {
+        assert_eq!(vis.reconcile(rb), Vec::<String>::new());
+    }
+
+    #[test]
+    fn reader_blocks_writer() {
+        let (mut vis, rb) = setup(2, 1);
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Reading, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Writing, Some(0), 0.0);
+
+        assert_eq!(vis.threads[0].state, ThreadState::Reading);
+        assert_eq!(vis.threads[1].state, ThreadState::Waiting);
+        assert_eq!((rb.readers(0), rb.writers(0)), (1, 0));
+        assert_consistent(&vis, &rb);
+    }
+
+    #[test]
+    fn writer_blocks_readers_and_writers() {
+        let (mut vis, rb) = setup(3, 1);
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Writing, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Reading, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 2, ThreadState::Writing, Some(0), 0.0);
+
+        assert_eq!(vis.threads[0].state, ThreadState::Writing);
+        assert_eq!(vis.threads[1].state, ThreadState::Waiting);
+        assert_eq!(vis.threads[2].state, ThreadState::Waiting);
+        assert_eq!((rb.readers(0), rb.writers(0)), (0, 1));
+        assert_consistent(&vis, &rb);
+    }
+
+    #[test]
+    fn max_writers_admits_that_many_writers_and_no_readers() {
+        let (mut vis, rb) = setup(4, 1);
+        rb.set_max_writers(0, 2);
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Writing, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Writing, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 2, ThreadState::Reading, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 3, ThreadState::Writing, Some(0), 0.0);
+
+        assert_eq!(vis.threads[0].state, ThreadState::Writing);
+        assert_eq!(vis.threads[1].state, ThreadState::Writing);
+        assert_eq!(vis.threads[2].state, ThreadState::Waiting);
+        assert_eq!(vis.threads[3].state, ThreadState::Waiting);
+        assert_eq!((rb.readers(0), rb.writers(0)), (0, 2));
+        assert!(rb.validate().is_ok());
+        assert_consistent(&vis, &rb);
+    }
+
+    #[test]
+    fn releasing_last_reader_allows_writer() {
+        let (mut vis, rb) = setup(3, 1);
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Reading, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Reading, Some(0), 0.0);
+
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Idle, None, 1.0);
+        vis.set_thread_resource_state(&rb, 2, ThreadState::Writing, Some(0), 1.0);
+        assert_eq!(vis.threads[2].state, ThreadState::Waiting);
+
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Idle, None, 2.0);
+        vis.set_thread_resource_state(&rb, 2, ThreadState::Writing, Some(0), 2.0);
+        assert_eq!(vis.threads[2].state, ThreadState::Writing);
+        assert_eq!((rb.readers(0), rb.writers(0)), (0, 1));
+        assert_consistent(&vis, &rb);
+    }
+
+    #[test]
+    fn switching_resource_releases_the_old_one_first() {
+        let (mut vis, rb) = setup(1, 2);
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Writing, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Reading, Some(1), 1.0);
+
+        assert_eq!(vis.threads[0].resource_in_use, Some(1));
+        assert_eq!((rb.readers(0), rb.writers(0)), (0, 0));
+        assert_eq!((rb.readers(1), rb.writers(1)), (1, 0));
+
+        // Pedir de novo o que já segura não conta o mesmo leitor duas vezes
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Reading, Some(1), 2.0);
+        assert_eq!(rb.readers(1), 1);
+        assert_consistent(&vis, &rb);
+    }
+
+    #[test]
+    fn shutdown_releases_everything() {
+        let (mut vis, rb) = setup(3, 2);
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Reading, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Reading, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 2, ThreadState::Writing, Some(1), 0.0);
+
+        assert!(vis.shutdown(&rb).is_empty());
+        assert_eq!(rb.resources_in_use().count(), 0);
+        assert!(vis.threads.iter().all(|t| t.state == ThreadState::Idle));
+    }
+
+    #[test]
+    fn failed_acquisition_waits_without_leaking_counts() {
+        let (mut vis, rb) = setup(2, 1);
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Writing, Some(0), 0.0);
+        for t in 1..5 {
+            vis.set_thread_resource_state(&rb, 1, ThreadState::Reading, Some(0), t as f64);
+        }
+
+        let waiter = &vis.threads[1];
+        assert_eq!(waiter.state, ThreadState::Waiting);
+        assert_eq!(waiter.resource_in_use, None);
+        assert_eq!(waiter.intended_resource, Some(0));
+        assert_eq!(waiter.waiting_since, Some(1.0));
+        assert_eq!((rb.readers(0), rb.writers(0)), (0, 1));
+        assert!(rb.validate().is_ok());
+
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Idle, None, 5.0);
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Idle, None, 5.0);
+        assert_eq!((rb.readers(0), rb.writers(0)), (0, 0));
+        assert_consistent(&vis, &rb);
+    }
+
+    #[test]
+    fn reconcile_repairs_drifted_counters() {
+        let (mut vis, rb) = setup(2, 2);
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Reading, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Writing, Some(1), 0.0);
+        // Um caminho que mexeu no contador sem mexer na thread
+        rb.remove_reading(0, 0);
+        rb.remove_writing(1);
+
+        assert_eq!(vis.reconcile(&rb).len(), 2);
+        assert_eq!((rb.readers(0), rb.writers(0)), (1, 0));
+        assert_eq!((rb.readers(1), rb.writers(1)), (0, 1));
+        assert_consistent(&vis, &rb);
+    }
+
+    #[test]
+    fn frozen_resource_rejects_until_unfrozen() {
+        let (mut vis, rb) = setup(2, 1);
+        vis.blocking_mode = true;
+        rb.set_frozen(0, true);
+        assert!(!rb.try_set_reading(0, 0));
+        assert!(!rb.try_set_writing(0));
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Writing, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Reading, Some(0), 0.0);
+        assert_eq!(vis.threads[0].state, ThreadState::Waiting);
+        assert_eq!(vis.threads[1].state, ThreadState::Waiting);
+        assert_eq!((rb.readers(0), rb.writers(0)), (0, 0));
+
+        // Descongelado, a fila volta a andar na próxima passada
+        rb.set_frozen(0, false);
+        vis.update_threads_randomly(&rb, 1.0);
+        assert_eq!(vis.threads[0].state, ThreadState::Writing);
+        assert_eq!(vis.threads[1].state, ThreadState::Waiting);
+        assert_consistent(&vis, &rb);
+    }
+
+    #[test]
+    fn write_storm_admits_one_writer() {
+        let (mut vis, rb) = setup(6, 2);
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Reading, Some(1), 0.0);
+        assert_eq!(vis.inject_write_storm(&rb, 0, 4, 0.0), 4);
+
+        let writers: Vec<usize> = vis.threads_in_state(ThreadState::Writing).map(|(i, _)| i).collect();
+        let waiting: Vec<usize> = vis.threads_in_state(ThreadState::Waiting).map(|(i, _)| i).collect();
+        assert_eq!(writers, vec![1]);
+        assert_eq!(waiting, vec![2, 3, 4]);
+        assert_eq!(vis.threads[0].state, ThreadState::Reading);
+        assert_eq!(rb.writers(0), 1);
+        assert_consistent(&vis, &rb);
+    }
+
+    #[test]
+    fn reentrant_read_does_not_block_or_double_count() {
+        let (_, rb) = setup(0, 1);
+        let resource = &rb.resources[0];
+        resource.set_reentrant_reads(true);
+        resource.set_max_readers(1);
+
+        assert!(resource.try_set_reading(0));
+        // A vaga única está com a thread 0: outra thread espera, ela mesma não
+        assert!(!resource.try_set_reading(1));
+        assert!(resource.try_set_reading(0));
+        assert_eq!((rb.readers(0), resource.read_depth(0)), (1, 2));
+    }
+
+    #[test]
+    fn reentrant_reads_release_once_per_acquisition() {
+        let (_, rb) = setup(0, 1);
+        let resource = &rb.resources[0];
+        resource.set_reentrant_reads(true);
+        assert!(resource.try_set_reading(0));
+        assert!(resource.try_set_reading(0));
+        assert!(resource.try_set_reading(1));
+
+        resource.remove_reading(0);
+        assert_eq!(rb.readers(0), 2);
+        assert!(!resource.try_set_writing());
+        resource.remove_reading(0);
+        resource.remove_reading(1);
+        assert_eq!((rb.readers(0), resource.read_depth(0)), (0, 0));
+        assert!(resource.try_set_writing());
+    }
+
+    #[test]
+    fn concurrent_readers_keep_the_counters_consistent() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        let (_, rb) = setup(0, 1);
+        rb.set_max_readers(0, 3);
+        let peak = Arc::new(AtomicU32::new(0));
+        let handles: Vec<_> = (0..8)
+            .map(|t| {
+                let resource = rb.resources[0].clone();
+                let peak = Arc::clone(&peak);
+                std::thread::spawn(move || {
+                    for _ in 0..2_000 {
+                        if resource.try_set_reading(t) {
+                            let counts = resource.counts();
+                            peak.fetch_max(counts.read_count, Ordering::Relaxed);
+                            assert_eq!(counts.write_count, 0);
+                            resource.remove_reading(t);
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        let peak = peak.load(Ordering::Relaxed);
+        assert!((1..=3).contains(&peak), "pico de {} leitores", peak);
+        assert_eq!(rb.resources[0].counts(), LockCounts::default());
+    }
+
+    /// Roda o exemplo de inversão de prioridade até `until` e diz quando a Thread 3
+    /// (alta prioridade) começou a escrever.
+    fn high_priority_write_time(inheritance: bool, until: f64) -> Option<f64> {
+        let (mut vis, rb) = setup(3, 2);
+        vis.priority_inheritance = inheritance;
+        vis.load_scenario(Scenario::load("priority-inversion").unwrap(), 0.0);
+        let mut now = 0.0;
+        while now <= until {
+            vis.apply_scenario_step(now, &rb);
+            assert_consistent(&vis, &rb);
+            if vis.threads[2].state == ThreadState::Writing {
+                return Some(now);
+            }
+            now += 0.5;
+        }
+        None
+    }
+
+    #[test]
+    fn priority_inversion_delays_the_high_priority_thread() {
+        assert_eq!(high_priority_write_time(false, 20.0), Some(13.0));
+        assert_eq!(high_priority_write_time(true, 20.0), Some(7.0));
+    }
+
+    #[test]
+    fn opposite_order_dependencies_deadlock() {
+        let (mut vis, mut rb) = setup(2, 2);
+        // Thread 0 pega R2 e depois R1; thread 1 pega R1 e depois R2
+        rb.add_dependency(0, 1).unwrap();
+        rb.add_dependency(1, 0).unwrap();
+        assert_eq!(rb.acquisition_chain(0), vec![1, 0]);
+
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Writing, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Writing, Some(1), 0.0);
+        for step in 1..=5 {
+            let now = f64::from(step);
+            vis.set_thread_resource_state(&rb, 0, ThreadState::Writing, Some(0), now);
+            vis.set_thread_resource_state(&rb, 1, ThreadState::Writing, Some(1), now);
+        }
+
+        assert_eq!(vis.threads[0].state, ThreadState::Waiting);
+        assert_eq!(vis.threads[1].state, ThreadState::Waiting);
+        assert_eq!(vis.threads[0].held_dependencies, vec![(1, ThreadState::Writing)]);
+        assert_eq!(vis.threads[1].held_dependencies, vec![(0, ThreadState::Writing)]);
+        assert_eq!((vis.who_blocks(0), vis.who_blocks(1)), (vec![1], vec![0]));
+        assert_eq!(vis.deadlocked_threads(), vec![0, 1]);
+        assert_eq!((rb.writers(0), rb.writers(1)), (1, 1));
+        assert_consistent(&vis, &rb);
+
+        // Desistir devolve o pré-requisito e o outro lado consegue terminar
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Idle, None, 6.0);
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Writing, Some(1), 6.0);
+        assert_eq!(vis.threads[1].state, ThreadState::Writing);
+        assert_consistent(&vis, &rb);
+    }
+
+    #[test]
+    fn log_overflow_policies_keep_the_expected_entries() {
+        for (overflow, first_kept) in [(LogOverflow::DropOldest, 6.0), (LogOverflow::StopWhenFull, 0.0)] {
+            let (mut vis, rb) = setup(1, 1);
+            vis.set_log_overflow(overflow);
+            vis.set_log_capacity(4);
+            for step in 0..10 {
+                let state = if step % 2 == 0 { ThreadState::Reading } else { ThreadState::Idle };
+                let resource = (state == ThreadState::Reading).then_some(0);
+                vis.set_thread_resource_state(&rb, 0, state, resource, f64::from(step));
+            }
+            let times: Vec<f64> = vis.log.entries().map(|t| t.time).collect();
+            let expected: Vec<f64> = (0..4).map(|i| first_kept + f64::from(i)).collect();
+            assert_eq!(times, expected, "{:?}", overflow);
+            assert_eq!(vis.log.dropped(), 6);
+            assert_eq!(vis.to_json(&rb).matches("\"thread\": 0,").count(), 4);
+        }
+    }
+
+    #[test]
+    fn cooldown_keeps_recently_changed_threads_out_of_the_draw() {
+        let (mut vis, rb) = setup(2, 1);
+        vis.seed = Some(1);
+        vis.cooldown = 5.0;
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Reading, Some(0), 0.0);
+        assert!(vis.in_cooldown(0, 4.9));
+        assert!(!vis.in_cooldown(1, 0.0));
+
+        vis.log.clear();
+        vis.update_threads_randomly(&rb, 2.0);
+        assert!(vis.log.entries().all(|t| t.thread != 0));
+        assert_eq!(vis.threads[0].last_change_time, Some(0.0));
+        assert_eq!(vis.threads[0].state, ThreadState::Reading);
+        assert!(!vis.in_cooldown(0, 5.0));
+        assert_consistent(&vis, &rb);
+    }
+
+    #[test]
+    fn affinity_biases_the_resource_draw() {
+        let (mut vis, rb) = setup(1, 4);
+        vis.seed = Some(9);
+        vis.threads[0].affinity = Some(2);
+        let picks = |vis: &mut ThreadsVisualizer| {
+            (0..200)
+                .filter(|_| {
+                    vis.tick += 1;
+                    vis.pick_resource(&rb, 0) == Some(2)
+                })
+                .count()
+        };
+        vis.affinity_strength = 1.0;
+        assert_eq!(picks(&mut vis), 200);
+        // Com 70%, o preferido ainda sai bem mais que os 25% de um sorteio uniforme
+        vis.affinity_strength = 0.7;
+        assert!(picks(&mut vis) > 120);
+    }
+
+    #[test]
+    fn acquisition_latency_counts_from_the_first_failed_request() {
+        let (mut vis, rb) = setup(2, 1);
+        let clock = MockClock::new(0.0);
+        vis.clock = Arc::new(clock.clone());
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Writing, Some(0), clock.now());
+        clock.set(1.0);
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Reading, Some(0), clock.now());
+        clock.set(2.5);
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Reading, Some(0), clock.now());
+        clock.set(4.0);
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Idle, None, clock.now());
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Reading, Some(0), clock.now());
+
+        assert_eq!(vis.threads[0].state, ThreadState::Reading);
+        assert_eq!(vis.latency.worst, Some((0, 3.0)));
+        assert_eq!(vis.latency.average(), Some(1.5));
+        vis.reset(&rb);
+        assert_eq!(vis.latency.max(), None);
+    }
+
+    #[test]
+    fn expired_hold_releases_the_resource() {
+        let (mut vis, rb) = setup(2, 1);
+        let clock = MockClock::new(0.0);
+        vis.clock = Arc::new(clock.clone());
+        vis.set_write_hold_range(2.0, 2.0).unwrap();
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Writing, Some(0), clock.now());
+        assert_eq!(vis.threads[0].hold_until, Some(2.0));
+
+        clock.advance(1.5);
+        vis.release_expired_holds(&rb, clock.now());
+        assert_eq!(vis.threads[0].state, ThreadState::Writing);
+        assert_eq!(rb.resources[0].counts().write_count, 1);
+
+        clock.advance(0.5);
+        vis.release_expired_holds(&rb, clock.now());
+        assert_eq!(vis.threads[0].state, ThreadState::Idle);
+        assert_eq!(vis.threads[0].resource_in_use, None);
+        assert_eq!(vis.threads[0].hold_until, None);
+        assert_eq!(rb.resources[0].counts(), LockCounts::default());
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Writing, Some(0), clock.now());
+        assert_eq!(vis.threads[1].state, ThreadState::Writing);
+        assert_consistent(&vis, &rb);
+    }
+
+    #[test]
+    fn weighted_pick_follows_the_resource_weights() {
+        let (_, rb) = setup(1, 3);
+        for (idx, weight) in [1.0, 2.0, 7.0].into_iter().enumerate() {
+            rb.set_weight(idx, weight);
+        }
+        let mut rng = StdRng::seed_from_u64(42);
+        let draws = 20_000;
+        let mut hits = [0usize; 3];
+        for _ in 0..draws {
+            hits[rb.pick_weighted(&mut rng).unwrap()] += 1;
+        }
+        for (idx, expected) in [0.1, 0.2, 0.7].into_iter().enumerate() {
+            let frequency = hits[idx] as f64 / draws as f64;
+            assert!((frequency - expected).abs() < 0.02, "R{}: {} (esperado {})", idx + 1, frequency, expected);
+        }
+
+        // Peso zero nunca sai
+        rb.set_weight(0, 0.0);
+        assert!((0..1000).all(|_| rb.pick_weighted(&mut rng) != Some(0)));
+    }
+
+    #[test]
+    fn rolled_states_follow_the_state_weights() {
+        let mut vis = ThreadsVisualizer::new(1);
+        let weights = [4.0, 1.0, 3.0, 2.0];
+        vis.set_state_weights(weights).unwrap();
+        let mut rng = StdRng::seed_from_u64(7);
+        let draws = 20_000;
+        let mut hits = [0usize; 4];
+        for _ in 0..draws {
+            let state = vis.roll_state(&mut rng);
+            hits[RANDOM_STATES.iter().position(|&s| s == state).unwrap()] += 1;
+        }
+        let total: f32 = weights.iter().sum();
+        for (i, weight) in weights.into_iter().enumerate() {
+            let expected = (weight / total) as f64;
+            let frequency = hits[i] as f64 / draws as f64;
+            assert!((frequency - expected).abs() < 0.02, "{:?}: {} (esperado {})", RANDOM_STATES[i], frequency, expected);
+        }
+
+        vis.set_state_weights([0.0, 0.0, 1.0, 0.0]).unwrap();
+        assert!((0..1000).all(|_| vis.roll_state(&mut rng) == ThreadState::Reading));
+        assert!(vis.set_state_weights([0.0; 4]).is_err());
+    }
+
+    #[test]
+    fn force_release_frees_the_resource_and_its_holders() {
+        let (mut vis, mut rb) = setup(5, 3);
+        // Quem pede R3 precisa pegar R1 antes
+        rb.add_dependency(2, 0).unwrap();
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Reading, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Reading, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 2, ThreadState::Writing, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 3, ThreadState::Writing, Some(1), 0.0);
+        vis.set_thread_resource_state(&rb, 4, ThreadState::Reading, Some(2), 0.0);
+        vis.set_thread_resource_state(&rb, 4, ThreadState::Reading, Some(2), 0.0);
+        assert_eq!(vis.threads[2].state, ThreadState::Waiting);
+        assert_eq!(vis.threads[4].state, ThreadState::Reading);
+        assert_eq!(vis.threads[4].held_dependencies, vec![(0, ThreadState::Reading)]);
+        let logged = vis.log.len();
+
+        vis.force_release_resource(&rb, 0, 1.0);
+        assert_eq!(rb.resources[0].counts(), LockCounts::default());
+        assert!(rb.holders(0).is_empty());
+        // Quem segurava R1 como pré-requisito solta também o alvo
+        assert_eq!(rb.resources[2].counts(), LockCounts::default());
+        for i in [0, 1, 4] {
+            let thread = &vis.threads[i];
+            assert_eq!(thread.state, ThreadState::Idle);
+            assert_eq!(thread.resource_in_use, None);
+            assert_eq!(thread.hold_until, None);
+            assert!(thread.held_dependencies.is_empty());
+            assert_eq!(thread.idle_since, Some(1.0));
+            assert_eq!(thread.last_change_time, Some(1.0));
+        }
+        assert_eq!(vis.log.len(), logged + 3);
+        // Quem esperava continua esperando, e o outro recurso não é tocado
+        assert_eq!(vis.threads[2].state, ThreadState::Waiting);
+        assert_eq!(vis.threads[3].state, ThreadState::Writing);
+        assert_eq!(rb.resources[1].counts().write_count, 1);
+        assert_consistent(&vis, &rb);
+
+        vis.set_thread_resource_state(&rb, 2, ThreadState::Writing, Some(0), 2.0);
+        assert_eq!(vis.threads[2].state, ThreadState::Writing);
+        assert_consistent(&vis, &rb);
+    }
+
+    #[test]
+    fn from_states_counts_readers_and_blocks_the_writer() {
+        let (_, rb) = setup(0, 2);
+        let vis = ThreadsVisualizer::from_states(
+            vec![
+                (ThreadState::Reading, Some(0)),
+                (ThreadState::Reading, Some(0)),
+                (ThreadState::Writing, Some(0)),
+                (ThreadState::Idle, None),
+            ],
+            &rb,
+        );
+        let counts = rb.resources[0].counts();
+        assert_eq!((counts.read_count, counts.write_count), (2, 0));
+        assert_eq!(rb.resources[1].counts(), LockCounts::default());
+        assert_eq!(vis.threads[0].state, ThreadState::Reading);
+        assert_eq!(vis.threads[1].state, ThreadState::Reading);
+        assert_eq!(vis.threads[2].state, ThreadState::Waiting);
+        assert_eq!(vis.threads[2].resource_in_use, None);
+        assert_eq!(vis.threads[3].state, ThreadState::Idle);
+        // As posses criadas assim não expiram
+        assert!(vis.threads.iter().all(|t| t.hold_until.is_none()));
+        assert_consistent(&vis, &rb);
+    }
+
+    #[test]
+    fn resource_status_reports_idle_read_and_write() {
+        use crate::resource_box::ResourceStatus;
+
+        let (mut vis, rb) = setup(3, 1);
+        assert_eq!(rb.resources[0].state(), ResourceStatus::Idle);
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Reading, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Reading, Some(0), 0.0);
+        assert_eq!(rb.resources[0].state(), ResourceStatus::Reading(2));
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Idle, None, 0.0);
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Idle, None, 0.0);
+        assert_eq!(rb.resources[0].state(), ResourceStatus::Idle);
+        vis.set_thread_resource_state(&rb, 2, ThreadState::Writing, Some(0), 0.0);
+        assert_eq!(rb.resources[0].state(), ResourceStatus::Writing);
+    }
+
+    #[test]
+    fn shrinking_the_box_leaves_no_dead_index() {
+        let (mut vis, mut rb) = setup(4, 3);
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Reading, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Writing, Some(2), 0.0);
+        vis.set_thread_resource_state(&rb, 2, ThreadState::Reading, Some(2), 0.0);
+        vis.set_thread_resource_state(&rb, 3, ThreadState::Reading, Some(1), 0.0);
+        assert_eq!(vis.threads[2].state, ThreadState::Waiting);
+
+        rb.resources.truncate(1);
+        vis.drop_invalid_resources(&rb);
+        for thread in &vis.threads {
+            assert!(thread.resource_in_use.is_none_or(|r| r < 1), "{} ainda segura um recurso", thread.name);
+            assert!(thread.intended_resource.is_none_or(|r| r < 1));
+            assert!(thread.held_dependencies.iter().all(|&(r, _)| r < 1));
+        }
+        assert_eq!(vis.threads[0].state, ThreadState::Reading);
+        for i in 1..4 {
+            assert_eq!(vis.threads[i].state, ThreadState::Idle);
+        }
+        assert_consistent(&vis, &rb);
+    }
+
+    #[test]
+    fn higher_priority_waiter_jumps_the_queue() {
+        let (mut vis, rb) = setup(4, 1);
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Writing, Some(0), 0.0);
+        for (i, at) in [(1, 1.0), (2, 2.0), (3, 3.0)] {
+            vis.set_thread_resource_state(&rb, i, ThreadState::Writing, Some(0), at);
+        }
+        vis.threads[3].priority = 5;
+        // Quem espera vem antes, e entre eles a prioridade vence a antiguidade
+        assert_eq!(vis.service_order(vec![0, 1, 2, 3]), vec![3, 1, 2, 0]);
+
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Idle, None, 4.0);
+        for i in vis.service_order(vec![1, 2, 3]) {
+            vis.set_thread_resource_state(&rb, i, ThreadState::Writing, Some(0), 4.0);
+        }
+        assert_eq!(vis.threads[3].state, ThreadState::Writing);
+        assert_eq!(vis.threads[1].state, ThreadState::Waiting);
+        assert_eq!(vis.threads[2].state, ThreadState::Waiting);
+        assert_consistent(&vis, &rb);
+    }
+
+    #[test]
+    fn waiting_queue_is_published_to_the_resources() {
+        let (mut vis, rb) = setup(4, 2);
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Writing, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Writing, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 2, ThreadState::Reading, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 3, ThreadState::Reading, Some(1), 0.0);
+        assert_eq!(rb.resources[0].waiting(), (1, 1));
+        assert_eq!(rb.resources[1].waiting(), (0, 0));
+
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Idle, None, 1.0);
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Writing, Some(0), 1.0);
+        assert_eq!(rb.resources[0].waiting(), (1, 0));
+        vis.reset(&rb);
+        assert_eq!(rb.resources[0].waiting(), (0, 0));
+    }
+
+    #[test]
+    fn writer_preferring_holds_new_readers_behind_a_waiting_writer() {
+        use crate::policy::WriterPreferring;
+
+        let run = |rb: &ResourceBox| {
+            let mut vis = ThreadsVisualizer::new(3);
+            vis.clock = Arc::new(MockClock::new(0.0));
+            vis.set_thread_resource_state(rb, 0, ThreadState::Reading, Some(0), 0.0);
+            vis.set_thread_resource_state(rb, 1, ThreadState::Writing, Some(0), 0.0);
+            vis.set_thread_resource_state(rb, 2, ThreadState::Reading, Some(0), 1.0);
+            vis
+        };
+        // Com preferência de leitura o leitor novo entra; com a de escrita, espera
+        let (_, reader_box) = setup(0, 1);
+        assert_eq!(run(&reader_box).threads[2].state, ThreadState::Reading);
+
+        let rb = ResourceBox::new(Vec2::ZERO, 1, || Box::new(WriterPreferring));
+        let mut vis = run(&rb);
+        assert_eq!(vis.threads[1].state, ThreadState::Waiting);
+        assert_eq!(vis.threads[2].state, ThreadState::Waiting);
+        assert_eq!(rb.resources[0].counts().read_count, 1);
+
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Idle, None, 2.0);
+        for i in vis.service_order(vec![1, 2]) {
+            let mode = vis.threads[i].intended_mode.unwrap();
+            vis.set_thread_resource_state(&rb, i, mode, Some(0), 2.0);
+        }
+        assert_eq!(vis.threads[1].state, ThreadState::Writing);
+        assert_eq!(vis.threads[2].state, ThreadState::Waiting);
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Idle, None, 3.0);
+        vis.set_thread_resource_state(&rb, 2, ThreadState::Reading, Some(0), 3.0);
+        assert_eq!(vis.threads[2].state, ThreadState::Reading);
+        assert_consistent(&vis, &rb);
+    }
+
+    #[test]
+    fn blocked_writer_lists_both_readers() {
+        let (mut vis, rb) = setup(3, 1);
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Reading, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Reading, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 2, ThreadState::Writing, Some(0), 1.0);
+        assert_eq!(vis.threads[2].state, ThreadState::Waiting);
+
+        let refused = vis.log.last().expect("a recusa deveria estar no log");
+        assert_eq!(refused.thread, 2);
+        assert!(refused.blocked());
+        let mut blockers = refused.blockers();
+        blockers.sort();
+        assert_eq!(blockers, vec![0, 1]);
+        assert!(refused.blocked_by.iter().all(|&(_, state)| state == ThreadState::Reading));
+        assert_eq!(vis.who_blocks(2), vec![0, 1]);
+
+        // Com um leitor a menos, só o outro bloqueia
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Idle, None, 2.0);
+        assert_eq!(vis.who_blocks(2), vec![1]);
+    }
+
+    #[test]
+    fn removing_a_reading_thread_releases_its_read() {
+        let (mut vis, rb) = setup(3, 1);
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Reading, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 2, ThreadState::Reading, Some(0), 0.0);
+        vis.selected_thread = Some(2);
+        vis.hovered_thread = Some(2);
+        assert_eq!(rb.resources[0].counts().read_count, 2);
+
+        assert!(vis.remove_thread(&rb, 1.0));
+        assert_eq!(vis.threads.len(), 2);
+        assert_eq!(rb.resources[0].counts().read_count, 1);
+        assert_eq!(vis.selected_thread, None);
+        assert_eq!(vis.hovered_thread, None);
+        assert_consistent(&vis, &rb);
+
+        // Apontar para uma thread que fica não é mexido
+        vis.hovered_thread = Some(0);
+        assert!(vis.remove_thread(&rb, 2.0));
+        assert_eq!(rb.resources[0].counts(), LockCounts::default());
+        assert_eq!(vis.hovered_thread, Some(0));
+        assert!(!vis.remove_thread(&rb, 3.0));
+    }
+
+    #[test]
+    fn recorded_run_replays_to_the_same_state() {
+        use crate::simulation::step_simulation;
+
+        let path = std::env::temp_dir().join(format!("threads-replay-{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        // Estado de cada thread e de cada recurso depois de um passo
+        type Frame = (Vec<(ThreadState, Option<usize>)>, Vec<(LockCounts, bool)>);
+        let frame = |vis: &ThreadsVisualizer, rb: &ResourceBox| -> Frame {
+            let threads = vis.threads.iter().map(|t| (t.state, t.resource_in_use)).collect();
+            let resources = (0..rb.resources.len()).map(|i| (rb.resources[i].counts(), rb.is_frozen(i))).collect();
+            (threads, resources)
+        };
+        let ticks = 400;
+        let time = |tick: usize| tick as f64 * 0.1;
+
+        let (mut vis, rb) = setup(6, 3);
+        vis.seed = Some(9);
+        vis.record_to(path, &rb, 0.0).unwrap();
+        let mut recorded = Vec::new();
+        for tick in 1..=ticks {
+            let now = time(tick);
+            // As posses sorteadas expiram sozinhas dentro de `step_simulation`
+            step_simulation(&mut vis, &rb, now, tick % 5 == 0);
+            match tick {
+                100 => vis.set_resource_frozen(&rb, 1, true, now),
+                180 => vis.set_resource_frozen(&rb, 1, false, now),
+                250 => vis.force_release_resource(&rb, 0, now),
+                _ => {}
+            }
+            recorded.push(frame(&vis, &rb));
+        }
+        vis.recorder = None;
+        assert!(vis.throughput.total() > 20 && vis.failed_attempts > 0);
+
+        let (mut replayed, rb2) = setup(6, 3);
+        replayed.replay_from(path, &rb2, 0.0).unwrap();
+        for (tick, expected) in (1..=ticks).zip(&recorded) {
+            step_simulation(&mut replayed, &rb2, time(tick), tick % 5 == 0);
+            assert_eq!(&frame(&replayed, &rb2), expected, "passo {}", tick);
+        }
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(replayed.throughput.total(), vis.throughput.total());
+        assert_eq!(replayed.failed_attempts, vis.failed_attempts);
+        assert_consistent(&replayed, &rb2);
+    }
+
+    #[test]
+    fn idle_thread_is_nudged_after_the_timeout() {
+        let (mut vis, rb) = setup(2, 2);
+        let clock = MockClock::new(0.0);
+        vis.clock = Arc::new(clock.clone());
+        vis.seed = Some(1);
+        vis.idle_timeout = Some(5.0);
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Writing, Some(1), clock.now());
+
+        vis.nudge_idle_threads(&rb, clock.now());
+        clock.advance(4.9);
+        vis.nudge_idle_threads(&rb, clock.now());
+        assert_eq!(vis.threads[0].state, ThreadState::Idle);
+        assert_eq!(vis.threads[0].last_attempt, None);
+
+        clock.advance(0.1);
+        vis.nudge_idle_threads(&rb, clock.now());
+        assert_ne!(vis.threads[0].state, ThreadState::Idle);
+        assert!(matches!(vis.threads[0].last_attempt, Some((at, _)) if at == clock.now()));
+        // Quem não estava parado não é mexido
+        assert_eq!(vis.threads[1].state, ThreadState::Writing);
+        assert_eq!(vis.threads[1].resource_in_use, Some(1));
+        assert_consistent(&vis, &rb);
+    }
+
+    #[test]
+    fn reset_restarts_the_seeded_sequence() {
+        let (mut vis, rb) = setup(4, 2);
+        vis.seed = Some(11);
+        let run = |vis: &mut ThreadsVisualizer| {
+            (1..=30)
+                .map(|tick| {
+                    vis.update_threads_randomly(&rb, tick as f64);
+                    vis.threads.iter().map(|t| (t.state, t.resource_in_use)).collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+        let first = run(&mut vis);
+        vis.reset(&rb);
+        assert_eq!(vis.tick, 0);
+        assert_eq!(run(&mut vis), first);
+        assert_consistent(&vis, &rb);
+    }
+
+    #[test]
+    fn reset_clears_failures_and_hotspots() {
+        let (mut vis, rb) = setup(3, 1);
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Writing, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Reading, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 2, ThreadState::Writing, Some(0), 0.0);
+        rb.record_hold(0, 1.0);
+        assert_eq!(vis.failed_attempts, 2);
+        assert_eq!(rb.hotspot(0.0), Some(0));
+
+        vis.reset(&rb);
+        rb.reset_stats();
+        assert_eq!(vis.failed_attempts, 0);
+        assert_eq!(vis.throughput.total(), 0);
+        assert_eq!(rb.hotspot(0.0), None);
+        assert_eq!(rb.resources[0].contention(0.0), 0.0);
+        assert!(rb.hold_duration_histogram(0).iter().all(|&(_, n)| n == 0));
+        assert_consistent(&vis, &rb);
+    }
+
+    #[test]
+    fn chaos_always_recovers_what_it_breaks() {
+        let (mut vis, rb) = setup(4, 3);
+        vis.seed = Some(5);
+        let mut chaos = Chaos::new(0.5, 1.0, Some(5), 0.0);
+        let mut started = 0;
+        for step in 0..400 {
+            let now = step as f64 * 0.1;
+            if step % 20 == 0 {
+                vis.update_threads_randomly(&rb, now);
+            }
+            for change in chaos.update(&vis, &rb, now) {
+                started += matches!(change, ChaosChange::Start(_)) as usize;
+                change.apply(&mut vis, &rb, now);
+            }
+            assert_consistent(&vis, &rb);
+        }
+        assert!(started > 0);
+        for change in chaos.stop() {
+            change.apply(&mut vis, &rb, 40.0);
+        }
+        assert!(chaos.active.is_empty());
+        assert!((0..3).all(|idx| !rb.is_frozen(idx)));
+        assert!(vis.threads.iter().all(|t| t.state != ThreadState::Crashed));
+        assert_consistent(&vis, &rb);
+    }
+
+    #[test]
+    fn display_labels_match_what_draw_shows() {
+        let (mut vis, rb) = setup(3, 2);
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Writing, Some(1), 0.0);
+        vis.threads[1].state = ThreadState::Waiting;
+        vis.threads[2].state = ThreadState::Waiting;
+        vis.threads[2].intended_resource = Some(1);
+
+        assert_eq!(vis.threads[0].display_label(), "Thread 1 (R2)\nWriting");
+        assert_eq!(vis.threads[1].display_label(), "Thread 2\nWaiting");
+        assert_eq!(vis.threads[2].display_label(), "Thread 3 (-> R2)\nWaiting");
+        vis.threads[2].intended_mode = Some(ThreadState::Writing);
+        vis.demand_mode = true;
+        assert_eq!(vis.display_label(2), "Thread 3 (-> R2)\nBlocked");
+
+        rb.resources[1].set_name("Disk");
+        assert_eq!(rb.resources[0].display_label(0.0), "Resource 1\nState: Idle\nReaders: 0, Writers: 0\nBusy: 0%");
+        assert!(rb.resources[1]
+            .display_label(0.0)
+            .starts_with("Disk\nState: Writing\nReaders: 0, Writers: 1\n"));
+    }
+
+    #[test]
+    fn holders_keep_the_acquisition_order() {
+        let (mut vis, rb) = setup(3, 1);
+        for (i, now) in [(2, 0.0), (0, 1.0), (1, 2.0)] {
+            vis.set_thread_resource_state(&rb, i, ThreadState::Reading, Some(0), now);
+        }
+        assert_eq!(rb.holders(0), vec![(2, 0.0), (0, 1.0), (1, 2.0)]);
+
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Idle, None, 3.0);
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Reading, Some(0), 4.0);
+        assert_eq!(rb.holders(0), vec![(2, 0.0), (1, 2.0), (0, 4.0)]);
+        assert_consistent(&vis, &rb);
+
+        vis.force_release_resource(&rb, 0, 5.0);
+        assert!(rb.holders(0).is_empty());
+        assert_consistent(&vis, &rb);
+    }
+
+    #[test]
+    fn retry_backoff_grows_with_failures_and_resets_on_success() {
+        let (mut vis, rb) = setup(2, 1);
+        let clock = Arc::new(MockClock::new(0.0));
+        vis.clock = clock.clone();
+        vis.seed = Some(3);
+        vis.demand_mode = true;
+        vis.retry_backoff = 1.0;
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Writing, Some(0), 0.0);
+        vis.threads[0].hold_until = None;
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Writing, Some(0), 0.0);
+        assert_eq!(vis.threads[1].next_retry_time, Some(1.0));
+
+        // Cada tentativa só sai depois do recuo, que dobra a cada falha: 1, 2, 4 s
+        let mut retries = Vec::new();
+        for step in 1..=80 {
+            let now = step as f64 * 0.1;
+            clock.set(now);
+            let before = vis.threads[1].last_attempt;
+            vis.update_threads_randomly(&rb, now);
+            if vis.threads[1].last_attempt != before {
+                retries.push((now * 10.0).round() / 10.0);
+            }
+        }
+        assert_eq!(retries, vec![1.0, 3.0, 7.0]);
+        assert!(vis.is_backing_off(1, 7.5));
+        assert_eq!(vis.display_label(1), "Thread 2 (-> R1)\nBacking off");
+
+        assert_eq!(vis.threads[0].state, ThreadState::Writing);
+
+        // Com o recurso livre, a próxima tentativa consegue e o recuo zera
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Idle, None, 8.0);
+        vis.update_threads_randomly(&rb, 15.0);
+        assert_eq!(vis.threads[1].state, ThreadState::Writing);
+        assert_eq!((vis.threads[1].backoff, vis.threads[1].next_retry_time), (0.0, None));
+        assert_consistent(&vis, &rb);
+    }
+
+    #[test]
+    fn json_snapshot_lists_every_thread() {
+        let (mut vis, rb) = setup(4, 2);
+        vis.threads[3].name = "Quote \"q\" \\ tab\t".to_string();
+        vis.set_thread_resource_state(&rb, 0, ThreadState::Writing, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 1, ThreadState::Reading, Some(0), 0.0);
+        vis.set_thread_resource_state(&rb, 2, ThreadState::Reading, Some(1), 0.0);
+        vis.set_thread_resource_state(&rb, 3, ThreadState::Crashed, None, 0.0);
+
+        let json = vis.to_json(&rb);
+        assert!(json.starts_with("{\n  \"time\": 0,\n  \"threads\": [\n"));
+        assert!(json.ends_with("\"log_dropped\": 0\n}\n"));
+        // A thread 2 ficou esperando o escritor, sem recurso
+        for (thread, state, resource) in [(0, "Writing", "0"), (1, "Waiting", "null"), (2, "Reading", "1")] {
+            let line = format!(
+                "{{\"name\": \"{}\", \"state\": \"{}\", \"resource\": {},",
+                vis.threads[thread].name, state, resource
+            );
+            assert!(json.contains(&line), "falta {}", line);
+        }
+        // Aspas, barra e tab escapados; sem recurso vira null
+        assert!(json.contains(r#"{"name": "Quote \"q\" \\ tab\t", "state": "Crashed", "resource": null,"#));
+        assert_eq!(json.matches("\"state\": ").count(), vis.threads.len());
+        assert!(json.contains("\"readers\": 0, \"writers\": 1, \"x\": "));
+        assert!(json.contains("\"readers\": 1, \"writers\": 0, \"x\": "));
+    }
+
 }
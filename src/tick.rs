@@ -0,0 +1,120 @@
+use std::sync::Arc;
+
+use crate::clock::{Clock, MockClock};
+
+/// Nomes aceitos por `--tick`
+pub const TICK_MODES: &[&str] = &["real", "fixed", "manual"];
+
+/// O que faz o tempo da simulação andar.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TickMode {
+    /// O relógio de verdade (`get_time()`): um passo por frame, do tamanho do frame
+    RealTime,
+    /// Passos fixos de `1 / steps_per_second` segundos, `steps_per_frame` por frame.
+    /// A linha do tempo só depende de quantos frames passaram, não de quanto duraram;
+    /// com mais de um passo por frame a simulação corre mais rápido que o real.
+    FixedSteps { steps_per_second: f64, steps_per_frame: u32 },
+    /// Passos de `1 / steps_per_second` segundos só quando pedidos (`request_step`)
+    Manual { steps_per_second: f64 },
+}
+
+impl TickMode {
+    /// Modo pelo nome de `TICK_MODES`, com os passos por segundo e por frame dados.
+    pub fn from_name(name: &str, steps_per_second: f64, steps_per_frame: u32) -> Option<Self> {
+        match name {
+            "real" => Some(Self::RealTime),
+            "fixed" => Some(Self::FixedSteps {
+                steps_per_second,
+                steps_per_frame,
+            }),
+            "manual" => Some(Self::Manual { steps_per_second }),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::RealTime => "real",
+            Self::FixedSteps { .. } => "fixed",
+            Self::Manual { .. } => "manual",
+        }
+    }
+}
+
+/// Marca-passo do loop: diz, a cada frame, quantos passos da simulação rodar, em
+/// que instante e se é hora de sortear de novo (a cada `interval` segundos da
+/// simulação). Fora do tempo real, o próprio `TickDriver` é o relógio da simulação.
+#[derive(Debug)]
+pub struct TickDriver {
+    pub mode: TickMode,
+    /// Segundos (da simulação) entre dois sorteios
+    pub interval: f64,
+    last_tick: f64,
+    /// Relógio da simulação nos modos de passo fixo (None no tempo real)
+    clock: Option<MockClock>,
+    /// Passos pedidos no modo manual e ainda não rodados
+    pending_steps: u32,
+}
+
+impl TickDriver {
+    /// Driver no modo `mode`, sorteando a cada `interval` segundos; fora do tempo
+    /// real o relógio da simulação começa em `start`.
+    pub fn new(mode: TickMode, interval: f64, start: f64) -> Self {
+        let clock = (mode != TickMode::RealTime).then(|| MockClock::new(start));
+        Self {
+            mode,
+            interval,
+            last_tick: 0.0,
+            clock,
+            pending_steps: 0,
+        }
+    }
+
+    /// Relógio que a simulação (e o `ThreadsVisualizer`) deve usar, se não for o real.
+    pub fn clock(&self) -> Option<Arc<dyn Clock>> {
+        self.clock.clone().map(|clock| Arc::new(clock) as Arc<dyn Clock>)
+    }
+
+    /// Pede mais um passo no modo manual (nos outros modos não faz nada).
+    pub fn request_step(&mut self) {
+        if matches!(self.mode, TickMode::Manual { .. }) {
+            self.pending_steps += 1;
+        }
+    }
+
+    /// O próximo sorteio fica para `interval` segundos depois de `now`.
+    pub fn restart(&mut self, now: f64) {
+        self.last_tick = now;
+    }
+
+    /// Os passos deste frame, como (instante, é hora de sortear?). No tempo real é
+    /// sempre um passo em `now`; nos outros modos o relógio anda um passo por item.
+    pub fn steps(&mut self, now: f64) -> Vec<(f64, bool)> {
+        let (count, dt) = match self.mode {
+            TickMode::RealTime => return vec![(now, self.tick(now))],
+            TickMode::FixedSteps {
+                steps_per_second,
+                steps_per_frame,
+            } => (steps_per_frame, 1.0 / steps_per_second),
+            TickMode::Manual { steps_per_second } => (std::mem::take(&mut self.pending_steps), 1.0 / steps_per_second),
+        };
+        let Some(clock) = self.clock.clone() else {
+            return Vec::new();
+        };
+        (0..count)
+            .map(|_| {
+                clock.advance(dt);
+                let now = clock.now();
+                (now, self.tick(now))
+            })
+            .collect()
+    }
+
+    fn tick(&mut self, now: f64) -> bool {
+        let due = now - self.last_tick >= self.interval;
+        if due {
+            self.last_tick = now;
+        }
+        due
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::VecDeque;
+
+use macroquad::prelude::*;
+
+use crate::resource_box::{ResourceBox, ResourceStatus};
+use crate::text::draw_label;
+use crate::theme::Theme;
+
+/// Histórico da ocupação dos recursos: uma linha por recurso e uma amostra
+/// a cada `interval` segundos. As amostras mais antigas saem pela esquerda.
+pub struct Timeline {
+    /// Intervalo entre amostras, em segundos
+    pub interval: f64,
+    /// Amostras guardadas por recurso
+    pub capacity: usize,
+    rows: Vec<VecDeque<ResourceStatus>>,
+    last_sample: Option<f64>,
+}
+
+impl Timeline {
+    pub fn new(interval: f64, capacity: usize) -> Self {
+        Self {
+            interval,
+            capacity: capacity.max(1),
+            rows: Vec::new(),
+            last_sample: None,
+        }
+    }
+
+    /// Guarda o estado de cada recurso se já passou `interval` desde a última amostra.
+    pub fn record(&mut self, resource_box: &ResourceBox, now: f64) {
+        if matches!(self.last_sample, Some(last) if now - last < self.interval) {
+            return;
+        }
+        self.last_sample = Some(now);
+        self.rows.resize_with(resource_box.resources.len(), VecDeque::new);
+        for (row, resource) in self.rows.iter_mut().zip(&resource_box.resources) {
+            if row.len() == self.capacity {
+                row.pop_front();
+            }
+            row.push_back(resource.state());
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.rows.clear();
+        self.last_sample = None;
+    }
+
+    /// Desenha a faixa no pé de `area` (o canto inferior direito, no `Layout`), com as
+    /// linhas encolhendo se não couberem. Amostras vizinhas iguais viram um só
+    /// retângulo, então o custo depende das mudanças, não da capacidade.
+    pub fn draw(&self, theme: &Theme, area: Rect) {
+        if self.rows.is_empty() {
+            return;
+        }
+        let row_gap = 2.0;
+        let label_width = 36.0;
+        let rows = self.rows.len() as f32;
+        let row_height = ((area.h + row_gap) / rows - row_gap).clamp(1.0, 8.0);
+        let width = (area.w - label_width).max(0.0);
+        let sample_width = width / self.capacity as f32;
+        let height = rows * (row_height + row_gap) - row_gap;
+        let x = area.x + label_width;
+        let top = area.bottom() - height;
+
+        draw_rectangle_lines(x - 1.0, top - 1.0, width + 2.0, height + 2.0, 1.0, theme.border);
+        for (idx, row) in self.rows.iter().enumerate() {
+            let y = top + idx as f32 * (row_height + row_gap);
+            draw_label(
+                &format!("R{}", idx + 1),
+                x - label_width,
+                y + row_height,
+                14.0,
+                theme.text,
+            );
+
+            // Alinhadas à direita: a amostra mais nova fica sempre na borda direita
+            let start_x = x + width - row.len() as f32 * sample_width;
+            let mut run_start = 0;
+            for i in 1..=row.len() {
+                if i == row.len() || row[i] != row[run_start] {
+                    let color = match row[run_start] {
+                        ResourceStatus::Writing => theme.resource_writing,
+                        ResourceStatus::Reading(_) => theme.resource_reading,
+                        ResourceStatus::Idle => theme.resource_idle,
+                    };
+                    draw_rectangle(
+                        start_x + run_start as f32 * sample_width,
+                        y,
+                        (i - run_start) as f32 * sample_width,
+                        row_height,
+                        color,
+                    );
+                    run_start = i;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use macroquad::prelude::*;
+
+use crate::logger::{self, Level};
+use crate::resource_box::ResourceBox;
+use crate::scenario::Scenario;
+use crate::text::draw_wrapped_text;
+use crate::theme::Theme;
+use crate::threads::ThreadsVisualizer;
+
+/// Uma etapa do tour: a legenda, quanto tempo ela fica na tela e o roteiro
+/// (no formato de `Scenario`, com tempos contados do início da etapa).
+pub struct TourStep {
+    pub caption: &'static str,
+    pub duration: f64,
+    pub script: &'static str,
+}
+
+/// Etapas do tour, na ordem. Usam as threads 1 a 5 e os recursos 1 e 2.
+pub const TOUR_STEPS: &[TourStep] = &[
+    TourStep {
+        caption: "Thread 1 asks to read Resource 1. Nobody holds it, so the read starts right away.",
+        duration: 5.0,
+        script: "0.5 thread 1 read 1",
+    },
+    TourStep {
+        caption: "Readers share: Thread 2 and Thread 3 read Resource 1 at the same time as Thread 1.",
+        duration: 6.0,
+        script: "0.5 thread 2 read 1\n1.5 thread 3 read 1",
+    },
+    TourStep {
+        caption: "Thread 4 wants to write Resource 1, but a writer needs it empty, so it has to wait.",
+        duration: 6.0,
+        script: "0.5 thread 4 write 1",
+    },
+    TourStep {
+        caption: "The readers leave one by one. Only after the last one does Thread 4 get to write.",
+        duration: 7.0,
+        script: "1 thread 1 release\n2 thread 2 release\n3 thread 3 release\n4 thread 4 write 1",
+    },
+    TourStep {
+        caption: "A deadlock needs a thread holding one lock while asking for another (--depends, \
+                  off here). Its cousin is starvation: readers keep overlapping on Resource 2, so \
+                  Thread 5's write never gets its turn.",
+        duration: 10.0,
+        script: "0 thread 4 release\n0.5 thread 1 read 2\n1 thread 5 write 2\n2 thread 2 read 2\n\
+                 3 thread 1 release\n4 thread 3 read 2\n5 thread 2 release\n6 thread 1 read 2\n\
+                 7 thread 3 release",
+    },
+    TourStep {
+        caption: "That's the tour! The simulation goes back to random mode now.",
+        duration: 4.0,
+        script: "0 thread 1 release\n0 thread 5 release",
+    },
+];
+
+/// Menor cena em que o tour faz sentido
+const TOUR_THREADS: usize = 5;
+const TOUR_RESOURCES: usize = 2;
+
+/// Tour guiado: passa pelas `TOUR_STEPS`, carregando o roteiro de cada etapa
+/// no `ThreadsVisualizer` e trocando de etapa pelo relógio.
+#[derive(Debug)]
+pub struct Tour {
+    step: usize,
+    step_started: f64,
+    /// Instante em que o tour foi pausado (o relógio da etapa fica parado)
+    paused_at: Option<f64>,
+}
+
+impl Tour {
+    /// Zera a simulação e começa pela primeira etapa.
+    pub fn start(threads_vis: &mut ThreadsVisualizer, resource_box: &ResourceBox, now: f64) -> Result<Self, String> {
+        if threads_vis.threads.len() < TOUR_THREADS || resource_box.resources.len() < TOUR_RESOURCES {
+            return Err(format!(
+                "o tour precisa de pelo menos {} threads e {} recursos",
+                TOUR_THREADS, TOUR_RESOURCES
+            ));
+        }
+        threads_vis.reset(resource_box);
+        let tour = Self {
+            step: 0,
+            step_started: now,
+            paused_at: None,
+        };
+        tour.load_step(threads_vis, now);
+        Ok(tour)
+    }
+
+    /// Passa para a próxima etapa quando a atual acaba. Retorna `false` depois da última.
+    pub fn update(&mut self, threads_vis: &mut ThreadsVisualizer, now: f64) -> bool {
+        if self.paused_at.is_some() {
+            return true;
+        }
+        if now - self.step_started < TOUR_STEPS[self.step].duration {
+            return true;
+        }
+        self.step += 1;
+        if self.step >= TOUR_STEPS.len() {
+            return false;
+        }
+        self.step_started = now;
+        self.load_step(threads_vis, now);
+        true
+    }
+
+    /// Para (ou volta a andar) o relógio da etapa e o do roteiro junto.
+    pub fn set_paused(&mut self, paused: bool, threads_vis: &mut ThreadsVisualizer, now: f64) {
+        match (paused, self.paused_at) {
+            (true, None) => self.paused_at = Some(now),
+            (false, Some(at)) => {
+                self.step_started += now - at;
+                threads_vis.scenario_start += now - at;
+                self.paused_at = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Sai do tour: some o roteiro e a simulação volta ao sorteio, do zero.
+    pub fn stop(self, threads_vis: &mut ThreadsVisualizer, resource_box: &ResourceBox) {
+        threads_vis.scenario = None;
+        threads_vis.reset(resource_box);
+    }
+
+    fn load_step(&self, threads_vis: &mut ThreadsVisualizer, now: f64) {
+        match Scenario::parse(TOUR_STEPS[self.step].script) {
+            Ok(scenario) => threads_vis.load_scenario(scenario, now),
+            Err(err) => logger::log(Level::Error, || format!("erro no roteiro do tour, etapa {}: {}", self.step + 1, err)),
+        }
+    }
+
+    /// Legenda da etapa numa faixa no topo da tela, com a barra de tempo da etapa.
+    pub fn draw(&self, theme: &Theme, now: f64) {
+        let step = &TOUR_STEPS[self.step];
+        let width = screen_width() * 0.6;
+        let height = 110.0;
+        let x = (screen_width() - width) * 0.5;
+        let y = 10.0;
+        draw_rectangle(x, y, width, height, theme.text_background);
+        draw_rectangle_lines(x, y, width, height, 2.0, theme.highlight);
+
+        let status = if self.paused_at.is_some() { "paused, P resumes" } else { "P pauses" };
+        let header = format!(
+            "Tour {}/{}  ({}, O exits)",
+            self.step + 1,
+            TOUR_STEPS.len(),
+            status
+        );
+        draw_wrapped_text(&header, x + 10.0, y + 6.0, width - 20.0, 20.0, 18.0, theme.highlight);
+        draw_wrapped_text(step.caption, x + 10.0, y + 30.0, width - 20.0, height - 44.0, 22.0, theme.text);
+
+        let elapsed = self.paused_at.unwrap_or(now) - self.step_started;
+        let progress = (elapsed / step.duration).clamp(0.0, 1.0) as f32;
+        draw_rectangle(x, y + height - 4.0, width * progress, 4.0, theme.highlight);
+    }
+}
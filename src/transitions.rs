@@ -0,0 +1,209 @@
+use std::collections::VecDeque;
+
+use crate::resource_box::ResourceBox;
+use crate::threads::{ThreadInfo, ThreadState};
+
+/// Uma mudança de estado de thread, com o motivo quando a aquisição falhou.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Transition {
+    pub time: f64,
+    pub thread: usize,
+    pub from: ThreadState,
+    pub to: ThreadState,
+    /// Recurso que a thread segurava antes da mudança
+    pub released: Option<usize>,
+    /// Recurso pedido e o modo (Reading/Writing), se houve tentativa de aquisição
+    pub requested: Option<(usize, ThreadState)>,
+    /// Quem segurava o recurso pedido no momento da decisão (índice e modo)
+    pub blocked_by: Vec<(usize, ThreadState)>,
+}
+
+impl Transition {
+    /// A tentativa de aquisição falhou?
+    pub fn blocked(&self) -> bool {
+        self.requested.is_some() && self.to == ThreadState::Waiting
+    }
+
+    /// Só os índices de quem bloqueou a aquisição (ver `blocked_by`).
+    pub fn blockers(&self) -> Vec<usize> {
+        self.blocked_by.iter().map(|&(i, _)| i).collect()
+    }
+
+    /// Frase em linguagem simples sobre o que aconteceu, por exemplo:
+    /// "Thread 4 tried to write Resource 2 but Thread 1 is reading it, so Thread 4 is now waiting."
+    pub fn explain(&self, threads: &[ThreadInfo], resource_box: &ResourceBox) -> String {
+        let thread_name = |i: usize| {
+            threads
+                .get(i)
+                .map_or_else(|| format!("Thread {}", i + 1), |t| t.name.clone())
+        };
+        let resource_name = |i: usize| {
+            resource_box
+                .resources
+                .get(i)
+                .map_or_else(|| format!("Resource {}", i + 1), |r| r.name())
+        };
+        let name = thread_name(self.thread);
+
+        if let Some((res_idx, mode)) = self.requested {
+            let verb = if mode == ThreadState::Writing { "write" } else { "read" };
+            if self.blocked() {
+                let reason = if self.blocked_by.is_empty() {
+                    "the lock policy did not allow it".to_string()
+                } else {
+                    let holders: Vec<String> =
+                        self.blocked_by.iter().map(|&(i, _)| thread_name(i)).collect();
+                    let writer_holds = self
+                        .blocked_by
+                        .iter()
+                        .any(|&(_, state)| state == ThreadState::Writing);
+                    let holder_verb = if writer_holds { "writing" } else { "reading" };
+                    let is_are = if holders.len() == 1 { "is" } else { "are" };
+                    format!("{} {} {} it", join_names(&holders), is_are, holder_verb)
+                };
+                return format!(
+                    "{} tried to {} {} but {}, so {} is now waiting.",
+                    name,
+                    verb,
+                    resource_name(res_idx),
+                    reason,
+                    name
+                );
+            }
+            let started = if mode == ThreadState::Writing { "writing" } else { "reading" };
+            return format!("{} started {} {}.", name, started, resource_name(res_idx));
+        }
+
+        let now_state = match self.to {
+            ThreadState::Reading => "reading",
+            ThreadState::Writing => "writing",
+            ThreadState::Waiting => "waiting",
+            ThreadState::Idle => "idle",
+            ThreadState::Crashed => "crashed",
+        };
+        match (self.released, self.to) {
+            (Some(res_idx), ThreadState::Crashed) => {
+                format!("{} crashed and released {}.", name, resource_name(res_idx))
+            }
+            (Some(res_idx), _) => format!(
+                "{} released {} and is now {}.",
+                name,
+                resource_name(res_idx),
+                now_state
+            ),
+            (None, ThreadState::Crashed) => format!("{} crashed.", name),
+            (None, _) => format!("{} is now {}.", name, now_state),
+        }
+    }
+}
+
+/// "A", "A and B", "A, B and C"
+fn join_names(names: &[String]) -> String {
+    match names {
+        [] => String::new(),
+        [one] => one.clone(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+/// O que o `TransitionLog` faz com uma transição nova quando já está cheio.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogOverflow {
+    /// Anel: a mais antiga sai para a nova entrar
+    DropOldest,
+    /// Guarda as primeiras `capacity` e ignora o resto
+    StopWhenFull,
+}
+
+/// Transições guardadas quando ninguém muda a capacidade
+pub const DEFAULT_LOG_CAPACITY: usize = 1000;
+
+/// Nomes aceitos por `--log-overflow`
+pub const LOG_OVERFLOWS: &[&str] = &["drop-oldest", "stop"];
+
+impl LogOverflow {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::DropOldest => "drop-oldest",
+            Self::StopWhenFull => "stop",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::DropOldest, Self::StopWhenFull].into_iter().find(|p| p.name() == name)
+    }
+}
+
+/// Registro das transições, com no máximo `capacity` entradas (o que sai ou deixa
+/// de entrar depois disso vem de `overflow`).
+#[derive(Clone, Debug)]
+pub struct TransitionLog {
+    pub capacity: usize,
+    pub overflow: LogOverflow,
+    entries: VecDeque<Transition>,
+    /// Transições descartadas (ou não guardadas) por falta de espaço
+    dropped: u64,
+}
+
+impl TransitionLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            overflow: LogOverflow::DropOldest,
+            entries: VecDeque::new(),
+            dropped: 0,
+        }
+    }
+
+    pub fn push(&mut self, transition: Transition) {
+        if self.entries.len() >= self.capacity && self.overflow == LogOverflow::StopWhenFull {
+            self.dropped += 1;
+            return;
+        }
+        self.entries.push_back(transition);
+        self.trim();
+    }
+
+    /// Muda a capacidade; se o que já está guardado não couber, sai conforme `overflow`
+    /// (as mais antigas no anel, as mais novas parando quando cheio).
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.entries.len() > self.capacity {
+            match self.overflow {
+                LogOverflow::DropOldest => self.entries.pop_front(),
+                LogOverflow::StopWhenFull => self.entries.pop_back(),
+            };
+            self.dropped += 1;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Quantas transições ficaram de fora por falta de espaço desde o último `clear`.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    pub fn last(&self) -> Option<&Transition> {
+        self.entries.back()
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &Transition> {
+        self.entries.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.dropped = 0;
+    }
+}
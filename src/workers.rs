@@ -0,0 +1,212 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use ::rand::random_range;
+
+use crate::logger::{self, Level};
+use crate::resource_box::{Resource, ResourceBox};
+use crate::threads::{sample_hold, ThreadInfo, ThreadState};
+
+/// Intervalo entre tentativas de uma thread bloqueada
+const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Maior pedaço de sono de uma vez, para a parada ser atendida rápido
+const SLEEP_SLICE: Duration = Duration::from_millis(50);
+
+/// Threads de verdade (`std::thread`) disputando os recursos.
+///
+/// Cada worker fica em loop: sorteia um recurso e um modo, tenta até conseguir,
+/// segura por um tempo, libera e descansa. O estado de cada um é publicado em
+/// `shared`, que o loop de desenho copia a cada frame.
+pub struct Workers {
+    pub shared: Arc<Mutex<Vec<ThreadInfo>>>,
+    stop: Arc<AtomicBool>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+/// Parâmetros de tempo dos workers
+#[derive(Clone, Copy)]
+pub struct WorkerTiming {
+    pub read_hold_range: (f64, f64),
+    pub write_hold_range: (f64, f64),
+    /// Diferença entre o relógio do sistema e o relógio do `ThreadsVisualizer`,
+    /// para os instantes publicados baterem com os usados no `draw`.
+    pub time_base: f64,
+}
+
+impl Workers {
+    /// Cria um worker para cada thread de `threads`.
+    pub fn spawn(resource_box: &ResourceBox, threads: Vec<ThreadInfo>, timing: WorkerTiming) -> Self {
+        let shared = Arc::new(Mutex::new(threads));
+        let stop = Arc::new(AtomicBool::new(false));
+        let count = lock(&shared).len();
+
+        let handles = (0..count)
+            .map(|index| {
+                let resources = resource_box.resources.clone();
+                let shared = Arc::clone(&shared);
+                let stop = Arc::clone(&stop);
+                thread::spawn(move || worker_loop(index, resources, shared, stop, timing))
+            })
+            .collect();
+
+        Self {
+            shared,
+            stop,
+            handles,
+        }
+    }
+
+    /// Cópia do estado atual de todas as threads.
+    pub fn snapshot(&self) -> Vec<ThreadInfo> {
+        lock(&self.shared).clone()
+    }
+
+    /// Pede para os workers pararem e espera todos terminarem
+    /// (cada um libera o recurso que estiver segurando antes de sair).
+    /// Retorna o estado final das threads, já sem nenhum recurso em uso.
+    pub fn stop(self) -> Vec<ThreadInfo> {
+        self.stop.store(true, Ordering::Relaxed);
+        for handle in self.handles {
+            if handle.join().is_err() {
+                logger::log(Level::Error, || "um worker terminou em pânico".to_string());
+            }
+        }
+        lock(&self.shared).clone()
+    }
+}
+
+/// Tempo do sistema em segundos
+pub fn system_time() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+fn lock(shared: &Mutex<Vec<ThreadInfo>>) -> MutexGuard<'_, Vec<ThreadInfo>> {
+    shared.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn worker_loop(
+    index: usize,
+    resources: Vec<Resource>,
+    shared: Arc<Mutex<Vec<ThreadInfo>>>,
+    stop: Arc<AtomicBool>,
+    timing: WorkerTiming,
+) {
+    if resources.is_empty() {
+        return;
+    }
+    let now = || system_time() - timing.time_base;
+    let publish = |update: &dyn Fn(&mut ThreadInfo)| {
+        if let Some(info) = lock(&shared).get_mut(index) {
+            update(info);
+        }
+    };
+
+    while !stop.load(Ordering::Relaxed) {
+        let res_idx = random_range(0..resources.len());
+        let writing = random_range(0..2) == 1;
+        let resource = &resources[res_idx];
+
+        // Tenta até conseguir (ou até mandarem parar)
+        let wait_start = now();
+        publish(&|info| {
+            info.waiting_since = Some(wait_start);
+            info.state = ThreadState::Waiting;
+            info.resource_in_use = None;
+            info.intended_resource = Some(res_idx);
+            info.intended_mode = Some(if writing {
+                ThreadState::Writing
+            } else {
+                ThreadState::Reading
+            });
+        });
+        let mut first_attempt = true;
+        // Na fila do recurso enquanto não consegue (a política pode levá-la em conta)
+        resource.update_waiting(writing, true);
+        loop {
+            let ok = if writing {
+                resource.try_set_writing()
+            } else {
+                resource.try_set_reading(index)
+            };
+            // Só a primeira falha conta como contenção (as novas tentativas são a mesma espera)
+            if first_attempt || ok {
+                let t = now();
+                publish(&|info| info.last_attempt = Some((t, ok)));
+                if !ok {
+                    resource.record_failure(t);
+                }
+                first_attempt = false;
+            }
+            if ok {
+                resource.update_waiting(writing, false);
+                break;
+            }
+            if stop.load(Ordering::Relaxed) {
+                resource.update_waiting(writing, false);
+                return;
+            }
+            thread::sleep(RETRY_INTERVAL);
+        }
+
+        // Segura o recurso
+        let hold = sample_hold(
+            if writing {
+                timing.write_hold_range
+            } else {
+                timing.read_hold_range
+            },
+            &mut ::rand::rng(),
+        );
+        let start = now();
+        if writing {
+            resource.set_write_hold(start, Some(start + hold));
+        }
+        resource.add_holder(index, start);
+        publish(&|info| {
+            info.state = if writing {
+                ThreadState::Writing
+            } else {
+                ThreadState::Reading
+            };
+            info.resource_in_use = Some(res_idx);
+            info.waiting_since = None;
+            info.hold_started = Some(start);
+            info.hold_until = Some(start + hold);
+        });
+        sleep_unless_stopped(hold, &stop);
+
+        // Libera e descansa (a posse pode ter acabado antes, se o worker foi parado)
+        resource.record_hold(now() - start);
+        resource.remove_holder(index);
+        if writing {
+            resource.remove_writing();
+        } else {
+            resource.remove_reading(index);
+        }
+        publish(&|info| {
+            info.state = ThreadState::Idle;
+            info.resource_in_use = None;
+            info.intended_resource = None;
+            info.intended_mode = None;
+            info.hold_started = None;
+            info.hold_until = None;
+        });
+        sleep_unless_stopped(random_range(0.5..2.0), &stop);
+    }
+}
+
+/// Dorme `seconds`, acordando antes se a parada for pedida.
+fn sleep_unless_stopped(seconds: f64, stop: &AtomicBool) {
+    let mut remaining = Duration::from_secs_f64(seconds.max(0.0));
+    while !remaining.is_zero() && !stop.load(Ordering::Relaxed) {
+        let slice = remaining.min(SLEEP_SLICE);
+        thread::sleep(slice);
+        remaining -= slice;
+    }
+}